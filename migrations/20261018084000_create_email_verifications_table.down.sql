@@ -0,0 +1,4 @@
+-- Add migration script here
+DROP TABLE IF EXISTS email_verifications;
+
+ALTER TABLE users DROP COLUMN IF EXISTS email_verified_at;
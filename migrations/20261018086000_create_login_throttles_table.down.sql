@@ -0,0 +1,4 @@
+-- Add migration script here
+DROP TABLE IF EXISTS login_throttles;
+
+DELETE FROM permissions WHERE name IN ('security:read', 'security:write');
@@ -0,0 +1,21 @@
+-- Add migration script here
+-- Failed login counters, one row per account identifier and per client IP.
+CREATE TABLE IF NOT EXISTS login_throttles (
+    kind VARCHAR(16) NOT NULL,
+    key VARCHAR(255) NOT NULL,
+    failures INTEGER NOT NULL DEFAULT 0,
+    last_failure_at TIMESTAMP NOT NULL,
+    locked_until TIMESTAMP,
+    PRIMARY KEY (kind, key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_throttles_last_failure_at ON login_throttles (last_failure_at);
+
+INSERT INTO permissions (name, description) VALUES
+    ('security:read', 'View login lockouts'),
+    ('security:write', 'Clear login lockouts')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id FROM roles r JOIN permissions p ON p.name IN ('security:read', 'security:write') WHERE r.name = 'admin'
+ON CONFLICT DO NOTHING;
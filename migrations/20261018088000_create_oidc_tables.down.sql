@@ -0,0 +1,3 @@
+-- Add migration script here
+DROP TABLE IF EXISTS user_identities;
+DROP TABLE IF EXISTS oidc_states;
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE users DROP COLUMN IF EXISTS name;
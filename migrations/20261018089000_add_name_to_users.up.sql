@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE users ADD COLUMN IF NOT EXISTS name VARCHAR(100);
\ No newline at end of file
@@ -0,0 +1,6 @@
+-- Add migration script here
+-- The append-only trigger also blocks TRUNCATE, it goes with the table.
+DROP TABLE IF EXISTS audit_events;
+DROP FUNCTION IF EXISTS audit_events_append_only();
+
+DELETE FROM permissions WHERE name = 'audit:read';
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE items DROP COLUMN IF EXISTS version;
+ALTER TABLE users DROP COLUMN IF EXISTS version;
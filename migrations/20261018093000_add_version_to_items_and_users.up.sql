@@ -0,0 +1,4 @@
+-- Add migration script here
+-- Bumped on every write, sent as the ETag and checked against If-Match.
+ALTER TABLE items ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,13 @@
+-- Add migration script here
+DROP TRIGGER IF EXISTS items_set_updated_at ON items;
+DROP TRIGGER IF EXISTS users_set_updated_at ON users;
+DROP FUNCTION IF EXISTS set_updated_at();
+
+ALTER TABLE items DROP COLUMN IF EXISTS created_at;
+ALTER TABLE items DROP COLUMN IF EXISTS updated_at;
+ALTER TABLE items DROP COLUMN IF EXISTS created_by;
+ALTER TABLE items DROP COLUMN IF EXISTS updated_by;
+ALTER TABLE users ALTER COLUMN created_at DROP NOT NULL;
+ALTER TABLE users DROP COLUMN IF EXISTS updated_at;
+ALTER TABLE users DROP COLUMN IF EXISTS created_by;
+ALTER TABLE users DROP COLUMN IF EXISTS updated_by;
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE email_verifications DROP COLUMN IF EXISTS email;
+ALTER TABLE users DROP COLUMN IF EXISTS pending_email;
\ No newline at end of file
@@ -0,0 +1,6 @@
+-- Add migration script here
+-- A changed email address waits in pending_email until it is verified, the
+-- current one keeps working meanwhile. Verification links remember the address
+-- they were sent to, NULL for links sent before this column existed.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS pending_email VARCHAR(50);
+ALTER TABLE email_verifications ADD COLUMN IF NOT EXISTS email VARCHAR(50);
\ No newline at end of file
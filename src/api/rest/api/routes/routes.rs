@@ -1,31 +1,71 @@
+use crate::internal::application::controllers::admin::{audit, lockouts};
 use crate::internal::application::controllers::items::items;
 use crate::internal::application::controllers::users::users;
-use crate::internal::application::controllers::auth::login;
-use crate::middlewares::jwt::JwtMiddleware;
+use crate::internal::application::controllers::auth::{api_keys, jwks, login, logout, mfa, oidc, password, profile, refresh, registration};
+use crate::config::settings::CONFIG;
+use crate::middlewares::policy::{AuthPolicy, Policy};
 use actix_web::web;
 
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    let items_read_policy: AuthPolicy = CONFIG.items_read_policy.parse().expect("Invalid ITEMS_READ_POLICY");
+
     cfg.service(
         web::scope("/items")
-            .route("", web::post().to(items::create_item_controller))
-            .route("", web::get().to(items::get_items_controller))
-            .route("/{id}", web::get().to(items::get_item_controller))
-            .route("/{id}", web::put().to(items::update_item_controller))
-            .route("/{id}", web::delete().to(items::delete_item_controller))
+            .route("", web::post().to(items::create_item_controller).wrap(Policy::permission("items:write")))
+            .route("", web::get().to(items::get_items_controller).wrap(Policy::new(items_read_policy.clone())))
+            .route("/trash", web::get().to(items::get_trashed_items_controller).wrap(Policy::permission("items:delete")))
+            .route("/{id}", web::get().to(items::get_item_controller).wrap(Policy::new(items_read_policy)))
+            .route("/{id}", web::put().to(items::update_item_controller).wrap(Policy::permission("items:write")))
+            .route("/{id}", web::patch().to(items::update_item_controller).wrap(Policy::permission("items:write")))
+            .route("/{id}", web::delete().to(items::delete_item_controller).wrap(Policy::permission("items:delete")))
+            .route("/{id}/restore", web::post().to(items::restore_item_controller).wrap(Policy::permission("items:delete")))
     );
 
     cfg.service(
         web::scope("/users")
-            .wrap(JwtMiddleware)
-            .route("", web::post().to(users::create_user_controller))
-            .route("", web::get().to(users::get_users_controller))
-            .route("/{id}", web::get().to(users::get_user_controller))
-            .route("/{id}", web::put().to(users::update_user_controller))
-            .route("/{id}", web::delete().to(users::delete_user_controller))
+            .wrap(Policy::authenticated())
+            .route("", web::post().to(users::create_user_controller).wrap(Policy::permission("users:write")))
+            .route("", web::get().to(users::get_users_controller).wrap(Policy::permission("users:read")))
+            .route("/trash", web::get().to(users::get_trashed_users_controller).wrap(Policy::permission("users:delete")))
+            .route("/{id}", web::get().to(users::get_user_controller).wrap(Policy::permission("users:read")))
+            .route("/{id}", web::put().to(users::update_user_controller).wrap(Policy::permission("users:write")))
+            .route("/{id}", web::patch().to(users::patch_user_controller).wrap(Policy::permission("users:write")))
+            .route("/{id}", web::delete().to(users::delete_user_controller).wrap(Policy::permission("users:delete")))
+            .route("/{id}/restore", web::post().to(users::restore_user_controller).wrap(Policy::permission("users:delete")))
     );
 
     cfg.service(
         web::scope("/auth")
-            .route("/login", web::post().to(login::login_controller)),
+            .route("/login", web::post().to(login::login_controller))
+            .route("/refresh", web::post().to(refresh::refresh_controller))
+            .route("/logout", web::post().to(logout::logout_controller).wrap(Policy::authenticated()))
+            .route("/logout/all", web::post().to(logout::logout_all_controller).wrap(Policy::authenticated()))
+            .route("/password/forgot", web::post().to(password::forgot_password_controller))
+            .route("/password/reset", web::post().to(password::reset_password_controller))
+            .route("/me", web::get().to(profile::get_profile_controller).wrap(Policy::authenticated()))
+            .route("/me", web::patch().to(profile::update_profile_controller).wrap(Policy::authenticated()))
+            .route("/me/password", web::post().to(profile::change_password_controller).wrap(Policy::authenticated()))
+            .route("/register", web::post().to(registration::register_controller))
+            .route("/verify", web::post().to(registration::verify_email_controller))
+            .route("/verify/resend", web::post().to(registration::resend_verification_controller))
+            .route("/mfa/enroll", web::post().to(mfa::enroll_controller).wrap(Policy::authenticated()))
+            .route("/mfa/activate", web::post().to(mfa::activate_controller).wrap(Policy::authenticated()))
+            .route("/mfa/disable", web::post().to(mfa::disable_controller).wrap(Policy::authenticated()))
+            .route("/mfa/verify", web::post().to(mfa::verify_controller))
+            .route("/api-keys", web::post().to(api_keys::create_api_key_controller).wrap(Policy::authenticated()))
+            .route("/api-keys", web::get().to(api_keys::list_api_keys_controller).wrap(Policy::authenticated()))
+            .route("/api-keys/{id}", web::delete().to(api_keys::revoke_api_key_controller).wrap(Policy::authenticated()))
+            .route("/oidc/authorize", web::get().to(oidc::authorize_controller))
+            .route("/oidc/callback", web::get().to(oidc::callback_controller)),
     );
+
+    cfg.service(
+        web::scope("/admin")
+            .wrap(Policy::authenticated())
+            .route("/lockouts", web::get().to(lockouts::list_lockouts_controller).wrap(Policy::permission("security:read")))
+            .route("/lockouts/{kind}/{key}", web::delete().to(lockouts::clear_lockout_controller).wrap(Policy::permission("security:write")))
+            .route("/audit-events", web::get().to(audit::list_audit_events_controller).wrap(Policy::permission("audit:read"))),
+    );
+
+    cfg.route("/.well-known/jwks.json", web::get().to(jwks::jwks_controller));
 }
@@ -1,24 +1,55 @@
 use crate::config::settings::CONFIG;
+use crate::internal::application::repositories::items::items::{ItemRepository, ITEM_SCHEMA};
+use crate::internal::application::repositories::users::users::UserRepository;
+use crate::internal::application::usecases::audit::audit::Auditor;
+use crate::internal::application::usecases::trash::trash::spawn_trash_purge;
+use crate::internal::pkg::mailer::mailer::Mailer;
+use crate::internal::pkg::utils::jwt_keys::JWT_KEYS;
+use crate::internal::pkg::oidc::oidc::OIDC_CLIENT;
+use crate::internal::pkg::password::password::{hasher_name, PASSWORDS};
+use crate::internal::pkg::password::policy::PASSWORD_POLICY;
+use crate::internal::pkg::query::search::check_language;
 use crate::api::rest::api::routes::routes::init_routes;
 use actix_web::{App, HttpServer, web, middleware::DefaultHeaders, http::header};
 use crate::middlewares::logger::SlogMiddleware;
 use crate::middlewares::logger::init_logger;
+use crate::middlewares::request_id::RequestId;
+use once_cell::sync::Lazy;
 use slog::info;
 
-pub async fn start_server(pool_data: web::Data<sqlx::Pool<sqlx::Postgres>>) -> std::io::Result<()> {
+pub async fn start_server(
+    pool_data: web::Data<sqlx::Pool<sqlx::Postgres>>,
+    mailer_data: web::Data<dyn Mailer>,
+    items_data: web::Data<dyn ItemRepository>,
+    users_data: web::Data<dyn UserRepository>,
+    auditor_data: web::Data<Auditor>,
+) -> std::io::Result<()> {
     let port: u16 = CONFIG.port.parse().expect("Invalid port");
+    // Fail fast on a broken key, OIDC, password, search or trash configuration instead of on first use.
+    Lazy::force(&JWT_KEYS);
+    Lazy::force(&OIDC_CLIENT);
+    Lazy::force(&PASSWORDS);
+    Lazy::force(&PASSWORD_POLICY);
+    check_language(pool_data.get_ref(), &ITEM_SCHEMA, &CONFIG.search_language).await.expect("Invalid SEARCH_LANGUAGE");
+    spawn_trash_purge(items_data.clone(), users_data.clone());
 
     let (logger_file, logger_terminal) = init_logger();
+    info!(logger_terminal, "{}", format!("Hashing passwords with {}, {} breached passwords loaded", hasher_name(), PASSWORD_POLICY.breached_count()));
     info!(logger_terminal, "{}", format!("Serving Rest Http on 0.0.0.0: {}", port));
 
     HttpServer::new(move || {
         App::new()
             .app_data(pool_data.clone())
+            .app_data(mailer_data.clone())
+            .app_data(items_data.clone())
+            .app_data(users_data.clone())
+            .app_data(auditor_data.clone())
             .wrap(SlogMiddleware::new(logger_file.clone()))
             .wrap(SlogMiddleware::new(logger_terminal.clone()))
             .wrap(DefaultHeaders::new()
                 .add((header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "false"))
-                .add((header::ACCESS_CONTROL_ALLOW_HEADERS, "Accept, Content-Type, Content-Length, Accept-Encoding, Authorization, Origin, Cookie, Timestamp"))
+                .add((header::ACCESS_CONTROL_ALLOW_HEADERS, "Accept, Content-Type, Content-Length, Accept-Encoding, Authorization, Origin, Cookie, Timestamp, X-Request-Id, X-API-Key, If-Match, If-None-Match"))
+                .add((header::ACCESS_CONTROL_EXPOSE_HEADERS, "ETag"))
                 .add((header::ACCESS_CONTROL_ALLOW_METHODS, "GET, POST, PUT, PATCH, DELETE, HEAD, OPTIONS"))
                 .add((header::CACHE_CONTROL, "no-store"))
                 .add((header::CONTENT_SECURITY_POLICY, "default-src 'self'"))
@@ -27,6 +58,7 @@ pub async fn start_server(pool_data: web::Data<sqlx::Pool<sqlx::Postgres>>) -> s
                 .add((header::VARY, "Origin"))
                 .add((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
             )
+            .wrap(RequestId)
             .configure(init_routes)
     })
     .bind(("0.0.0.0", port))?
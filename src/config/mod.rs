@@ -1 +1,3 @@
-pub mod settings;
\ No newline at end of file
+pub mod settings;
+#[cfg(test)]
+pub mod testing;
\ No newline at end of file
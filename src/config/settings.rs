@@ -6,6 +6,56 @@ pub struct Config {
     pub port: String,
     pub secret_key_jwt: String,
     pub jwt_exp: String,
+    pub refresh_token_exp: String,
+    pub revocation_cache_ttl: String,
+    pub items_read_policy: String,
+    pub jwt_algorithm: String,
+    pub jwt_kid: String,
+    pub jwt_private_key_path: Option<String>,
+    pub jwt_public_key_path: Option<String>,
+    pub jwt_previous_keys: String,
+    pub mailer: String,
+    pub mail_from: String,
+    pub mail_outbox_dir: String,
+    pub smtp_host: String,
+    pub smtp_port: Option<String>,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub smtp_tls: String,
+    pub password_reset_exp: String,
+    pub password_reset_url: String,
+    pub registration_enabled: bool,
+    pub email_verification_exp: String,
+    pub email_verification_url: String,
+    pub mfa_issuer: String,
+    pub mfa_challenge_exp: String,
+    pub trust_proxy_headers: bool,
+    pub login_max_attempts: i32,
+    pub login_ip_max_attempts: i32,
+    pub login_attempt_window: String,
+    pub login_lockout_base: String,
+    pub login_lockout_max: String,
+    pub oidc_issuer: Option<String>,
+    pub oidc_client_id: String,
+    pub oidc_client_secret: Option<String>,
+    pub oidc_redirect_uri: String,
+    pub oidc_scopes: String,
+    pub oidc_allow_insecure_http: bool,
+    pub oidc_auto_create_users: bool,
+    pub oidc_link_by_email: bool,
+    pub oidc_state_exp: String,
+    pub password_hasher: String,
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub bcrypt_cost: u32,
+    pub password_min_length: usize,
+    pub password_max_length: usize,
+    pub password_breached_list: Option<String>,
+    pub search_language: String,
+    pub trash_retention: String,
+    pub trash_purge_interval: String,
+    pub migration_mode: String,
 }
 
 // Using Lazy to initialize configuration once.
@@ -14,7 +64,139 @@ pub static CONFIG: Lazy<Config> = Lazy::new(|| {
     let port = env::var("PORT").expect("PORT must be set");
     let secret_key_jwt = env::var("SECRET_KEY_JWT").expect("SECRET_KEY_JWT must be set");
     let jwt_exp = env::var("JWT_EXP").expect("JWT_EXP must be set");
+    let refresh_token_exp = env::var("REFRESH_TOKEN_EXP").unwrap_or_else(|_| "7d".to_string());
+    let revocation_cache_ttl = env::var("REVOCATION_CACHE_TTL").unwrap_or_else(|_| "30s".to_string());
+    let items_read_policy = env::var("ITEMS_READ_POLICY").unwrap_or_else(|_| "public".to_string());
+    let jwt_algorithm = env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string());
+    let jwt_kid = env::var("JWT_KID").unwrap_or_else(|_| "default".to_string());
+    let jwt_private_key_path = env::var("JWT_PRIVATE_KEY_PATH").ok();
+    let jwt_public_key_path = env::var("JWT_PUBLIC_KEY_PATH").ok();
+    // Retired public keys still accepted for verification: "kid|ALG|/path/to/public.pem|valid-until-rfc3339", comma separated.
+    let jwt_previous_keys = env::var("JWT_PREVIOUS_KEYS").unwrap_or_default();
+    let mailer = env::var("MAILER").unwrap_or_else(|_| "outbox".to_string());
+    let mail_from = env::var("MAIL_FROM").unwrap_or_else(|_| "no-reply@localhost".to_string());
+    let mail_outbox_dir = env::var("MAIL_OUTBOX_DIR").unwrap_or_else(|_| "target/outbox".to_string());
+    let smtp_host = env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string());
+    let smtp_port = env::var("SMTP_PORT").ok();
+    let smtp_username = env::var("SMTP_USERNAME").ok();
+    let smtp_password = env::var("SMTP_PASSWORD").ok();
+    let smtp_tls = env::var("SMTP_TLS").unwrap_or_else(|_| "starttls".to_string());
+    let password_reset_exp = env::var("PASSWORD_RESET_EXP").unwrap_or_else(|_| "30m".to_string());
+    // The reset token is appended to this URL in the email.
+    let password_reset_url = env::var("PASSWORD_RESET_URL").unwrap_or_else(|_| "http://localhost:3000/reset-password?token=".to_string());
+    let registration_enabled = env::var("REGISTRATION_ENABLED")
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(false);
+    let email_verification_exp = env::var("EMAIL_VERIFICATION_EXP").unwrap_or_else(|_| "24h".to_string());
+    // The verification token is appended to this URL in the email.
+    let email_verification_url = env::var("EMAIL_VERIFICATION_URL").unwrap_or_else(|_| "http://localhost:3000/verify-email?token=".to_string());
+    // Shown as the account label in authenticator apps.
+    let mfa_issuer = env::var("MFA_ISSUER").unwrap_or_else(|_| "rust_crud_basic".to_string());
+    let mfa_challenge_exp = env::var("MFA_CHALLENGE_EXP").unwrap_or_else(|_| "5m".to_string());
+    // Only honour Forwarded / X-Forwarded-For when running behind a trusted proxy.
+    let trust_proxy_headers = env::var("TRUST_PROXY_HEADERS")
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(false);
+    // Failed logins tolerated per account / per IP before the lockout kicks in.
+    let login_max_attempts = env::var("LOGIN_MAX_ATTEMPTS").ok().and_then(|value| value.parse().ok()).unwrap_or(5);
+    let login_ip_max_attempts = env::var("LOGIN_IP_MAX_ATTEMPTS").ok().and_then(|value| value.parse().ok()).unwrap_or(20);
+    // Counters reset once no failure happened for this long.
+    let login_attempt_window = env::var("LOGIN_ATTEMPT_WINDOW").unwrap_or_else(|_| "15m".to_string());
+    // The lockout doubles with every further failure, up to the max.
+    let login_lockout_base = env::var("LOGIN_LOCKOUT_BASE").unwrap_or_else(|_| "30s".to_string());
+    let login_lockout_max = env::var("LOGIN_LOCKOUT_MAX").unwrap_or_else(|_| "15m".to_string());
+    // OIDC login is enabled when an issuer is configured.
+    let oidc_issuer = env::var("OIDC_ISSUER").ok().map(|issuer| issuer.trim_end_matches('/').to_string());
+    let oidc_client_id = env::var("OIDC_CLIENT_ID").unwrap_or_default();
+    let oidc_client_secret = env::var("OIDC_CLIENT_SECRET").ok();
+    let oidc_redirect_uri = env::var("OIDC_REDIRECT_URI").unwrap_or_else(|_| "http://localhost:9009/auth/oidc/callback".to_string());
+    let oidc_scopes = env::var("OIDC_SCOPES").unwrap_or_else(|_| "openid email profile".to_string());
+    // Plain http issuers are refused unless this is set, e.g. for a local mock issuer.
+    let oidc_allow_insecure_http = env::var("OIDC_ALLOW_INSECURE_HTTP")
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(false);
+    let oidc_auto_create_users = env::var("OIDC_AUTO_CREATE_USERS")
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(true);
+    // Link a first time OIDC login to the local account with the same, verified, email.
+    let oidc_link_by_email = env::var("OIDC_LINK_BY_EMAIL")
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(true);
+    let oidc_state_exp = env::var("OIDC_STATE_EXP").unwrap_or_else(|_| "10m".to_string());
+    // New passwords are hashed with PASSWORD_HASHER, older hashes are upgraded on login.
+    let password_hasher = env::var("PASSWORD_HASHER").unwrap_or_else(|_| "argon2id".to_string());
+    let argon2_memory_kib = env::var("ARGON2_MEMORY_KIB").ok().and_then(|value| value.parse().ok()).unwrap_or(19456);
+    let argon2_iterations = env::var("ARGON2_ITERATIONS").ok().and_then(|value| value.parse().ok()).unwrap_or(2);
+    let argon2_parallelism = env::var("ARGON2_PARALLELISM").ok().and_then(|value| value.parse().ok()).unwrap_or(1);
+    let bcrypt_cost = env::var("BCRYPT_COST").ok().and_then(|value| value.parse().ok()).unwrap_or(bcrypt::DEFAULT_COST);
+    let password_min_length = env::var("PASSWORD_MIN_LENGTH").ok().and_then(|value| value.parse().ok()).unwrap_or(8);
+    let password_max_length = env::var("PASSWORD_MAX_LENGTH").ok().and_then(|value| value.parse().ok()).unwrap_or(128);
+    // One password or SHA-1 hash (HIBP "HASH:count" lines work too) per line.
+    let password_breached_list = env::var("PASSWORD_BREACHED_LIST").ok().filter(|path| !path.trim().is_empty());
+    // Text search configuration item searches are parsed with, it has to match the one
+    // the items.search_vector column is generated with.
+    let search_language = env::var("SEARCH_LANGUAGE").unwrap_or_else(|_| "english".to_string());
+    // How long deleted items and users stay restorable, empty keeps them until restored.
+    let trash_retention = env::var("TRASH_RETENTION").unwrap_or_else(|_| "30d".to_string());
+    let trash_purge_interval = env::var("TRASH_PURGE_INTERVAL").unwrap_or_else(|_| "1h".to_string());
+    // "run" applies pending migrations at startup, "verify" only refuses to start when any are pending.
+    let migration_mode = env::var("MIGRATION_MODE").unwrap_or_else(|_| "run".to_string());
     // println!("DATABASE_URL: {}", database_url);
     // println!("DATABASE_URL: {}", std::env::var("DATABASE_URL").unwrap());
-    Config { database_url, port, secret_key_jwt, jwt_exp }
+    Config {
+        database_url,
+        port,
+        secret_key_jwt,
+        jwt_exp,
+        refresh_token_exp,
+        revocation_cache_ttl,
+        items_read_policy,
+        jwt_algorithm,
+        jwt_kid,
+        jwt_private_key_path,
+        jwt_public_key_path,
+        jwt_previous_keys,
+        mailer,
+        mail_from,
+        mail_outbox_dir,
+        smtp_host,
+        smtp_port,
+        smtp_username,
+        smtp_password,
+        smtp_tls,
+        password_reset_exp,
+        password_reset_url,
+        registration_enabled,
+        email_verification_exp,
+        email_verification_url,
+        mfa_issuer,
+        mfa_challenge_exp,
+        trust_proxy_headers,
+        login_max_attempts,
+        login_ip_max_attempts,
+        login_attempt_window,
+        login_lockout_base,
+        login_lockout_max,
+        oidc_issuer,
+        oidc_client_id,
+        oidc_client_secret,
+        oidc_redirect_uri,
+        oidc_scopes,
+        oidc_allow_insecure_http,
+        oidc_auto_create_users,
+        oidc_link_by_email,
+        oidc_state_exp,
+        password_hasher,
+        argon2_memory_kib,
+        argon2_iterations,
+        argon2_parallelism,
+        bcrypt_cost,
+        password_min_length,
+        password_max_length,
+        password_breached_list,
+        search_language,
+        trash_retention,
+        trash_purge_interval,
+        migration_mode,
+    }
 });
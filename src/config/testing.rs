@@ -0,0 +1,26 @@
+use std::env;
+use std::sync::Once;
+
+// Settings every deployment has to provide, for tests run without them. Variables
+// already set, e.g. from a sourced .env, are kept.
+const REQUIRED: [(&str, &str); 4] = [
+    ("DATABASE_URL", "postgres://localhost/rust_crud_basic_test"),
+    ("PORT", "0"),
+    ("SECRET_KEY_JWT", "test-only-secret-key-not-for-any-deployment"),
+    ("JWT_EXP", "15m"),
+];
+
+static INIT: Once = Once::new();
+
+// Call before anything reads CONFIG.
+pub fn init() {
+    INIT.call_once(|| {
+        for (name, value) in REQUIRED {
+            if env::var_os(name).is_none() {
+                // SAFETY: runs once, before the calling test touches CONFIG, and std
+                // serialises its own reads of the environment with this write.
+                unsafe { env::set_var(name, value) };
+            }
+        }
+    });
+}
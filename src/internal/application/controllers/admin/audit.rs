@@ -0,0 +1,11 @@
+use crate::internal::domain::entities::audit::audit::AuditEventsQuery;
+use crate::internal::application::usecases::audit::audit::list_audit_events;
+use actix_web::{Responder, web};
+use sqlx::postgres::PgPool;
+
+pub async fn list_audit_events_controller(
+    pool: web::Data<PgPool>,
+    query: web::Query<AuditEventsQuery>,
+) -> impl Responder {
+    list_audit_events(pool, query).await
+}
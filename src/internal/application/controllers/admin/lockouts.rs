@@ -0,0 +1,18 @@
+use crate::internal::domain::entities::auth::lockout::LockoutsQuery;
+use crate::internal::application::usecases::auth::lockout::{clear_lockout, list_lockouts};
+use actix_web::{Responder, web};
+use sqlx::postgres::PgPool;
+
+pub async fn list_lockouts_controller(
+    pool: web::Data<PgPool>,
+    query: web::Query<LockoutsQuery>,
+) -> impl Responder {
+    list_lockouts(pool, query).await
+}
+
+pub async fn clear_lockout_controller(
+    pool: web::Data<PgPool>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    clear_lockout(pool, path).await
+}
@@ -0,0 +1,2 @@
+pub mod audit;
+pub mod lockouts;
\ No newline at end of file
@@ -0,0 +1,28 @@
+use crate::internal::domain::entities::auth::api_key::CreateApiKeyRequest;
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::internal::application::usecases::auth::api_keys::{create_api_key, list_api_keys, revoke_api_key};
+use actix_web::{Responder, web};
+use sqlx::postgres::PgPool;
+
+pub async fn create_api_key_controller(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+    req: web::Json<CreateApiKeyRequest>,
+) -> impl Responder {
+    create_api_key(pool, identity, req).await
+}
+
+pub async fn list_api_keys_controller(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+) -> impl Responder {
+    list_api_keys(pool, identity).await
+}
+
+pub async fn revoke_api_key_controller(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+    path: web::Path<i32>,
+) -> impl Responder {
+    revoke_api_key(pool, identity, path).await
+}
@@ -0,0 +1,6 @@
+use crate::internal::application::usecases::auth::jwks::jwks;
+use actix_web::Responder;
+
+pub async fn jwks_controller() -> impl Responder {
+    jwks().await
+}
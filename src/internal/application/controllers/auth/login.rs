@@ -1,11 +1,12 @@
 use crate::internal::domain::entities::auth::login::LoginRequest;
 use crate::internal::application::usecases::auth::login::login;
-use actix_web::{Responder, web};
+use actix_web::{HttpRequest, Responder, web};
 use sqlx::postgres::PgPool;
 
 pub async fn login_controller(
     pool: web::Data<PgPool>,
+    http_req: HttpRequest,
     req: web::Json<LoginRequest>,
 ) -> impl Responder {
-    login(pool, req).await
+    login(pool, http_req, req).await
 }
\ No newline at end of file
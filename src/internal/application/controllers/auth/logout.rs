@@ -0,0 +1,18 @@
+use crate::internal::application::usecases::auth::logout::{logout, logout_all};
+use crate::internal::domain::entities::auth::identity::Identity;
+use actix_web::{Responder, web};
+use sqlx::postgres::PgPool;
+
+pub async fn logout_controller(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+) -> impl Responder {
+    logout(pool, identity).await
+}
+
+pub async fn logout_all_controller(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+) -> impl Responder {
+    logout_all(pool, identity).await
+}
@@ -0,0 +1,36 @@
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::internal::domain::entities::auth::mfa::{MfaCodeRequest, MfaVerifyRequest};
+use crate::internal::application::usecases::auth::mfa::{activate, disable, enroll, verify};
+use actix_web::{HttpRequest, Responder, web};
+use sqlx::postgres::PgPool;
+
+pub async fn enroll_controller(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+) -> impl Responder {
+    enroll(pool, identity).await
+}
+
+pub async fn activate_controller(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+    req: web::Json<MfaCodeRequest>,
+) -> impl Responder {
+    activate(pool, identity, req).await
+}
+
+pub async fn disable_controller(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+    req: web::Json<MfaCodeRequest>,
+) -> impl Responder {
+    disable(pool, identity, req).await
+}
+
+pub async fn verify_controller(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+    req: web::Json<MfaVerifyRequest>,
+) -> impl Responder {
+    verify(pool, http_req, req).await
+}
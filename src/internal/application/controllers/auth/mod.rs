@@ -1 +1,10 @@
-pub mod login;
\ No newline at end of file
+pub mod api_keys;
+pub mod jwks;
+pub mod login;
+pub mod logout;
+pub mod mfa;
+pub mod oidc;
+pub mod password;
+pub mod profile;
+pub mod refresh;
+pub mod registration;
\ No newline at end of file
@@ -0,0 +1,18 @@
+use crate::internal::domain::entities::auth::oidc::OidcCallbackQuery;
+use crate::internal::application::usecases::auth::oidc::{authorize, callback};
+use actix_web::{HttpRequest, Responder, web};
+use sqlx::postgres::PgPool;
+
+pub async fn authorize_controller(
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    authorize(pool).await
+}
+
+pub async fn callback_controller(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+    query: web::Query<OidcCallbackQuery>,
+) -> impl Responder {
+    callback(pool, http_req, query).await
+}
@@ -0,0 +1,22 @@
+use crate::internal::domain::entities::auth::password::{ForgotPasswordRequest, ResetPasswordRequest};
+use crate::internal::application::usecases::auth::password::{forgot_password, reset_password};
+use crate::internal::pkg::mailer::mailer::Mailer;
+use actix_web::{HttpRequest, Responder, web};
+use sqlx::postgres::PgPool;
+
+pub async fn forgot_password_controller(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    http_req: HttpRequest,
+    req: web::Json<ForgotPasswordRequest>,
+) -> impl Responder {
+    forgot_password(pool, mailer, http_req, req).await
+}
+
+pub async fn reset_password_controller(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+    req: web::Json<ResetPasswordRequest>,
+) -> impl Responder {
+    reset_password(pool, http_req, req).await
+}
@@ -0,0 +1,32 @@
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::internal::domain::entities::auth::profile::{ChangePasswordRequest, UpdateProfileRequest};
+use crate::internal::application::usecases::auth::profile::{change_password, get_profile, update_profile};
+use crate::internal::pkg::mailer::mailer::Mailer;
+use actix_web::{HttpRequest, Responder, web};
+use sqlx::postgres::PgPool;
+
+pub async fn get_profile_controller(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+) -> impl Responder {
+    get_profile(pool, identity).await
+}
+
+pub async fn update_profile_controller(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    http_req: HttpRequest,
+    identity: Identity,
+    req: web::Json<UpdateProfileRequest>,
+) -> impl Responder {
+    update_profile(pool, mailer, http_req, identity, req).await
+}
+
+pub async fn change_password_controller(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+    identity: Identity,
+    req: web::Json<ChangePasswordRequest>,
+) -> impl Responder {
+    change_password(pool, http_req, identity, req).await
+}
@@ -0,0 +1,12 @@
+use crate::internal::domain::entities::auth::session::RefreshRequest;
+use crate::internal::application::usecases::auth::refresh::refresh;
+use actix_web::{HttpRequest, Responder, web};
+use sqlx::postgres::PgPool;
+
+pub async fn refresh_controller(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+    req: web::Json<RefreshRequest>,
+) -> impl Responder {
+    refresh(pool, http_req, req).await
+}
@@ -0,0 +1,28 @@
+use crate::internal::domain::entities::auth::registration::{RegisterRequest, ResendVerificationRequest, VerifyEmailRequest};
+use crate::internal::application::usecases::auth::registration::{register, resend_verification, verify_email};
+use crate::internal::pkg::mailer::mailer::Mailer;
+use actix_web::{Responder, web};
+use sqlx::postgres::PgPool;
+
+pub async fn register_controller(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    req: web::Json<RegisterRequest>,
+) -> impl Responder {
+    register(pool, mailer, req).await
+}
+
+pub async fn verify_email_controller(
+    pool: web::Data<PgPool>,
+    req: web::Json<VerifyEmailRequest>,
+) -> impl Responder {
+    verify_email(pool, req).await
+}
+
+pub async fn resend_verification_controller(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    req: web::Json<ResendVerificationRequest>,
+) -> impl Responder {
+    resend_verification(pool, mailer, req).await
+}
@@ -1,38 +1,71 @@
 use crate::internal::domain::entities::items::items::{CreateItem, UpdateItem, ItemsQuery};
-use crate::internal::application::usecases::items::items::{create_item, get_items, get_item, update_item, delete_item};
+use crate::internal::application::usecases::items::items::{create_item, get_items, get_trashed_items, get_item, update_item, delete_item, restore_item};
+use crate::internal::application::repositories::items::items::ItemRepository;
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::internal::application::usecases::audit::audit::Auditor;
 use actix_web::{HttpRequest, Responder, web};
-use sqlx::postgres::PgPool;
 
 pub async fn create_item_controller(
-    pool: web::Data<PgPool>,
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn ItemRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
     item: web::Json<CreateItem>,
 ) -> impl Responder {
-    create_item(pool, item).await
+    create_item(auditor, repository, http_req, identity, item).await
 }
 
 pub async fn get_items_controller(
-    pool: web::Data<PgPool>,
+    repository: web::Data<dyn ItemRepository>,
     req: HttpRequest,
     params: web::Query<ItemsQuery>
 ) -> impl Responder {
-    get_items(pool, req, params).await
+    get_items(repository, req, params).await
 }
 
-pub async fn get_item_controller(pool: web::Data<PgPool>, item_id: web::Path<i32>) -> impl Responder {
-    get_item(pool, item_id).await
+pub async fn get_trashed_items_controller(
+    repository: web::Data<dyn ItemRepository>,
+    req: HttpRequest,
+    params: web::Query<ItemsQuery>
+) -> impl Responder {
+    get_trashed_items(repository, req, params).await
+}
+
+pub async fn get_item_controller(
+    repository: web::Data<dyn ItemRepository>,
+    http_req: HttpRequest,
+    item_id: web::Path<i32>,
+) -> impl Responder {
+    get_item(repository, http_req, item_id).await
 }
 
 pub async fn update_item_controller(
-    pool: web::Data<PgPool>,
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn ItemRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
     item_id: web::Path<i32>,
     update: web::Json<UpdateItem>,
 ) -> impl Responder {
-    update_item(pool, item_id, update).await
+    update_item(auditor, repository, http_req, identity, item_id, update).await
 }
 
 pub async fn delete_item_controller(
-    pool: web::Data<PgPool>,
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn ItemRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
+    item_id: web::Path<i32>,
+) -> impl Responder {
+    delete_item(auditor, repository, http_req, identity, item_id).await
+}
+
+pub async fn restore_item_controller(
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn ItemRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
     item_id: web::Path<i32>,
 ) -> impl Responder {
-    delete_item(pool, item_id).await
+    restore_item(auditor, repository, http_req, identity, item_id).await
 }
@@ -1,3 +1,4 @@
 pub mod items;
+pub mod admin;
 pub mod auth;
 pub mod users;
\ No newline at end of file
@@ -1,39 +1,82 @@
 use crate::internal::domain::entities::users::users::{CreateUserRequest, UpdateUserRequest, UsersQuery};
-use crate::internal::application::usecases::users::users::{create_user, get_users, get_user, update_user, delete_user};
+use crate::internal::application::usecases::users::users::{create_user, get_users, get_trashed_users, get_user, update_user, patch_user, delete_user, restore_user};
+use crate::internal::application::repositories::users::users::UserRepository;
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::internal::application::usecases::audit::audit::Auditor;
 use actix_web::{HttpRequest, Responder, web};
-use sqlx::postgres::PgPool;
 
 pub async fn create_user_controller(
-    pool: web::Data<PgPool>,
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn UserRepository>,
     http_req: HttpRequest,
+    identity: Identity,
     payload: web::Json<CreateUserRequest>,
 ) -> impl Responder {
-    create_user(pool, http_req, payload).await
+    create_user(auditor, repository, http_req, identity, payload).await
 }
 
 pub async fn get_users_controller(
-    pool: web::Data<PgPool>,
+    repository: web::Data<dyn UserRepository>,
     http_req: HttpRequest,
     params: web::Query<UsersQuery>
 ) -> impl Responder {
-    get_users(pool, http_req, params).await
+    get_users(repository, http_req, params).await
 }
 
-pub async fn get_user_controller(pool: web::Data<PgPool>, id: web::Path<i32>) -> impl Responder {
-    get_user(pool, id).await
+pub async fn get_trashed_users_controller(
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    params: web::Query<UsersQuery>
+) -> impl Responder {
+    get_trashed_users(repository, http_req, params).await
+}
+
+pub async fn get_user_controller(
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    id: web::Path<i32>,
+) -> impl Responder {
+    get_user(repository, http_req, id).await
 }
 
 pub async fn update_user_controller(
-    pool: web::Data<PgPool>,
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
     id: web::Path<i32>,
     update: web::Json<UpdateUserRequest>,
 ) -> impl Responder {
-    update_user(pool, id, update).await
+    update_user(auditor, repository, http_req, identity, id, update).await
+}
+
+pub async fn patch_user_controller(
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
+    id: web::Path<i32>,
+    update: web::Json<UpdateUserRequest>,
+) -> impl Responder {
+    patch_user(auditor, repository, http_req, identity, id, update).await
 }
 
 pub async fn delete_user_controller(
-    pool: web::Data<PgPool>,
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
+    id: web::Path<i32>,
+) -> impl Responder {
+    delete_user(auditor, repository, http_req, identity, id).await
+}
+
+pub async fn restore_user_controller(
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
     id: web::Path<i32>,
 ) -> impl Responder {
-    delete_user(pool, id).await
+    restore_user(auditor, repository, http_req, identity, id).await
 }
@@ -0,0 +1,84 @@
+use crate::internal::domain::entities::audit::audit::{AuditEvent, AuditEventFilter, NewAuditEvent};
+use async_trait::async_trait;
+use sqlx::{postgres::PgPool, Postgres, QueryBuilder};
+
+// Where the item and user use cases write their audit trail, shared with handlers
+// through `Auditor`. The functions below are the Postgres queries behind
+// PgAuditEventRepository and stay in use by the auth flows and the audit listing.
+#[async_trait]
+pub trait AuditEventRepository: Send + Sync {
+    async fn insert_audit_event(&self, event: NewAuditEvent) -> Result<(), sqlx::Error>;
+}
+
+pub async fn insert_audit_event(pool: &PgPool, event: NewAuditEvent) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO audit_events (actor_id, actor, api_key_id, action, target_type, target_id, outcome, ip, user_agent, request_id, changes)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)"
+    )
+    .bind(event.actor_id)
+    .bind(event.actor)
+    .bind(event.api_key_id)
+    .bind(event.action)
+    .bind(event.target_type)
+    .bind(event.target_id)
+    .bind(event.outcome)
+    .bind(event.ip)
+    .bind(event.user_agent)
+    .bind(event.request_id)
+    .bind(event.changes)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &AuditEventFilter) {
+    query.push(" WHERE TRUE");
+    if let Some(actor_id) = filter.actor_id {
+        query.push(" AND actor_id = ").push_bind(actor_id);
+    }
+    if let Some(actor) = &filter.actor {
+        query.push(" AND actor = ").push_bind(actor.clone());
+    }
+    // "user." matches every user action.
+    if let Some(action) = &filter.action {
+        match action.strip_suffix('.') {
+            Some(prefix) => query.push(" AND action LIKE ").push_bind(format!("{}.%", prefix.replace('%', "\\%").replace('_', "\\_"))),
+            None => query.push(" AND action = ").push_bind(action.clone()),
+        };
+    }
+    if let Some(target_type) = &filter.target_type {
+        query.push(" AND target_type = ").push_bind(target_type.clone());
+    }
+    if let Some(target_id) = &filter.target_id {
+        query.push(" AND target_id = ").push_bind(target_id.clone());
+    }
+    if let Some(outcome) = &filter.outcome {
+        query.push(" AND outcome = ").push_bind(outcome.clone());
+    }
+    if let Some(from) = filter.from {
+        query.push(" AND occurred_at >= ").push_bind(from);
+    }
+    if let Some(to) = filter.to {
+        query.push(" AND occurred_at < ").push_bind(to);
+    }
+}
+
+// Newest first.
+pub async fn list_audit_events(
+    pool: &PgPool,
+    filter: &AuditEventFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<AuditEvent>, i64), sqlx::Error> {
+    let mut query = QueryBuilder::new(
+        "SELECT id, occurred_at, actor_id, actor, api_key_id, action, target_type, target_id, outcome, ip, user_agent, request_id, changes FROM audit_events"
+    );
+    push_filter(&mut query, filter);
+    query.push(" ORDER BY id DESC LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(offset);
+    let events = query.build_query_as::<AuditEvent>().fetch_all(pool).await?;
+
+    let mut count_query = QueryBuilder::new("SELECT COUNT(id) FROM audit_events");
+    push_filter(&mut count_query, filter);
+    let (count,): (i64,) = count_query.build_query_as().fetch_one(pool).await?;
+    Ok((events, count))
+}
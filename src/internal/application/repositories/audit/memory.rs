@@ -0,0 +1,31 @@
+use crate::internal::application::repositories::audit::audit_events::AuditEventRepository;
+use crate::internal::domain::entities::audit::audit::NewAuditEvent;
+use async_trait::async_trait;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+// Audit events kept in memory in the order they were recorded.
+#[derive(Default)]
+pub struct MemoryAuditEventRepository {
+    events: Mutex<Vec<NewAuditEvent>>,
+}
+
+impl MemoryAuditEventRepository {
+    fn events(&self) -> MutexGuard<'_, Vec<NewAuditEvent>> {
+        self.events.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // The recorded events as (action, outcome, target id).
+    pub fn recorded(&self) -> Vec<(String, String, Option<String>)> {
+        self.events().iter()
+            .map(|event| (event.action.clone(), event.outcome.clone(), event.target_id.clone()))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AuditEventRepository for MemoryAuditEventRepository {
+    async fn insert_audit_event(&self, event: NewAuditEvent) -> Result<(), sqlx::Error> {
+        self.events().push(event);
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod audit_events;
+#[cfg(test)]
+pub mod memory;
+pub mod postgres;
\ No newline at end of file
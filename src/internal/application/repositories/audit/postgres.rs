@@ -0,0 +1,22 @@
+use crate::internal::application::repositories::audit::audit_events::{self, AuditEventRepository};
+use crate::internal::domain::entities::audit::audit::NewAuditEvent;
+use async_trait::async_trait;
+use sqlx::postgres::PgPool;
+
+// The audit_events table.
+pub struct PgAuditEventRepository {
+    pool: PgPool,
+}
+
+impl PgAuditEventRepository {
+    pub fn new(pool: PgPool) -> Self {
+        PgAuditEventRepository { pool }
+    }
+}
+
+#[async_trait]
+impl AuditEventRepository for PgAuditEventRepository {
+    async fn insert_audit_event(&self, event: NewAuditEvent) -> Result<(), sqlx::Error> {
+        audit_events::insert_audit_event(&self.pool, event).await
+    }
+}
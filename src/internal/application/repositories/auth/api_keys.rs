@@ -0,0 +1,70 @@
+use crate::internal::domain::entities::auth::api_key::ApiKey;
+use chrono::NaiveDateTime;
+use sqlx::postgres::PgPool;
+
+const API_KEY_COLUMNS: &str = "id, user_id, name, prefix, key_hash, scopes, expires_at, last_used_at, created_at, revoked_at";
+
+pub async fn create_api_key(
+    pool: &PgPool,
+    user_id: i32,
+    name: &str,
+    prefix: &str,
+    key_hash: &str,
+    scopes: &[String],
+    expires_at: Option<NaiveDateTime>,
+) -> Result<ApiKey, sqlx::Error> {
+    let api_key = sqlx::query_as::<_, ApiKey>(&format!(
+        "INSERT INTO api_keys (user_id, name, prefix, key_hash, scopes, expires_at) VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+        API_KEY_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(name)
+    .bind(prefix)
+    .bind(key_hash)
+    .bind(scopes)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await?;
+    Ok(api_key)
+}
+
+pub async fn list_api_keys(pool: &PgPool, user_id: i32) -> Result<Vec<ApiKey>, sqlx::Error> {
+    let api_keys = sqlx::query_as::<_, ApiKey>(&format!(
+        "SELECT {} FROM api_keys WHERE user_id = $1 ORDER BY created_at DESC",
+        API_KEY_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(api_keys)
+}
+
+pub async fn get_api_key_by_prefix(pool: &PgPool, prefix: &str) -> Result<ApiKey, sqlx::Error> {
+    let api_key = sqlx::query_as::<_, ApiKey>(&format!("SELECT {} FROM api_keys WHERE prefix = $1", API_KEY_COLUMNS))
+        .bind(prefix)
+        .fetch_one(pool)
+        .await?;
+    Ok(api_key)
+}
+
+// Returns false when the key does not exist, belongs to someone else or is already revoked.
+pub async fn revoke_api_key(pool: &PgPool, id: i32, user_id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Written at most once a minute per key to keep hot keys from hammering the row.
+pub async fn touch_api_key(pool: &PgPool, id: i32, now: NaiveDateTime) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE api_keys SET last_used_at = $1 WHERE id = $2 AND (last_used_at IS NULL OR last_used_at < $1 - INTERVAL '1 minute')"
+    )
+    .bind(now)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
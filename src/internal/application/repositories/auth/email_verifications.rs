@@ -0,0 +1,42 @@
+use chrono::NaiveDateTime;
+use sqlx::postgres::PgPool;
+
+// Store a new verification token for the address it is mailed to, any earlier
+// unused token of the user stops working.
+pub async fn create_email_verification(
+    pool: &PgPool,
+    user_id: i32,
+    email: &str,
+    token_hash: &str,
+    expires_at: NaiveDateTime,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE email_verifications SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .execute(&mut tx)
+        .await?;
+    sqlx::query("INSERT INTO email_verifications (user_id, email, token_hash, expires_at) VALUES ($1, $2, $3, $4)")
+        .bind(user_id)
+        .bind(email)
+        .bind(token_hash)
+        .bind(expires_at)
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// Consume a verification token, returning the user it belongs to and the address
+// it was sent to. RowNotFound when the token is unknown, expired or already used.
+pub async fn consume_email_verification(pool: &PgPool, token_hash: &str, now: NaiveDateTime) -> Result<(i32, String), sqlx::Error> {
+    let verification: (i32, String) = sqlx::query_as(
+        "UPDATE email_verifications ev SET used_at = NOW() FROM users u \
+        WHERE u.id = ev.user_id AND ev.token_hash = $1 AND ev.used_at IS NULL AND ev.expires_at > $2 \
+        RETURNING ev.user_id, COALESCE(ev.email, u.email)"
+    )
+    .bind(token_hash)
+    .bind(now)
+    .fetch_one(pool)
+    .await?;
+    Ok(verification)
+}
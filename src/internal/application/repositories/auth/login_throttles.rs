@@ -0,0 +1,100 @@
+use crate::internal::domain::entities::auth::lockout::LoginThrottle;
+use chrono::NaiveDateTime;
+use sqlx::postgres::PgPool;
+
+pub const KIND_ACCOUNT: &str = "account";
+pub const KIND_IP: &str = "ip";
+
+pub async fn get_locked_until(
+    pool: &PgPool,
+    kind: &str,
+    key: &str,
+    now: NaiveDateTime,
+) -> Result<Option<NaiveDateTime>, sqlx::Error> {
+    let locked_until: Option<NaiveDateTime> = sqlx::query_scalar(
+        "SELECT locked_until FROM login_throttles WHERE kind = $1 AND key = $2 AND locked_until > $3"
+    )
+    .bind(kind)
+    .bind(key)
+    .bind(now)
+    .fetch_optional(pool)
+    .await?;
+    Ok(locked_until)
+}
+
+// Count a failure and return the number of failures in the current window.
+// Counters whose last failure is older than `window_start` start over.
+pub async fn record_failure(
+    pool: &PgPool,
+    kind: &str,
+    key: &str,
+    now: NaiveDateTime,
+    window_start: NaiveDateTime,
+) -> Result<i32, sqlx::Error> {
+    let failures: i32 = sqlx::query_scalar(
+        "INSERT INTO login_throttles (kind, key, failures, last_failure_at) VALUES ($1, $2, 1, $3) \
+         ON CONFLICT (kind, key) DO UPDATE SET \
+             failures = CASE WHEN login_throttles.last_failure_at < $4 THEN 1 ELSE login_throttles.failures + 1 END, \
+             locked_until = CASE WHEN login_throttles.last_failure_at < $4 THEN NULL ELSE login_throttles.locked_until END, \
+             last_failure_at = $3 \
+         RETURNING failures"
+    )
+    .bind(kind)
+    .bind(key)
+    .bind(now)
+    .bind(window_start)
+    .fetch_one(pool)
+    .await?;
+    Ok(failures)
+}
+
+pub async fn lock(pool: &PgPool, kind: &str, key: &str, locked_until: NaiveDateTime) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE login_throttles SET locked_until = $1 WHERE kind = $2 AND key = $3")
+        .bind(locked_until)
+        .bind(kind)
+        .bind(key)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// Returns false when there was nothing to clear.
+pub async fn clear(pool: &PgPool, kind: &str, key: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM login_throttles WHERE kind = $1 AND key = $2")
+        .bind(kind)
+        .bind(key)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_stale(pool: &PgPool, window_start: NaiveDateTime, now: NaiveDateTime) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM login_throttles WHERE last_failure_at < $1 AND (locked_until IS NULL OR locked_until <= $2)")
+        .bind(window_start)
+        .bind(now)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn list_throttles(
+    pool: &PgPool,
+    kind: Option<&str>,
+    locked_only: bool,
+    window_start: NaiveDateTime,
+    now: NaiveDateTime,
+) -> Result<Vec<LoginThrottle>, sqlx::Error> {
+    let throttles = sqlx::query_as::<_, LoginThrottle>(
+        "SELECT kind, key, failures, last_failure_at, locked_until FROM login_throttles \
+         WHERE ($1::VARCHAR IS NULL OR kind = $1) \
+           AND (locked_until > $4 OR (NOT $2 AND last_failure_at >= $3)) \
+         ORDER BY last_failure_at DESC"
+    )
+    .bind(kind)
+    .bind(locked_only)
+    .bind(window_start)
+    .bind(now)
+    .fetch_all(pool)
+    .await?;
+    Ok(throttles)
+}
@@ -0,0 +1,170 @@
+use crate::internal::domain::entities::auth::mfa::UserMfa;
+use chrono::NaiveDateTime;
+use sqlx::postgres::PgPool;
+
+pub async fn get_user_mfa(pool: &PgPool, user_id: i32) -> Result<UserMfa, sqlx::Error> {
+    let mfa = sqlx::query_as::<_, UserMfa>(
+        "SELECT user_id, secret, enabled_at FROM user_mfa WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(mfa)
+}
+
+// Start or restart a pending enrolment. Returns false when MFA is already enabled.
+pub async fn save_pending_mfa(pool: &PgPool, user_id: i32, secret: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO user_mfa (user_id, secret) VALUES ($1, $2) \
+         ON CONFLICT (user_id) DO UPDATE SET secret = EXCLUDED.secret, last_used_step = NULL, created_at = NOW() \
+         WHERE user_mfa.enabled_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(secret)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Enable a pending enrolment and replace the recovery codes. Returns false when it
+// was activated concurrently or the code step was already used.
+pub async fn activate_mfa(pool: &PgPool, user_id: i32, step: i64, code_hashes: &[String]) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query(
+        "UPDATE user_mfa SET enabled_at = NOW(), last_used_step = $1 \
+         WHERE user_id = $2 AND enabled_at IS NULL AND (last_used_step IS NULL OR last_used_step < $1)"
+    )
+    .bind(step)
+    .bind(user_id)
+    .execute(&mut tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut tx)
+        .await?;
+    for code_hash in code_hashes {
+        sqlx::query("INSERT INTO mfa_recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(code_hash)
+            .execute(&mut tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(true)
+}
+
+pub async fn disable_mfa(pool: &PgPool, user_id: i32) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for statement in [
+        "DELETE FROM mfa_challenges WHERE user_id = $1",
+        "DELETE FROM mfa_recovery_codes WHERE user_id = $1",
+        "DELETE FROM user_mfa WHERE user_id = $1",
+    ] {
+        sqlx::query(statement)
+            .bind(user_id)
+            .execute(&mut tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+// Record a TOTP step as used. Returns false when it, or a later one, was already accepted.
+pub async fn use_totp_step(pool: &PgPool, user_id: i32, step: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE user_mfa SET last_used_step = $1 WHERE user_id = $2 AND (last_used_step IS NULL OR last_used_step < $1)"
+    )
+    .bind(step)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn use_recovery_code(pool: &PgPool, user_id: i32, code_hash: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE mfa_recovery_codes SET used_at = NOW() WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn create_challenge(pool: &PgPool, user_id: i32, token_hash: &str, expires_at: NaiveDateTime) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO mfa_challenges (user_id, token_hash, expires_at) VALUES ($1, $2, $3)")
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(expires_at)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// Count an attempt against a live challenge, returning the user it belongs to.
+// RowNotFound when the challenge is unknown, expired, used or out of attempts.
+pub async fn attempt_challenge(
+    pool: &PgPool,
+    token_hash: &str,
+    now: NaiveDateTime,
+    max_attempts: i32,
+) -> Result<i32, sqlx::Error> {
+    let user_id: i32 = sqlx::query_scalar(
+        "UPDATE mfa_challenges SET attempts = attempts + 1 \
+         WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2 AND attempts < $3 RETURNING user_id"
+    )
+    .bind(token_hash)
+    .bind(now)
+    .bind(max_attempts)
+    .fetch_one(pool)
+    .await?;
+    Ok(user_id)
+}
+
+// Returns false when the challenge was already completed by a concurrent request.
+pub async fn complete_challenge(pool: &PgPool, token_hash: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE mfa_challenges SET used_at = NOW() WHERE token_hash = $1 AND used_at IS NULL")
+        .bind(token_hash)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::application::repositories::users::users::create_user;
+    use crate::internal::domain::entities::users::users::CreateUserRequest;
+    use crate::internal::pkg::database::sql::migrate::migrate_up;
+    use crate::internal::pkg::database::sql::scratch::ScratchDatabase;
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn a_totp_step_is_only_accepted_once() {
+        let database = ScratchDatabase::create("mfa").await;
+        migrate_up(&database.pool).await.unwrap();
+        let user = CreateUserRequest {
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            password: "hash".to_string(),
+            name: None,
+            roles: None,
+        };
+        let user_id = create_user(&database.pool, user, None, None).await.unwrap().id;
+        save_pending_mfa(&database.pool, user_id, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").await.unwrap();
+
+        // The code confirming the enrolment is used up by it.
+        assert!(activate_mfa(&database.pool, user_id, 100, &[]).await.unwrap());
+        assert!(!use_totp_step(&database.pool, user_id, 100).await.unwrap());
+        assert!(use_totp_step(&database.pool, user_id, 101).await.unwrap());
+        assert!(!use_totp_step(&database.pool, user_id, 101).await.unwrap());
+        // A code of the step before is still within the drift window, but older than the last one used.
+        assert!(!use_totp_step(&database.pool, user_id, 100).await.unwrap());
+        assert!(use_totp_step(&database.pool, user_id, 102).await.unwrap());
+        database.drop().await;
+    }
+}
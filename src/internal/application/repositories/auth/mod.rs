@@ -0,0 +1,9 @@
+pub mod sessions;
+pub mod api_keys;
+pub mod email_verifications;
+pub mod login_throttles;
+pub mod mfa;
+pub mod oidc;
+pub mod password_resets;
+pub mod revocations;
+pub mod roles;
\ No newline at end of file
@@ -0,0 +1,78 @@
+use crate::internal::domain::entities::auth::oidc::OidcState;
+use chrono::NaiveDateTime;
+use sqlx::postgres::PgPool;
+
+pub async fn create_state(
+    pool: &PgPool,
+    state_hash: &str,
+    nonce: &str,
+    code_verifier: &str,
+    expires_at: NaiveDateTime,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO oidc_states (state_hash, nonce, code_verifier, expires_at) VALUES ($1, $2, $3, $4)")
+        .bind(state_hash)
+        .bind(nonce)
+        .bind(code_verifier)
+        .bind(expires_at)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// States are single use, RowNotFound when unknown or already consumed.
+pub async fn consume_state(pool: &PgPool, state_hash: &str) -> Result<OidcState, sqlx::Error> {
+    let state = sqlx::query_as::<_, OidcState>(
+        "DELETE FROM oidc_states WHERE state_hash = $1 RETURNING nonce, code_verifier, expires_at"
+    )
+    .bind(state_hash)
+    .fetch_one(pool)
+    .await?;
+    Ok(state)
+}
+
+pub async fn delete_expired_states(pool: &PgPool, now: NaiveDateTime) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM oidc_states WHERE expires_at <= $1")
+        .bind(now)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_identity_user(pool: &PgPool, issuer: &str, subject: &str) -> Result<i32, sqlx::Error> {
+    let user_id: i32 = sqlx::query_scalar("SELECT user_id FROM user_identities WHERE issuer = $1 AND subject = $2")
+        .bind(issuer)
+        .bind(subject)
+        .fetch_one(pool)
+        .await?;
+    Ok(user_id)
+}
+
+pub async fn link_identity(
+    pool: &PgPool,
+    user_id: i32,
+    issuer: &str,
+    subject: &str,
+    email: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO user_identities (user_id, issuer, subject, email, last_login_at) VALUES ($1, $2, $3, $4, NOW()) \
+         ON CONFLICT (issuer, subject) DO NOTHING"
+    )
+    .bind(user_id)
+    .bind(issuer)
+    .bind(subject)
+    .bind(email)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn touch_identity(pool: &PgPool, issuer: &str, subject: &str, email: Option<&str>) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE user_identities SET last_login_at = NOW(), email = COALESCE($3, email) WHERE issuer = $1 AND subject = $2")
+        .bind(issuer)
+        .bind(subject)
+        .bind(email)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
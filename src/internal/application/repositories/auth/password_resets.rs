@@ -0,0 +1,62 @@
+use chrono::NaiveDateTime;
+use sqlx::postgres::PgPool;
+use sqlx::{Postgres, Transaction};
+
+// Store a new reset token, any earlier unused token of the user stops working.
+pub async fn create_password_reset(
+    pool: &PgPool,
+    user_id: i32,
+    token_hash: &str,
+    ip: Option<String>,
+    expires_at: NaiveDateTime,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE password_resets SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .execute(&mut tx)
+        .await?;
+    sqlx::query("INSERT INTO password_resets (user_id, token_hash, ip, expires_at) VALUES ($1, $2, $3, $4)")
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(ip)
+        .bind(expires_at)
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// Consume a reset token, returning the user it belongs to. RowNotFound when the
+// token is unknown, expired or already used.
+pub async fn consume_password_reset(pool: &PgPool, token_hash: &str, now: NaiveDateTime) -> Result<i32, sqlx::Error> {
+    let user_id: i32 = sqlx::query_scalar(
+        "UPDATE password_resets SET used_at = NOW() WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2 RETURNING user_id"
+    )
+    .bind(token_hash)
+    .bind(now)
+    .fetch_one(pool)
+    .await?;
+    Ok(user_id)
+}
+
+// The user a usable reset token belongs to, without consuming it.
+pub async fn get_password_reset_user(pool: &PgPool, token_hash: &str, now: NaiveDateTime) -> Result<i32, sqlx::Error> {
+    let user_id: i32 = sqlx::query_scalar(
+        "SELECT user_id FROM password_resets WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2"
+    )
+    .bind(token_hash)
+    .bind(now)
+    .fetch_one(pool)
+    .await?;
+    Ok(user_id)
+}
+
+
+// Drop the user's unused reset tokens, inside a transaction the caller commits.
+pub async fn delete_pending_resets(tx: &mut Transaction<'_, Postgres>, user_id: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM password_resets WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use sqlx::postgres::PgPool;
+
+pub async fn revoke_token(
+    pool: &PgPool,
+    jti: &str,
+    user_id: Option<i32>,
+    expires_at: NaiveDateTime,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO revoked_tokens (jti, user_id, expires_at) VALUES ($1, $2, $3) ON CONFLICT (jti) DO NOTHING"
+    )
+    .bind(jti)
+    .bind(user_id)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn is_token_revoked(pool: &PgPool, jti: &str) -> Result<bool, sqlx::Error> {
+    let revoked: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM revoked_tokens WHERE jti = $1)")
+        .bind(jti)
+        .fetch_one(pool)
+        .await?;
+    Ok(revoked)
+}
+
+// Entries are only needed until the token would have expired anyway.
+pub async fn delete_expired_revocations(pool: &PgPool, now: NaiveDateTime) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM revoked_tokens WHERE expires_at < $1")
+        .bind(now)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
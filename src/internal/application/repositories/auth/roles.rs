@@ -0,0 +1,67 @@
+use sqlx::postgres::PgPool;
+use sqlx::{Postgres, Transaction};
+
+pub const DEFAULT_ROLE: &str = "user";
+
+pub async fn get_user_roles(pool: &PgPool, user_id: i32) -> Result<Vec<String>, sqlx::Error> {
+    let roles: Vec<String> = sqlx::query_scalar(
+        "SELECT r.name FROM roles r JOIN user_roles ur ON ur.role_id = r.id WHERE ur.user_id = $1 ORDER BY r.name"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(roles)
+}
+
+// Role names of many users at once, as (user id, role) pairs.
+pub async fn get_users_roles(pool: &PgPool, user_ids: &[i32]) -> Result<Vec<(i32, String)>, sqlx::Error> {
+    let roles: Vec<(i32, String)> = sqlx::query_as(
+        "SELECT ur.user_id, r.name FROM roles r JOIN user_roles ur ON ur.role_id = r.id WHERE ur.user_id = ANY($1) ORDER BY r.name"
+    )
+    .bind(user_ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(roles)
+}
+
+pub async fn get_user_permissions(pool: &PgPool, user_id: i32) -> Result<Vec<String>, sqlx::Error> {
+    let permissions: Vec<String> = sqlx::query_scalar(
+        "SELECT DISTINCT p.name FROM permissions p \
+         JOIN role_permissions rp ON rp.permission_id = p.id \
+         JOIN user_roles ur ON ur.role_id = rp.role_id \
+         WHERE ur.user_id = $1 ORDER BY p.name"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(permissions)
+}
+
+// Returns the requested role names that do not exist.
+pub async fn find_unknown_roles(pool: &PgPool, roles: &[String]) -> Result<Vec<String>, sqlx::Error> {
+    let known: Vec<String> = sqlx::query_scalar("SELECT name FROM roles WHERE name = ANY($1)")
+        .bind(roles)
+        .fetch_all(pool)
+        .await?;
+    Ok(roles.iter().filter(|role| !known.contains(role)).cloned().collect())
+}
+
+// Replace the roles of a user inside a transaction the caller commits. Tells
+// whether the user's roles are any different afterwards.
+pub async fn replace_user_roles(tx: &mut Transaction<'_, Postgres>, user_id: i32, roles: &[String]) -> Result<bool, sqlx::Error> {
+    let removed = sqlx::query(
+        "DELETE FROM user_roles WHERE user_id = $1 AND role_id NOT IN (SELECT id FROM roles WHERE name = ANY($2))"
+    )
+    .bind(user_id)
+    .bind(roles)
+    .execute(&mut *tx)
+    .await?;
+    let added = sqlx::query(
+        "INSERT INTO user_roles (user_id, role_id) SELECT $1, id FROM roles WHERE name = ANY($2) ON CONFLICT DO NOTHING"
+    )
+    .bind(user_id)
+    .bind(roles)
+    .execute(&mut *tx)
+    .await?;
+    Ok(removed.rows_affected() + added.rows_affected() > 0)
+}
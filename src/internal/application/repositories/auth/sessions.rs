@@ -0,0 +1,72 @@
+use crate::internal::domain::entities::auth::session::{CreateSession, Session};
+use sqlx::postgres::PgPool;
+use sqlx::{Postgres, Transaction};
+
+const SESSION_COLUMNS: &str = "id, user_id, family_id, refresh_token_hash, device, ip, expires_at, created_at, rotated_at, revoked_at";
+
+pub async fn create_session(pool: &PgPool, new_session: CreateSession) -> Result<Session, sqlx::Error> {
+    let query = format!(
+        "INSERT INTO sessions (user_id, family_id, refresh_token_hash, device, ip, expires_at) VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+        SESSION_COLUMNS
+    );
+    let rec = sqlx::query_as::<_, Session>(&query)
+        .bind(new_session.user_id)
+        .bind(new_session.family_id)
+        .bind(new_session.refresh_token_hash)
+        .bind(new_session.device)
+        .bind(new_session.ip)
+        .bind(new_session.expires_at)
+        .fetch_one(pool)
+        .await?;
+    Ok(rec)
+}
+
+pub async fn get_session_by_hash(pool: &PgPool, refresh_token_hash: &str) -> Result<Session, sqlx::Error> {
+    let query = format!("SELECT {} FROM sessions WHERE refresh_token_hash = $1", SESSION_COLUMNS);
+    let session = sqlx::query_as::<_, Session>(&query)
+        .bind(refresh_token_hash)
+        .fetch_one(pool)
+        .await?;
+    Ok(session)
+}
+
+// Mark a session as rotated. Returns false when another request already rotated
+// or revoked it, which callers must treat as refresh token reuse.
+pub async fn rotate_session(pool: &PgPool, id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE sessions SET rotated_at = NOW() WHERE id = $1 AND rotated_at IS NULL AND revoked_at IS NULL"
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+// Revoke every session descending from the same login.
+pub async fn revoke_family(pool: &PgPool, family_id: &str) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE sessions SET revoked_at = NOW() WHERE family_id = $1 AND revoked_at IS NULL"
+    )
+    .bind(family_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn revoke_user_sessions(pool: &PgPool, user_id: i32) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let revoked = end_user_sessions(&mut tx, user_id).await?;
+    tx.commit().await?;
+    Ok(revoked)
+}
+
+// revoke_user_sessions inside a transaction the caller commits.
+pub async fn end_user_sessions(tx: &mut Transaction<'_, Postgres>, user_id: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE sessions SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL"
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -1,11 +1,47 @@
 use crate::internal::domain::entities::items::items::{CreateItem, Item, UpdateItem};
-use crate::internal::pkg::utils::pagination::PaginationRequest;
+use crate::internal::pkg::query::cursor::{CountMode, CursorPage};
+use crate::internal::pkg::query::query::{Column, ColumnKind, ListQuery, Schema};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serde_json::{json, Value};
 use sqlx::postgres::PgPool;
-use std::collections::HashMap;
+
+// Fields the item list can return, filter and sort on.
+pub static ITEM_SCHEMA: Schema = Schema {
+    table: "items",
+    columns: &[
+        Column { name: "id", sql: "id", kind: ColumnKind::Integer },
+        Column { name: "name", sql: "name", kind: ColumnKind::Text },
+        Column { name: "description", sql: "description", kind: ColumnKind::Text },
+        Column { name: "version", sql: "version", kind: ColumnKind::Integer },
+        Column { name: "createdAt", sql: "created_at", kind: ColumnKind::Timestamp },
+        Column { name: "updatedAt", sql: "updated_at", kind: ColumnKind::Timestamp },
+        Column { name: "createdBy", sql: "created_by", kind: ColumnKind::Integer },
+        Column { name: "updatedBy", sql: "updated_by", kind: ColumnKind::Integer },
+        Column { name: "nameLength", sql: "char_length(name)", kind: ColumnKind::Integer },
+        // Only set while searching with `q`.
+        Column { name: "rank", sql: "ts_rank(search_vector, search.search_query)", kind: ColumnKind::Float },
+        Column {
+            name: "headline",
+            sql: "ts_headline(search.search_config, name || coalesce(': ' || description, ''), search.search_query, 'StartSel=<mark>, StopSel=</mark>, MaxFragments=2')",
+            kind: ColumnKind::Text,
+        },
+        // Only set on items in the trash.
+        Column { name: "deletedAt", sql: "deleted_at", kind: ColumnKind::Timestamp },
+        Column { name: "deletedBy", sql: "deleted_by", kind: ColumnKind::Integer },
+    ],
+    default_fields: &["id", "name", "description"],
+    default_sort: "id",
+    key: "id",
+    includes: &["creator"],
+    search: Some("search_vector"),
+    deleted: Some("deleted_at"),
+};
 
 #[allow(dead_code)]
 pub enum DeleteItemError {
     NotFound,
+    VersionMismatch,
     DatabaseError(sqlx::Error),
 }
 
@@ -15,69 +51,95 @@ impl From<sqlx::Error> for DeleteItemError {
     }
 }
 
-// Create a new item
-pub async fn create_item(pool: &PgPool, new_item: CreateItem) -> Result<Item, sqlx::Error> {
+pub enum UpdateItemError {
+    NotFound,
+    VersionMismatch,
+    DatabaseError(sqlx::Error),
+}
+
+impl From<sqlx::Error> for UpdateItemError {
+    fn from(err: sqlx::Error) -> Self {
+        UpdateItemError::DatabaseError(err)
+    }
+}
+
+// Item storage, shared with handlers as `web::Data<dyn ItemRepository>`. The functions
+// below are the Postgres queries behind PgItemRepository. MemoryItemRepository, which
+// the tests run on, keeps items in memory with the same semantics except for what
+// only Postgres knows:
+// - text compares and sorts by code point, as under the C collation, where Postgres
+//   uses the collation of the database;
+// - a search matches words as written, without the stemming and stop words of
+//   SEARCH_LANGUAGE;
+// - `rank` counts the matching words, weighted like the search vector, so name matches
+//   rank above description matches as with ts_rank but the values differ;
+// - `headline` marks every matching word of the text where ts_headline picks fragments.
+#[async_trait]
+pub trait ItemRepository: Send + Sync {
+    async fn create_item(&self, new_item: CreateItem, created_by: Option<i32>) -> Result<Item, sqlx::Error>;
+    async fn get_items(&self, query: ListQuery, includes: &[&str]) -> Result<(Vec<Value>, i64), sqlx::Error>;
+    async fn get_items_page(&self, query: ListQuery, includes: &[&str], count: CountMode) -> Result<(CursorPage<Value>, Option<i64>), sqlx::Error>;
+    async fn get_item(&self, item_id: i32) -> Result<Item, sqlx::Error>;
+    async fn get_item_name(&self, item_name: &str) -> Result<Item, sqlx::Error>;
+    async fn update_item(&self, item_id: i32, update: UpdateItem, versions: Option<Vec<i32>>, updated_by: Option<i32>) -> Result<Item, UpdateItemError>;
+    async fn delete_item(&self, item_id: i32, deleted_by: Option<i32>, versions: Option<Vec<i32>>) -> Result<(), DeleteItemError>;
+    async fn get_deleted_item(&self, item_id: i32) -> Result<Item, sqlx::Error>;
+    async fn restore_item(&self, item_id: i32, restored_by: Option<i32>) -> Result<Item, sqlx::Error>;
+    async fn purge_deleted_items(&self, deleted_before: NaiveDateTime) -> Result<u64, sqlx::Error>;
+}
+
+// Create a new item, `created_by` is the user creating it when known.
+pub async fn create_item(pool: &PgPool, new_item: CreateItem, created_by: Option<i32>) -> Result<Item, sqlx::Error> {
     let rec = sqlx::query_as::<_, Item>(
-        "INSERT INTO items (name, description) VALUES ($1, $2) RETURNING id, name, description",
+        "INSERT INTO items (name, description, created_by, updated_by) VALUES ($1, $2, $3, $3) RETURNING id, name, description, version, created_at, updated_at, created_by, updated_by",
     )
     .bind(new_item.name)
     .bind(new_item.description)
+    .bind(created_by)
     .fetch_one(pool)
     .await?;
     Ok(rec)
 }
 
-pub async fn get_items(
-    pool: &PgPool,
-    pagination: PaginationRequest,
-    filter: HashMap<String, String>
-) -> Result<(Vec<Item>, i64), sqlx::Error> {
-    let valid_sort = match pagination.field.as_str() {
-        "id" | "name" | "description" => pagination.field.clone(),
-        _ => "id".to_string(),
-    };
-    let valid_order = if pagination.sort.to_uppercase() == "DESC" { "DESC" } else { "ASC" };
-    let item_limit = pagination.limit;
-    let item_offset = (pagination.page - 1) * pagination.limit;
-
-    // Build dynamic WHERE clause based on filter parameters
-    let mut where_clauses = Vec::new();
-    if let Some(id) = filter.get("id") {
-        // Assuming the id is stored as integer, we cast it to text for comparison
-        where_clauses.push(format!("CAST(id AS TEXT) = '{}'", id));
-    }
-    if let Some(name) = filter.get("name") {
-        where_clauses.push(format!("name ILIKE '%{}%'", name));
-    }
-    if let Some(description) = filter.get("description") {
-        where_clauses.push(format!("description ILIKE '%{}%'", description));
-    }
-    let where_clause = if where_clauses.is_empty() {
-        "".to_string()
-    } else {
-        format!("WHERE {}", where_clauses.join(" AND "))
-    };
-    
-    let query = format!(
-        "SELECT id, name, description FROM items {} ORDER BY {} {} LIMIT {} OFFSET {}",
-        where_clause, valid_sort, valid_order, item_limit, item_offset
-    );
-    let items = sqlx::query_as::<_, Item>(&query)
+// Embeds the requested relations into listed items.
+async fn embed(pool: &PgPool, items: &mut [Value], includes: &[&str]) -> Result<(), sqlx::Error> {
+    if includes.contains(&"creator") {
+        let ids: Vec<i32> = items.iter().filter_map(|item| item["id"].as_i64()).map(|id| id as i32).collect();
+        let creators: Vec<(i32, i32, String)> = sqlx::query_as(
+            "SELECT i.id, u.id, u.username FROM items i JOIN users u ON u.id = i.created_by WHERE i.id = ANY($1)"
+        )
+        .bind(&ids)
         .fetch_all(pool)
         .await?;
-    let count_query = format!(
-        "SELECT COUNT(*) FROM items {}",
-        where_clause
-    );
-    let count: i64 = sqlx::query_scalar(&count_query)
-        .fetch_one(pool)
-        .await?;
+        for item in items.iter_mut() {
+            let id = item["id"].as_i64();
+            item["creator"] = match creators.iter().find(|(item_id, _, _)| Some(i64::from(*item_id)) == id) {
+                Some((_, user_id, username)) => json!({ "id": user_id, "username": username }),
+                None => Value::Null,
+            };
+        }
+    }
+    Ok(())
+}
+
+pub async fn get_items(pool: &PgPool, query: ListQuery, includes: &[&str]) -> Result<(Vec<Value>, i64), sqlx::Error> {
+    let mut items = query.fetch_all(pool).await?;
+    embed(pool, &mut items, includes).await?;
+    let count = query.total(pool, CountMode::Exact).await?.unwrap_or_default();
     Ok((items, count))
 }
 
+// Keyset variant of get_items for large tables, the total is only computed when asked for.
+pub async fn get_items_page(pool: &PgPool, query: ListQuery, includes: &[&str], count: CountMode) -> Result<(CursorPage<Value>, Option<i64>), sqlx::Error> {
+    let mut page = query.fetch_page(pool).await?;
+    embed(pool, &mut page.items, includes).await?;
+    let total = query.total(pool, count).await?;
+    Ok((page, total))
+}
+
 // Retrieve a single item by id
 pub async fn get_item(pool: &PgPool, item_id: i32) -> Result<Item, sqlx::Error> {
-    let item = sqlx::query_as::<_, Item>("SELECT id, name, description FROM items WHERE id = $1")
+    let item = sqlx::query_as::<_, Item>("SELECT id, name, description, version, created_at, updated_at, created_by, updated_by FROM items WHERE id = $1 AND deleted_at IS NULL")
         .bind(item_id)
         .fetch_one(pool)
         .await?;
@@ -86,45 +148,97 @@ pub async fn get_item(pool: &PgPool, item_id: i32) -> Result<Item, sqlx::Error>
 
 // Retrieve a single item by name
 pub async fn get_item_name(pool: &PgPool, item_name: &str) -> Result<Item, sqlx::Error> {
-    let item = sqlx::query_as::<_, Item>("SELECT id, name, description FROM items WHERE name = $1")
+    let item = sqlx::query_as::<_, Item>("SELECT id, name, description, version, created_at, updated_at, created_by, updated_by FROM items WHERE name = $1 AND deleted_at IS NULL")
         .bind(item_name)
         .fetch_one(pool)
         .await?;
     Ok(item)
 }
 
-// Update an item by id (partial update)
+// Update an item by id (partial update). With `versions` the item has to be at one
+// of them, the If-Match of the request. `updated_by` is the user making the change.
 pub async fn update_item(
     pool: &PgPool,
     item_id: i32,
     update: UpdateItem,
-) -> Result<Item, sqlx::Error> {
-    // Retrieve current item first.
-    let current = get_item(pool, item_id).await?;
-    let new_name = update.name.unwrap_or(current.name);
-    let new_description = update.description.or(current.description);
-
+    versions: Option<Vec<i32>>,
+    updated_by: Option<i32>,
+) -> Result<Item, UpdateItemError> {
     let item = sqlx::query_as::<_, Item>(
-        "UPDATE items SET name = $1, description = $2 WHERE id = $3 RETURNING id, name, description"
+        "UPDATE items SET name = COALESCE($1, name), description = COALESCE($2, description), updated_by = $3, version = version + 1 \
+         WHERE id = $4 AND deleted_at IS NULL AND ($5::INTEGER[] IS NULL OR version = ANY($5)) RETURNING id, name, description, version, created_at, updated_at, created_by, updated_by"
     )
-    .bind(new_name)
-    .bind(new_description)
+    .bind(update.name)
+    .bind(update.description)
+    .bind(updated_by)
     .bind(item_id)
-    .fetch_one(pool)
+    .bind(versions)
+    .fetch_optional(pool)
     .await?;
-    Ok(item)
+
+    match item {
+        Some(item) => Ok(item),
+        None if item_exists(pool, item_id).await? => Err(UpdateItemError::VersionMismatch),
+        None => Err(UpdateItemError::NotFound),
+    }
 }
 
-// Delete an item by id
-pub async fn delete_item(pool: &PgPool, item_id: i32) -> Result<(), DeleteItemError> {
-    let result = sqlx::query("DELETE FROM items WHERE id = $1")
+// Tells a version mismatch from a missing item once a conditional write matched no row.
+async fn item_exists(pool: &PgPool, item_id: i32) -> Result<bool, sqlx::Error> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM items WHERE id = $1 AND deleted_at IS NULL)")
         .bind(item_id)
-        .execute(pool)
+        .fetch_one(pool)
         .await?;
+    Ok(exists)
+}
+
+// Move an item to the trash. `deleted_by` is the user doing it, when known, and
+// `versions` works as in update_item.
+pub async fn delete_item(pool: &PgPool, item_id: i32, deleted_by: Option<i32>, versions: Option<Vec<i32>>) -> Result<(), DeleteItemError> {
+    let result = sqlx::query(
+        "UPDATE items SET deleted_at = now(), deleted_by = $1, version = version + 1 \
+         WHERE id = $2 AND deleted_at IS NULL AND ($3::INTEGER[] IS NULL OR version = ANY($3))"
+    )
+    .bind(deleted_by)
+    .bind(item_id)
+    .bind(versions)
+    .execute(pool)
+    .await?;
 
     if result.rows_affected() == 0 {
-        return Err(DeleteItemError::NotFound);
+        return Err(if item_exists(pool, item_id).await? { DeleteItemError::VersionMismatch } else { DeleteItemError::NotFound });
     }
 
     Ok(())
 }
+
+// Retrieve a single item from the trash by id
+pub async fn get_deleted_item(pool: &PgPool, item_id: i32) -> Result<Item, sqlx::Error> {
+    let item = sqlx::query_as::<_, Item>("SELECT id, name, description, version, created_at, updated_at, created_by, updated_by FROM items WHERE id = $1 AND deleted_at IS NOT NULL")
+        .bind(item_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(item)
+}
+
+// Take an item back out of the trash, `restored_by` becomes its last editor.
+pub async fn restore_item(pool: &PgPool, item_id: i32, restored_by: Option<i32>) -> Result<Item, sqlx::Error> {
+    let item = sqlx::query_as::<_, Item>(
+        "UPDATE items SET deleted_at = NULL, deleted_by = NULL, updated_by = $1, version = version + 1 \
+         WHERE id = $2 AND deleted_at IS NOT NULL RETURNING id, name, description, version, created_at, updated_at, created_by, updated_by"
+    )
+    .bind(restored_by)
+    .bind(item_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(item)
+}
+
+// Hard delete items that have been in the trash since before `deleted_before`.
+pub async fn purge_deleted_items(pool: &PgPool, deleted_before: NaiveDateTime) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM items WHERE deleted_at < $1")
+        .bind(deleted_before)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,427 @@
+use crate::internal::application::repositories::items::items::{DeleteItemError, ItemRepository, UpdateItemError};
+use crate::internal::application::repositories::users::memory::MemoryUserRepository;
+use crate::internal::domain::entities::items::items::{CreateItem, Item, UpdateItem};
+use crate::internal::pkg::database::sql::memory::MemoryDatabaseError;
+use crate::internal::pkg::query::cursor::{CountMode, CursorPage};
+use crate::internal::pkg::query::filter::FilterValue;
+use crate::internal::pkg::query::memory::MemoryRow;
+use crate::internal::pkg::query::query::ListQuery;
+use crate::internal::pkg::query::search::Search;
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+// Column limit of the items table.
+const MAX_NAME_LEN: usize = 255;
+
+// Weight of a description match against a name match, as set in the search vector.
+const DESCRIPTION_WEIGHT: f64 = 0.4;
+
+// An item as the items table holds it.
+#[derive(Debug, Clone)]
+struct StoredItem {
+    id: i32,
+    name: String,
+    description: Option<String>,
+    version: i32,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    created_by: Option<i32>,
+    updated_by: Option<i32>,
+    deleted_at: Option<NaiveDateTime>,
+    deleted_by: Option<i32>,
+}
+
+impl StoredItem {
+    // Every write bumps the version, which moves updated_at like the trigger does.
+    fn bump(&mut self) {
+        self.version += 1;
+        self.updated_at = Utc::now().naive_utc();
+    }
+
+    // The columns of ITEM_SCHEMA, with the rank and headline of the current search,
+    // and the text searches match under the search vector.
+    fn row(&self, search: Option<&Search>) -> MemoryRow {
+        let description = self.description.as_deref().unwrap_or_default();
+        let mut row: MemoryRow = [
+            ("id", Some(FilterValue::Integer(self.id.into()))),
+            ("name", Some(FilterValue::Text(self.name.clone()))),
+            ("description", self.description.clone().map(FilterValue::Text)),
+            ("version", Some(FilterValue::Integer(self.version.into()))),
+            ("createdAt", Some(FilterValue::Timestamp(self.created_at))),
+            ("updatedAt", Some(FilterValue::Timestamp(self.updated_at))),
+            ("createdBy", self.created_by.map(|id| FilterValue::Integer(id.into()))),
+            ("updatedBy", self.updated_by.map(|id| FilterValue::Integer(id.into()))),
+            ("nameLength", Some(FilterValue::Integer(self.name.chars().count() as i64))),
+            ("deletedAt", self.deleted_at.map(FilterValue::Timestamp)),
+            ("deletedBy", self.deleted_by.map(|id| FilterValue::Integer(id.into()))),
+            ("search_vector", Some(FilterValue::Text(format!("{} {}", self.name, description)))),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect();
+        if let Some(search) = search {
+            let rank = search.occurrences(&self.name) as f64 + DESCRIPTION_WEIGHT * search.occurrences(description) as f64;
+            let text = match &self.description {
+                Some(description) => format!("{}: {}", self.name, description),
+                None => self.name.clone(),
+            };
+            row.insert("rank", FilterValue::Float(rank));
+            row.insert("headline", FilterValue::Text(search.highlight(&text)));
+        }
+        row
+    }
+}
+
+#[derive(Default)]
+struct Items {
+    rows: Vec<StoredItem>,
+    last_id: i32,
+}
+
+impl Items {
+    fn live(&mut self, id: i32) -> Option<&mut StoredItem> {
+        self.rows.iter_mut().find(|item| item.id == id && item.deleted_at.is_none())
+    }
+
+    fn deleted(&mut self, id: i32) -> Option<&mut StoredItem> {
+        self.rows.iter_mut().find(|item| item.id == id && item.deleted_at.is_some())
+    }
+}
+
+// Items kept in memory with the semantics of PgItemRepository, for exercising the
+// use cases without a database. The users they reference live in `users`.
+pub struct MemoryItemRepository {
+    items: Mutex<Items>,
+    users: Arc<MemoryUserRepository>,
+}
+
+impl MemoryItemRepository {
+    pub fn new(users: Arc<MemoryUserRepository>) -> Self {
+        MemoryItemRepository { items: Mutex::new(Items::default()), users }
+    }
+
+    fn items(&self) -> MutexGuard<'_, Items> {
+        self.items.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // The user behind a write has to exist.
+    fn check_reference(&self, by: Option<i32>, constraint: &str) -> Result<(), sqlx::Error> {
+        match by {
+            Some(by) if !self.users.contains(by) => Err(MemoryDatabaseError::foreign_key_violation("items", constraint)),
+            _ => Ok(()),
+        }
+    }
+
+    // A stored item with the references to purged users cleared.
+    fn resolve(&self, mut item: StoredItem) -> StoredItem {
+        for reference in [&mut item.created_by, &mut item.updated_by, &mut item.deleted_by] {
+            *reference = reference.filter(|id| self.users.contains(*id));
+        }
+        item
+    }
+
+    fn item(&self, item: &StoredItem) -> Item {
+        let item = self.resolve(item.clone());
+        Item {
+            id: item.id,
+            name: item.name,
+            description: item.description,
+            version: item.version,
+            created_at: item.created_at,
+            updated_at: item.updated_at,
+            created_by: item.created_by,
+            updated_by: item.updated_by,
+        }
+    }
+
+    fn rows(&self, search: Option<&Search>) -> Vec<MemoryRow> {
+        let stored = self.items().rows.clone();
+        stored.into_iter().map(|item| self.resolve(item).row(search)).collect()
+    }
+
+    // Embeds the requested relations into listed items.
+    fn embed(&self, items: &mut [Value], includes: &[&str]) {
+        if includes.contains(&"creator") {
+            let stored = self.items().rows.clone();
+            for item in items.iter_mut() {
+                let id = item["id"].as_i64();
+                let creator = stored.iter()
+                    .find(|stored| Some(i64::from(stored.id)) == id)
+                    .and_then(|stored| stored.created_by)
+                    .and_then(|user_id| Some(json!({ "id": user_id, "username": self.users.username(user_id)? })));
+                item["creator"] = creator.unwrap_or(Value::Null);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ItemRepository for MemoryItemRepository {
+    async fn create_item(&self, new_item: CreateItem, created_by: Option<i32>) -> Result<Item, sqlx::Error> {
+        if new_item.name.chars().count() > MAX_NAME_LEN {
+            return Err(MemoryDatabaseError::value_too_long(MAX_NAME_LEN));
+        }
+        self.check_reference(created_by, "items_created_by_fkey")?;
+        let mut items = self.items();
+        let now = Utc::now().naive_utc();
+        let item = StoredItem {
+            id: items.last_id + 1,
+            name: new_item.name,
+            description: new_item.description,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            created_by,
+            updated_by: created_by,
+            deleted_at: None,
+            deleted_by: None,
+        };
+        items.last_id = item.id;
+        items.rows.push(item.clone());
+        drop(items);
+        Ok(self.item(&item))
+    }
+
+    async fn get_items(&self, query: ListQuery, includes: &[&str]) -> Result<(Vec<Value>, i64), sqlx::Error> {
+        let rows = self.rows(query.searching());
+        let mut items = query.fetch_all_rows(&rows);
+        self.embed(&mut items, includes);
+        Ok((items, query.total_rows(&rows, CountMode::Exact).unwrap_or_default()))
+    }
+
+    async fn get_items_page(&self, query: ListQuery, includes: &[&str], count: CountMode) -> Result<(CursorPage<Value>, Option<i64>), sqlx::Error> {
+        let rows = self.rows(query.searching());
+        let mut page = query.fetch_page_rows(&rows);
+        self.embed(&mut page.items, includes);
+        Ok((page, query.total_rows(&rows, count)))
+    }
+
+    async fn get_item(&self, item_id: i32) -> Result<Item, sqlx::Error> {
+        let item = self.items().live(item_id).cloned().ok_or(sqlx::Error::RowNotFound)?;
+        Ok(self.item(&item))
+    }
+
+    async fn get_item_name(&self, item_name: &str) -> Result<Item, sqlx::Error> {
+        let item = self.items().rows.iter()
+            .find(|item| item.name == item_name && item.deleted_at.is_none())
+            .cloned()
+            .ok_or(sqlx::Error::RowNotFound)?;
+        Ok(self.item(&item))
+    }
+
+    async fn update_item(&self, item_id: i32, update: UpdateItem, versions: Option<Vec<i32>>, updated_by: Option<i32>) -> Result<Item, UpdateItemError> {
+        if update.name.as_ref().is_some_and(|name| name.chars().count() > MAX_NAME_LEN) {
+            return Err(MemoryDatabaseError::value_too_long(MAX_NAME_LEN).into());
+        }
+        self.check_reference(updated_by, "items_updated_by_fkey")?;
+        let mut items = self.items();
+        let Some(item) = items.live(item_id) else {
+            return Err(UpdateItemError::NotFound);
+        };
+        if versions.is_some_and(|versions| !versions.contains(&item.version)) {
+            return Err(UpdateItemError::VersionMismatch);
+        }
+        if let Some(name) = update.name {
+            item.name = name;
+        }
+        if let Some(description) = update.description {
+            item.description = Some(description);
+        }
+        item.updated_by = updated_by;
+        item.bump();
+        let item = item.clone();
+        drop(items);
+        Ok(self.item(&item))
+    }
+
+    async fn delete_item(&self, item_id: i32, deleted_by: Option<i32>, versions: Option<Vec<i32>>) -> Result<(), DeleteItemError> {
+        self.check_reference(deleted_by, "items_deleted_by_fkey")?;
+        let mut items = self.items();
+        let Some(item) = items.live(item_id) else {
+            return Err(DeleteItemError::NotFound);
+        };
+        if versions.is_some_and(|versions| !versions.contains(&item.version)) {
+            return Err(DeleteItemError::VersionMismatch);
+        }
+        item.bump();
+        item.deleted_at = Some(item.updated_at);
+        item.deleted_by = deleted_by;
+        Ok(())
+    }
+
+    async fn get_deleted_item(&self, item_id: i32) -> Result<Item, sqlx::Error> {
+        let item = self.items().deleted(item_id).cloned().ok_or(sqlx::Error::RowNotFound)?;
+        Ok(self.item(&item))
+    }
+
+    async fn restore_item(&self, item_id: i32, restored_by: Option<i32>) -> Result<Item, sqlx::Error> {
+        self.check_reference(restored_by, "items_updated_by_fkey")?;
+        let mut items = self.items();
+        let item = items.deleted(item_id).ok_or(sqlx::Error::RowNotFound)?;
+        item.deleted_at = None;
+        item.deleted_by = None;
+        item.updated_by = restored_by;
+        item.bump();
+        let item = item.clone();
+        drop(items);
+        Ok(self.item(&item))
+    }
+
+    async fn purge_deleted_items(&self, deleted_before: NaiveDateTime) -> Result<u64, sqlx::Error> {
+        let mut items = self.items();
+        let count = items.rows.len();
+        items.rows.retain(|item| item.deleted_at.is_none_or(|deleted_at| deleted_at >= deleted_before));
+        Ok((count - items.rows.len()) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::application::repositories::items::items::ITEM_SCHEMA;
+    use crate::internal::application::repositories::items::postgres::PgItemRepository;
+    use crate::internal::application::repositories::users::postgres::PgUserRepository;
+    use crate::internal::application::repositories::users::users::UserRepository;
+    use crate::internal::domain::entities::users::users::CreateUserRequest;
+    use crate::internal::pkg::database::sql::migrate::migrate_up;
+    use crate::internal::pkg::database::sql::scratch::ScratchDatabase;
+
+    // Lowercase ASCII names, and words that are neither stemmed nor stop words, so the
+    // differences to Postgres documented on ItemRepository do not show. Item 4 is deleted.
+    const ITEMS: [(&str, Option<&str>); 7] = [
+        ("cherry", Some("red fruit")),
+        ("apple", Some("red or green fruit")),
+        ("elderberry", None),
+        ("banana", Some("yellow")),
+        ("date", Some("brown fruit")),
+        ("fig", Some("purple")),
+        ("fruit salad", Some("cherry, apple and banana")),
+    ];
+
+    struct Backends {
+        database: ScratchDatabase,
+        postgres: PgItemRepository,
+        memory: MemoryItemRepository,
+    }
+
+    // Both repositories holding ITEMS, created by the same user.
+    async fn backends() -> Backends {
+        let database = ScratchDatabase::create("items").await;
+        migrate_up(&database.pool).await.unwrap();
+        let admin = || CreateUserRequest {
+            username: "admin".to_string(),
+            email: "admin@example.com".to_string(),
+            password: String::new(),
+            name: None,
+            roles: None,
+        };
+        PgUserRepository::new(database.pool.clone()).create_user(admin(), None, None).await.unwrap();
+        let users = Arc::new(MemoryUserRepository::new(&["admin", "user"]));
+        users.create_user(admin(), None, None).await.unwrap();
+
+        let backends = Backends {
+            postgres: PgItemRepository::new(database.pool.clone()),
+            memory: MemoryItemRepository::new(users),
+            database,
+        };
+        for repository in [&backends.postgres as &dyn ItemRepository, &backends.memory] {
+            for (name, description) in ITEMS {
+                let item = CreateItem { name: name.to_string(), description: description.map(str::to_string) };
+                repository.create_item(item, Some(1)).await.unwrap();
+            }
+            assert!(repository.delete_item(4, Some(1), None).await.is_ok());
+        }
+        backends
+    }
+
+    // A list of the fields that do not depend on when an item was written.
+    fn query(filters: &str, sort: &str) -> ListQuery {
+        ListQuery::new(&ITEM_SCHEMA)
+            .fields(ITEM_SCHEMA.parse_fields(Some("name,description,nameLength,createdBy,deletedBy")).unwrap())
+            .filters(ITEM_SCHEMA.parse_filters(filters).unwrap())
+            .order_by(ITEM_SCHEMA.parse_sort(sort).unwrap())
+    }
+
+    fn search(q: &str) -> Option<Search> {
+        Some(Search::parse(q, "english").unwrap())
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn lists_like_postgres() {
+        let backends = backends().await;
+        let cases = [
+            ("", "id"),
+            ("filter[name]=an", "-name"),
+            ("filter[name][like]=*rr*", "name"),
+            ("filter[description][null]=true", "id"),
+            ("filter[description]=FRUIT", "description,id"),
+            ("filter[nameLength][gt]=5&filter[id][ne]=2", "-nameLength,name"),
+            ("filter[name][in]=apple,fig,date", "-name"),
+            ("filter[createdBy]=1&filter[id][lte]=3", "-id"),
+            ("", "description,-id"),
+            ("", "-description,name"),
+        ];
+        for (filters, sort) in cases {
+            for trashed in [false, true] {
+                for (limit, offset) in [(10, 0), (2, 1)] {
+                    let postgres = backends.postgres.get_items(query(filters, sort).trashed(trashed).paginate(limit, offset), &["creator"]).await.unwrap();
+                    let memory = backends.memory.get_items(query(filters, sort).trashed(trashed).paginate(limit, offset), &["creator"]).await.unwrap();
+                    assert_eq!(postgres, memory, "{:?} sorted by {} (trashed: {}, limit: {}, offset: {})", filters, sort, trashed, limit, offset);
+                }
+            }
+        }
+        backends.database.drop().await;
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn searches_like_postgres() {
+        let backends = backends().await;
+        // Name matches rank above description matches, ties go by id.
+        let cases = [("fruit", ""), ("red", ""), ("apple", ""), ("ban*", ""), ("red fruit", ""), ("fruit", "filter[nameLength][lt]=7"), ("grape", "")];
+        for (q, filters) in cases {
+            let postgres = backends.postgres.get_items(query(filters, "-rank,id").search(search(q)), &[]).await.unwrap();
+            let memory = backends.memory.get_items(query(filters, "-rank,id").search(search(q)), &[]).await.unwrap();
+            assert_eq!(postgres, memory, "{:?} with {:?}", q, filters);
+        }
+        backends.database.drop().await;
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn pages_by_cursor_like_postgres() {
+        let backends = backends().await;
+        for sort in ["name", "-id", "description,name", "-nameLength,-name"] {
+            let order = || ITEM_SCHEMA.parse_sort(sort).unwrap();
+            let mut cursor = Some(String::new());
+            let mut pages = 0;
+            while let Some(current) = cursor {
+                let page = |cursor: &str| {
+                    let cursor = (!cursor.is_empty()).then(|| ITEM_SCHEMA.decode_cursor(cursor, &order()).unwrap());
+                    query("", sort).paginate(2, 0).after(cursor)
+                };
+                let (postgres, postgres_total) = backends.postgres.get_items_page(page(&current), &[], CountMode::Exact).await.unwrap();
+                let (memory, memory_total) = backends.memory.get_items_page(page(&current), &[], CountMode::Exact).await.unwrap();
+                assert_eq!(
+                    (&postgres.items, &postgres.next_cursor, &postgres.prev_cursor, postgres_total),
+                    (&memory.items, &memory.next_cursor, &memory.prev_cursor, memory_total),
+                    "page {} sorted by {}", pages, sort,
+                );
+
+                // One step back from every page after the first.
+                if let Some(prev) = &postgres.prev_cursor {
+                    let (postgres, _) = backends.postgres.get_items_page(page(prev), &[], CountMode::None).await.unwrap();
+                    let (memory, _) = backends.memory.get_items_page(page(prev), &[], CountMode::None).await.unwrap();
+                    assert_eq!((postgres.items, postgres.next_cursor), (memory.items, memory.next_cursor), "before page {} sorted by {}", pages, sort);
+                }
+                cursor = postgres.next_cursor;
+                pages += 1;
+            }
+            assert_eq!(pages, 3, "sorted by {}", sort);
+        }
+        backends.database.drop().await;
+    }
+}
@@ -1 +1,4 @@
-pub mod items;
\ No newline at end of file
+pub mod items;
+#[cfg(test)]
+pub mod memory;
+pub mod postgres;
\ No newline at end of file
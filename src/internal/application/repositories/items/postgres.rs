@@ -0,0 +1,62 @@
+use crate::internal::application::repositories::items::items::{self, DeleteItemError, ItemRepository, UpdateItemError};
+use crate::internal::domain::entities::items::items::{CreateItem, Item, UpdateItem};
+use crate::internal::pkg::query::cursor::{CountMode, CursorPage};
+use crate::internal::pkg::query::query::ListQuery;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use sqlx::postgres::PgPool;
+
+// The items table.
+pub struct PgItemRepository {
+    pool: PgPool,
+}
+
+impl PgItemRepository {
+    pub fn new(pool: PgPool) -> Self {
+        PgItemRepository { pool }
+    }
+}
+
+#[async_trait]
+impl ItemRepository for PgItemRepository {
+    async fn create_item(&self, new_item: CreateItem, created_by: Option<i32>) -> Result<Item, sqlx::Error> {
+        items::create_item(&self.pool, new_item, created_by).await
+    }
+
+    async fn get_items(&self, query: ListQuery, includes: &[&str]) -> Result<(Vec<Value>, i64), sqlx::Error> {
+        items::get_items(&self.pool, query, includes).await
+    }
+
+    async fn get_items_page(&self, query: ListQuery, includes: &[&str], count: CountMode) -> Result<(CursorPage<Value>, Option<i64>), sqlx::Error> {
+        items::get_items_page(&self.pool, query, includes, count).await
+    }
+
+    async fn get_item(&self, item_id: i32) -> Result<Item, sqlx::Error> {
+        items::get_item(&self.pool, item_id).await
+    }
+
+    async fn get_item_name(&self, item_name: &str) -> Result<Item, sqlx::Error> {
+        items::get_item_name(&self.pool, item_name).await
+    }
+
+    async fn update_item(&self, item_id: i32, update: UpdateItem, versions: Option<Vec<i32>>, updated_by: Option<i32>) -> Result<Item, UpdateItemError> {
+        items::update_item(&self.pool, item_id, update, versions, updated_by).await
+    }
+
+    async fn delete_item(&self, item_id: i32, deleted_by: Option<i32>, versions: Option<Vec<i32>>) -> Result<(), DeleteItemError> {
+        items::delete_item(&self.pool, item_id, deleted_by, versions).await
+    }
+
+    async fn get_deleted_item(&self, item_id: i32) -> Result<Item, sqlx::Error> {
+        items::get_deleted_item(&self.pool, item_id).await
+    }
+
+    async fn restore_item(&self, item_id: i32, restored_by: Option<i32>) -> Result<Item, sqlx::Error> {
+        items::restore_item(&self.pool, item_id, restored_by).await
+    }
+
+    async fn purge_deleted_items(&self, deleted_before: NaiveDateTime) -> Result<u64, sqlx::Error> {
+        items::purge_deleted_items(&self.pool, deleted_before).await
+    }
+}
@@ -1,2 +1,4 @@
 pub mod items;
+pub mod audit;
+pub mod auth;
 pub mod users;
\ No newline at end of file
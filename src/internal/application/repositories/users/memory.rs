@@ -0,0 +1,435 @@
+use crate::internal::application::repositories::auth::roles;
+use crate::internal::application::repositories::users::users::{DeleteUserError, UpdateUserError, UserRepository, MAX_EMAIL_LEN, MAX_NAME_LEN, MAX_USERNAME_LEN};
+use crate::internal::domain::entities::users::users::{CreateUserRequest, CreateUserResponse, DetailUserResponse, UpdateUserRequest, UpdateUserResponse};
+use crate::internal::pkg::database::sql::memory::MemoryDatabaseError;
+use crate::internal::pkg::query::cursor::{CountMode, CursorPage};
+use crate::internal::pkg::query::filter::FilterValue;
+use crate::internal::pkg::query::memory::MemoryRow;
+use crate::internal::pkg::query::query::ListQuery;
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use serde_json::{json, Value};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+// A user as the users table holds it, with the names of its roles and when its
+// refresh sessions were last revoked. The password and the columns only the auth
+// flows read are not kept.
+#[derive(Debug, Clone)]
+struct StoredUser {
+    id: i32,
+    username: String,
+    email: String,
+    name: Option<String>,
+    version: i32,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    created_by: Option<i32>,
+    updated_by: Option<i32>,
+    deleted_at: Option<NaiveDateTime>,
+    deleted_by: Option<i32>,
+    roles: Vec<String>,
+    tokens_revoked_at: Option<NaiveDateTime>,
+    sessions_revoked_at: Option<NaiveDateTime>,
+}
+
+impl StoredUser {
+    fn detail(&self) -> DetailUserResponse {
+        DetailUserResponse {
+            id: self.id,
+            username: self.username.clone(),
+            email: self.email.clone(),
+            name: self.name.clone(),
+            version: self.version,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            created_by: self.created_by,
+            updated_by: self.updated_by,
+        }
+    }
+
+    // The columns of USER_SCHEMA.
+    fn row(&self) -> MemoryRow {
+        [
+            ("id", Some(FilterValue::Integer(self.id.into()))),
+            ("username", Some(FilterValue::Text(self.username.clone()))),
+            ("email", Some(FilterValue::Text(self.email.clone()))),
+            ("name", self.name.clone().map(FilterValue::Text)),
+            ("version", Some(FilterValue::Integer(self.version.into()))),
+            ("createdAt", Some(FilterValue::Timestamp(self.created_at))),
+            ("updatedAt", Some(FilterValue::Timestamp(self.updated_at))),
+            ("createdBy", self.created_by.map(|id| FilterValue::Integer(id.into()))),
+            ("updatedBy", self.updated_by.map(|id| FilterValue::Integer(id.into()))),
+            ("emailDomain", Some(FilterValue::Text(self.email.split('@').nth(1).unwrap_or_default().to_string()))),
+            ("deletedAt", self.deleted_at.map(FilterValue::Timestamp)),
+            ("deletedBy", self.deleted_by.map(|id| FilterValue::Integer(id.into()))),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+
+    // Every write bumps the version, which moves updated_at like the trigger does.
+    fn bump(&mut self) {
+        self.version += 1;
+        self.updated_at = Utc::now().naive_utc();
+    }
+}
+
+#[derive(Default)]
+struct Users {
+    rows: Vec<StoredUser>,
+    last_id: i32,
+}
+
+impl Users {
+    fn live(&mut self, id: i32) -> Option<&mut StoredUser> {
+        self.rows.iter_mut().find(|user| user.id == id && user.deleted_at.is_none())
+    }
+
+    fn deleted(&mut self, id: i32) -> Option<&mut StoredUser> {
+        self.rows.iter_mut().find(|user| user.id == id && user.deleted_at.is_some())
+    }
+
+    // The column limits and the unique indexes on username and email, which only
+    // cover users outside the trash.
+    fn check(&self, id: i32, username: &str, email: &str, name: Option<&str>) -> Result<(), sqlx::Error> {
+        for (value, max_len) in [(Some(username), MAX_USERNAME_LEN), (Some(email), MAX_EMAIL_LEN), (name, MAX_NAME_LEN)] {
+            if value.is_some_and(|value| value.chars().count() > max_len) {
+                return Err(MemoryDatabaseError::value_too_long(max_len));
+            }
+        }
+        let others = || self.rows.iter().filter(|user| user.id != id && user.deleted_at.is_none());
+        if others().any(|user| user.username == username) {
+            return Err(MemoryDatabaseError::unique_violation("users_username_key"));
+        }
+        if others().any(|user| user.email == email) {
+            return Err(MemoryDatabaseError::unique_violation("users_email_key"));
+        }
+        Ok(())
+    }
+
+    // The user behind a write has to exist.
+    fn check_reference(&self, by: Option<i32>, constraint: &str) -> Result<(), sqlx::Error> {
+        match by {
+            Some(by) if !self.rows.iter().any(|user| user.id == by) => Err(MemoryDatabaseError::foreign_key_violation("users", constraint)),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Users and their roles kept in memory with the semantics of PgUserRepository,
+// for exercising the use cases without a database.
+pub struct MemoryUserRepository {
+    users: Mutex<Users>,
+    roles: Vec<String>,
+}
+
+impl MemoryUserRepository {
+    // `roles` are the role names that exist, "admin" and "user" in a migrated database.
+    pub fn new(roles: &[&str]) -> Self {
+        MemoryUserRepository {
+            users: Mutex::new(Users::default()),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+        }
+    }
+
+    fn users(&self) -> MutexGuard<'_, Users> {
+        self.users.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Whether the user exists, in the trash or not. References to a purged user read
+    // as NULL, as the foreign keys set them.
+    pub fn contains(&self, id: i32) -> bool {
+        self.users().rows.iter().any(|user| user.id == id)
+    }
+
+    pub fn username(&self, id: i32) -> Option<String> {
+        self.users().rows.iter().find(|user| user.id == id).map(|user| user.username.clone())
+    }
+
+    // When the user's access tokens and refresh sessions were last revoked.
+    pub fn revoked_at(&self, id: i32) -> (Option<NaiveDateTime>, Option<NaiveDateTime>) {
+        self.users().rows.iter()
+            .find(|user| user.id == id)
+            .map_or((None, None), |user| (user.tokens_revoked_at, user.sessions_revoked_at))
+    }
+
+    // Embeds the requested relations into listed users.
+    fn embed(&self, users: &mut [Value], includes: &[&str]) {
+        if includes.contains(&"roles") {
+            let stored = self.users();
+            for user in users.iter_mut() {
+                let id = user["id"].as_i64();
+                let names = stored.rows.iter()
+                    .find(|stored| Some(i64::from(stored.id)) == id)
+                    .map(|stored| stored.roles.clone())
+                    .unwrap_or_default();
+                user["roles"] = json!(names);
+            }
+        }
+    }
+
+    fn rows(&self) -> Vec<MemoryRow> {
+        self.users().rows.iter().map(StoredUser::row).collect()
+    }
+
+    // The existing roles among `user_roles`, by name. Unknown names are skipped, as
+    // the insert from the roles table does.
+    fn known_roles(&self, user_roles: &[String]) -> Vec<String> {
+        let mut known: Vec<String> = self.roles.iter().filter(|role| user_roles.contains(role)).cloned().collect();
+        known.sort();
+        known
+    }
+}
+
+#[async_trait]
+impl UserRepository for MemoryUserRepository {
+    async fn create_user(&self, new_user: CreateUserRequest, _email_verified_at: Option<NaiveDateTime>, created_by: Option<i32>) -> Result<CreateUserResponse, sqlx::Error> {
+        let user_roles = new_user.roles.unwrap_or_else(|| vec![roles::DEFAULT_ROLE.to_string()]);
+        let mut users = self.users();
+        let id = users.last_id + 1;
+        users.check(id, &new_user.username, &new_user.email, new_user.name.as_deref())?;
+        users.check_reference(created_by, "users_created_by_fkey")?;
+        let now = Utc::now().naive_utc();
+        let user = StoredUser {
+            id,
+            username: new_user.username,
+            email: new_user.email,
+            name: new_user.name,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            created_by,
+            updated_by: created_by,
+            deleted_at: None,
+            deleted_by: None,
+            roles: self.known_roles(&user_roles),
+            tokens_revoked_at: None,
+            sessions_revoked_at: None,
+        };
+        users.last_id = id;
+        users.rows.push(user.clone());
+        Ok(CreateUserResponse { id, username: user.username, email: user.email, name: user.name, version: user.version })
+    }
+
+    async fn get_users(&self, query: ListQuery, includes: &[&str]) -> Result<(Vec<Value>, i64), sqlx::Error> {
+        let rows = self.rows();
+        let mut users = query.fetch_all_rows(&rows);
+        self.embed(&mut users, includes);
+        Ok((users, query.total_rows(&rows, CountMode::Exact).unwrap_or_default()))
+    }
+
+    async fn get_users_page(&self, query: ListQuery, includes: &[&str], count: CountMode) -> Result<(CursorPage<Value>, Option<i64>), sqlx::Error> {
+        let rows = self.rows();
+        let mut page = query.fetch_page_rows(&rows);
+        self.embed(&mut page.items, includes);
+        Ok((page, query.total_rows(&rows, count)))
+    }
+
+    async fn get_user_detail(&self, id: i32) -> Result<DetailUserResponse, sqlx::Error> {
+        self.users().live(id).map(|user| user.detail()).ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn get_user_username(&self, username: &str) -> Result<DetailUserResponse, sqlx::Error> {
+        self.users().rows.iter()
+            .find(|user| user.username == username && user.deleted_at.is_none())
+            .map(StoredUser::detail)
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn get_user_email(&self, email: &str) -> Result<DetailUserResponse, sqlx::Error> {
+        self.users().rows.iter()
+            .find(|user| user.email == email && user.deleted_at.is_none())
+            .map(StoredUser::detail)
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn update_user(&self, id: i32, req: UpdateUserRequest, versions: Option<Vec<i32>>, updated_by: Option<i32>) -> Result<UpdateUserResponse, UpdateUserError> {
+        let mut users = self.users();
+        let Some(user) = users.live(id) else {
+            return Err(UpdateUserError::NotFound);
+        };
+        if versions.is_some_and(|versions| !versions.contains(&user.version)) {
+            return Err(UpdateUserError::VersionMismatch);
+        }
+        let mut updated = user.clone();
+        updated.username = req.username.unwrap_or(updated.username);
+        updated.email = req.email.unwrap_or(updated.email);
+        updated.name = req.name.or(updated.name);
+        let renamed = updated.username != user.username;
+        let assigned = req.roles.as_deref().map(|user_roles| self.known_roles(user_roles));
+        let roles_changed = assigned.as_ref().is_some_and(|assigned| *assigned != user.roles);
+        users.check(id, &updated.username, &updated.email, updated.name.as_deref())?;
+        users.check_reference(updated_by, "users_updated_by_fkey")?;
+
+        updated.updated_by = updated_by;
+        updated.bump();
+        if renamed {
+            updated.tokens_revoked_at = Some(updated.updated_at);
+        }
+        if req.password.as_deref().is_some_and(|password| !password.trim().is_empty()) {
+            updated.sessions_revoked_at = Some(updated.updated_at);
+        }
+        if let Some(assigned) = assigned {
+            updated.roles = assigned;
+        }
+        if roles_changed {
+            updated.tokens_revoked_at = Some(updated.updated_at);
+            updated.sessions_revoked_at = Some(updated.updated_at);
+        }
+        let response = UpdateUserResponse {
+            id,
+            username: updated.username.clone(),
+            email: updated.email.clone(),
+            name: updated.name.clone(),
+            version: updated.version,
+        };
+        if let Some(user) = users.live(id) {
+            *user = updated;
+        }
+        Ok(response)
+    }
+
+    async fn delete_user(&self, id: i32, deleted_by: Option<i32>, versions: Option<Vec<i32>>) -> Result<(), DeleteUserError> {
+        let mut users = self.users();
+        users.check_reference(deleted_by, "users_deleted_by_fkey")?;
+        let Some(user) = users.live(id) else {
+            return Err(DeleteUserError::NotFound);
+        };
+        if versions.is_some_and(|versions| !versions.contains(&user.version)) {
+            return Err(DeleteUserError::VersionMismatch);
+        }
+        user.bump();
+        user.deleted_at = Some(user.updated_at);
+        user.deleted_by = deleted_by;
+        user.tokens_revoked_at = Some(user.updated_at);
+        user.sessions_revoked_at = Some(user.updated_at);
+        Ok(())
+    }
+
+    async fn get_deleted_user(&self, id: i32) -> Result<DetailUserResponse, sqlx::Error> {
+        self.users().deleted(id).map(|user| user.detail()).ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn restore_user(&self, id: i32, restored_by: Option<i32>) -> Result<DetailUserResponse, sqlx::Error> {
+        let mut users = self.users();
+        let Some(user) = users.deleted(id) else {
+            return Err(sqlx::Error::RowNotFound);
+        };
+        let (username, email) = (user.username.clone(), user.email.clone());
+        users.check(id, &username, &email, None)?;
+        users.check_reference(restored_by, "users_updated_by_fkey")?;
+
+        let user = users.deleted(id).ok_or(sqlx::Error::RowNotFound)?;
+        user.deleted_at = None;
+        user.deleted_by = None;
+        user.updated_by = restored_by;
+        user.bump();
+        Ok(user.detail())
+    }
+
+    // Purged users lose their roles and references to them are cleared, as the
+    // foreign keys cascade and set NULL.
+    async fn purge_deleted_users(&self, deleted_before: NaiveDateTime) -> Result<u64, sqlx::Error> {
+        let mut users = self.users();
+        let purged: Vec<i32> = users.rows.iter()
+            .filter(|user| user.deleted_at.is_some_and(|deleted_at| deleted_at < deleted_before))
+            .map(|user| user.id)
+            .collect();
+        users.rows.retain(|user| !purged.contains(&user.id));
+        for user in users.rows.iter_mut() {
+            for reference in [&mut user.created_by, &mut user.updated_by, &mut user.deleted_by] {
+                if reference.is_some_and(|id| purged.contains(&id)) {
+                    *reference = None;
+                }
+            }
+        }
+        Ok(purged.len() as u64)
+    }
+
+    async fn get_user_roles(&self, id: i32) -> Result<Vec<String>, sqlx::Error> {
+        Ok(self.users().rows.iter().find(|user| user.id == id).map(|user| user.roles.clone()).unwrap_or_default())
+    }
+
+    async fn find_unknown_roles(&self, user_roles: &[String]) -> Result<Vec<String>, sqlx::Error> {
+        Ok(user_roles.iter().filter(|role| !self.roles.contains(role)).cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::application::repositories::users::postgres::PgUserRepository;
+    use crate::internal::application::repositories::users::users::USER_SCHEMA;
+    use crate::internal::pkg::database::sql::migrate::migrate_up;
+    use crate::internal::pkg::database::sql::scratch::ScratchDatabase;
+
+    // Lowercase ASCII text, where the collations of Postgres and code point order agree.
+    // User 3 is deleted.
+    const USERS: [(&str, &str, Option<&str>, &[&str]); 5] = [
+        ("admin", "admin@example.com", None, &["admin"]),
+        ("alice", "alice@example.org", Some("alice liddell"), &["user"]),
+        ("bob", "bob@example.com", None, &["user"]),
+        ("carol", "carol@example.org", Some("carol"), &["admin", "user"]),
+        ("dave", "dave@example.net", Some("dave"), &[]),
+    ];
+
+    // A list of the fields that do not depend on when a user was written.
+    fn query(filters: &str, sort: &str) -> ListQuery {
+        ListQuery::new(&USER_SCHEMA)
+            .fields(USER_SCHEMA.parse_fields(Some("username,email,name,version,emailDomain,createdBy,deletedBy")).unwrap())
+            .filters(USER_SCHEMA.parse_filters(filters).unwrap())
+            .order_by(USER_SCHEMA.parse_sort(sort).unwrap())
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn lists_like_postgres() {
+        let database = ScratchDatabase::create("users").await;
+        migrate_up(&database.pool).await.unwrap();
+        let postgres = PgUserRepository::new(database.pool.clone());
+        let memory = MemoryUserRepository::new(&["admin", "user"]);
+        for repository in [&postgres as &dyn UserRepository, &memory] {
+            for (index, (username, email, name, user_roles)) in USERS.into_iter().enumerate() {
+                let user = CreateUserRequest {
+                    username: username.to_string(),
+                    email: email.to_string(),
+                    // users.password is unique.
+                    password: format!("hash of {}", username),
+                    name: name.map(str::to_string),
+                    roles: Some(user_roles.iter().map(|role| role.to_string()).collect()),
+                };
+                repository.create_user(user, None, (index > 0).then_some(1)).await.unwrap();
+            }
+            assert!(repository.delete_user(3, Some(1), None).await.is_ok());
+        }
+
+        let cases = [
+            ("", "id"),
+            ("filter[emailDomain]=example.org", "-username"),
+            ("filter[emailDomain][in]=example.com,example.net", "email"),
+            ("filter[name][null]=true", "-id"),
+            ("filter[name]=ALICE", "id"),
+            ("filter[createdBy][null]=false&filter[id][gte]=2", "name,id"),
+            ("", "-name,username"),
+        ];
+        for (filters, sort) in cases {
+            for trashed in [false, true] {
+                for (limit, offset) in [(10, 0), (2, 1)] {
+                    let expected = postgres.get_users(query(filters, sort).trashed(trashed).paginate(limit, offset), &["roles"]).await.unwrap();
+                    let actual = memory.get_users(query(filters, sort).trashed(trashed).paginate(limit, offset), &["roles"]).await.unwrap();
+                    assert_eq!(expected, actual, "{:?} sorted by {} (trashed: {}, limit: {}, offset: {})", filters, sort, trashed, limit, offset);
+                }
+            }
+
+            let (expected, expected_total) = postgres.get_users_page(query(filters, sort).paginate(2, 0), &[], CountMode::Exact).await.unwrap();
+            let (actual, actual_total) = memory.get_users_page(query(filters, sort).paginate(2, 0), &[], CountMode::Exact).await.unwrap();
+            assert_eq!(
+                (expected.items, expected.next_cursor, expected_total),
+                (actual.items, actual.next_cursor, actual_total),
+                "first page of {:?} sorted by {}", filters, sort,
+            );
+        }
+        database.drop().await;
+    }
+}
@@ -1 +1,4 @@
+#[cfg(test)]
+pub mod memory;
+pub mod postgres;
 pub mod users;
\ No newline at end of file
@@ -0,0 +1,75 @@
+use crate::internal::application::repositories::auth::roles;
+use crate::internal::application::repositories::users::users::{self, DeleteUserError, UpdateUserError, UserRepository};
+use crate::internal::domain::entities::users::users::{CreateUserRequest, CreateUserResponse, DetailUserResponse, UpdateUserRequest, UpdateUserResponse};
+use crate::internal::pkg::query::cursor::{CountMode, CursorPage};
+use crate::internal::pkg::query::query::ListQuery;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use sqlx::postgres::PgPool;
+
+// The users table and the roles assigned in user_roles.
+pub struct PgUserRepository {
+    pool: PgPool,
+}
+
+impl PgUserRepository {
+    pub fn new(pool: PgPool) -> Self {
+        PgUserRepository { pool }
+    }
+}
+
+#[async_trait]
+impl UserRepository for PgUserRepository {
+    async fn create_user(&self, new_user: CreateUserRequest, email_verified_at: Option<NaiveDateTime>, created_by: Option<i32>) -> Result<CreateUserResponse, sqlx::Error> {
+        users::create_user(&self.pool, new_user, email_verified_at, created_by).await
+    }
+
+    async fn get_users(&self, query: ListQuery, includes: &[&str]) -> Result<(Vec<Value>, i64), sqlx::Error> {
+        users::get_users(&self.pool, query, includes).await
+    }
+
+    async fn get_users_page(&self, query: ListQuery, includes: &[&str], count: CountMode) -> Result<(CursorPage<Value>, Option<i64>), sqlx::Error> {
+        users::get_users_page(&self.pool, query, includes, count).await
+    }
+
+    async fn get_user_detail(&self, id: i32) -> Result<DetailUserResponse, sqlx::Error> {
+        users::get_user_detail(&self.pool, id).await
+    }
+
+    async fn get_user_username(&self, username: &str) -> Result<DetailUserResponse, sqlx::Error> {
+        users::get_user_username(&self.pool, username).await
+    }
+
+    async fn get_user_email(&self, email: &str) -> Result<DetailUserResponse, sqlx::Error> {
+        users::get_user_email(&self.pool, email).await
+    }
+
+    async fn update_user(&self, id: i32, req: UpdateUserRequest, versions: Option<Vec<i32>>, updated_by: Option<i32>) -> Result<UpdateUserResponse, UpdateUserError> {
+        users::update_user(&self.pool, id, req, versions, updated_by).await
+    }
+
+    async fn delete_user(&self, id: i32, deleted_by: Option<i32>, versions: Option<Vec<i32>>) -> Result<(), DeleteUserError> {
+        users::delete_user(&self.pool, id, deleted_by, versions).await
+    }
+
+    async fn get_deleted_user(&self, id: i32) -> Result<DetailUserResponse, sqlx::Error> {
+        users::get_deleted_user(&self.pool, id).await
+    }
+
+    async fn restore_user(&self, id: i32, restored_by: Option<i32>) -> Result<DetailUserResponse, sqlx::Error> {
+        users::restore_user(&self.pool, id, restored_by).await
+    }
+
+    async fn purge_deleted_users(&self, deleted_before: NaiveDateTime) -> Result<u64, sqlx::Error> {
+        users::purge_deleted_users(&self.pool, deleted_before).await
+    }
+
+    async fn get_user_roles(&self, id: i32) -> Result<Vec<String>, sqlx::Error> {
+        roles::get_user_roles(&self.pool, id).await
+    }
+
+    async fn find_unknown_roles(&self, user_roles: &[String]) -> Result<Vec<String>, sqlx::Error> {
+        roles::find_unknown_roles(&self.pool, user_roles).await
+    }
+}
@@ -1,90 +1,159 @@
 use crate::internal::domain::entities::users::users::{CreateUserRequest, CreateUserResponse, UpdateUserRequest, UpdateUserResponse, DetailUserResponse, User};
-use crate::internal::pkg::utils::pagination::PaginationRequest;
+use crate::internal::application::repositories::auth::{password_resets, roles, sessions};
+use crate::internal::pkg::query::cursor::{CountMode, CursorPage};
+use crate::internal::pkg::query::query::{Column, ColumnKind, ListQuery, Schema};
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use serde_json::{json, Value};
 use sqlx::postgres::PgPool;
-use std::collections::HashMap;
+
+// Column limits of the users table.
+pub const MAX_USERNAME_LEN: usize = 20;
+pub const MAX_EMAIL_LEN: usize = 50;
+pub const MAX_NAME_LEN: usize = 100;
+
+// Fields the user list can return, filter and sort on. The password hash is never one of them.
+pub static USER_SCHEMA: Schema = Schema {
+    table: "users",
+    columns: &[
+        Column { name: "id", sql: "id", kind: ColumnKind::Integer },
+        Column { name: "username", sql: "username", kind: ColumnKind::Text },
+        Column { name: "email", sql: "email", kind: ColumnKind::Text },
+        Column { name: "name", sql: "name", kind: ColumnKind::Text },
+        Column { name: "version", sql: "version", kind: ColumnKind::Integer },
+        Column { name: "createdAt", sql: "created_at", kind: ColumnKind::Timestamp },
+        Column { name: "updatedAt", sql: "updated_at", kind: ColumnKind::Timestamp },
+        Column { name: "createdBy", sql: "created_by", kind: ColumnKind::Integer },
+        Column { name: "updatedBy", sql: "updated_by", kind: ColumnKind::Integer },
+        Column { name: "emailDomain", sql: "split_part(email, '@', 2)", kind: ColumnKind::Text },
+        // Only set on users in the trash.
+        Column { name: "deletedAt", sql: "deleted_at", kind: ColumnKind::Timestamp },
+        Column { name: "deletedBy", sql: "deleted_by", kind: ColumnKind::Integer },
+    ],
+    default_fields: &["id", "username", "email", "name"],
+    default_sort: "id",
+    key: "id",
+    includes: &["roles"],
+    search: None,
+    deleted: Some("deleted_at"),
+};
 
 #[allow(dead_code)]
-pub enum DeleteItemError {
+pub enum DeleteUserError {
     NotFound,
+    VersionMismatch,
     DatabaseError(sqlx::Error),
 }
 
-impl From<sqlx::Error> for DeleteItemError {
+impl From<sqlx::Error> for DeleteUserError {
     fn from(err: sqlx::Error) -> Self {
-        DeleteItemError::DatabaseError(err)
+        DeleteUserError::DatabaseError(err)
     }
 }
 
-pub async fn create_user(pool: &PgPool, new_user: CreateUserRequest) -> Result<CreateUserResponse, sqlx::Error> {
+pub enum UpdateUserError {
+    NotFound,
+    VersionMismatch,
+    DatabaseError(sqlx::Error),
+}
+
+impl From<sqlx::Error> for UpdateUserError {
+    fn from(err: sqlx::Error) -> Self {
+        UpdateUserError::DatabaseError(err)
+    }
+}
+
+// User storage as the user management endpoints see it, shared with handlers as
+// `web::Data<dyn UserRepository>`. The functions below are the Postgres queries
+// behind PgUserRepository and stay in use by the auth flows. MemoryUserRepository,
+// which the tests run on, keeps users and their roles in memory with the same
+// semantics, except that text compares and sorts by code point, as under the C
+// collation, where Postgres uses the collation of the database.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn create_user(&self, new_user: CreateUserRequest, email_verified_at: Option<NaiveDateTime>, created_by: Option<i32>) -> Result<CreateUserResponse, sqlx::Error>;
+    async fn get_users(&self, query: ListQuery, includes: &[&str]) -> Result<(Vec<Value>, i64), sqlx::Error>;
+    async fn get_users_page(&self, query: ListQuery, includes: &[&str], count: CountMode) -> Result<(CursorPage<Value>, Option<i64>), sqlx::Error>;
+    async fn get_user_detail(&self, id: i32) -> Result<DetailUserResponse, sqlx::Error>;
+    async fn get_user_username(&self, username: &str) -> Result<DetailUserResponse, sqlx::Error>;
+    async fn get_user_email(&self, email: &str) -> Result<DetailUserResponse, sqlx::Error>;
+    async fn update_user(&self, id: i32, req: UpdateUserRequest, versions: Option<Vec<i32>>, updated_by: Option<i32>) -> Result<UpdateUserResponse, UpdateUserError>;
+    async fn delete_user(&self, id: i32, deleted_by: Option<i32>, versions: Option<Vec<i32>>) -> Result<(), DeleteUserError>;
+    async fn get_deleted_user(&self, id: i32) -> Result<DetailUserResponse, sqlx::Error>;
+    async fn restore_user(&self, id: i32, restored_by: Option<i32>) -> Result<DetailUserResponse, sqlx::Error>;
+    async fn purge_deleted_users(&self, deleted_before: NaiveDateTime) -> Result<u64, sqlx::Error>;
+    async fn get_user_roles(&self, id: i32) -> Result<Vec<String>, sqlx::Error>;
+    async fn find_unknown_roles(&self, roles: &[String]) -> Result<Vec<String>, sqlx::Error>;
+}
+
+// `created_by` is the admin creating the account, None when people sign up themselves.
+// The user gets its `roles`, or the default role, in the same transaction.
+pub async fn create_user(
+    pool: &PgPool,
+    new_user: CreateUserRequest,
+    email_verified_at: Option<NaiveDateTime>,
+    created_by: Option<i32>,
+) -> Result<CreateUserResponse, sqlx::Error> {
+    let user_roles = new_user.roles.unwrap_or_else(|| vec![roles::DEFAULT_ROLE.to_string()]);
+    let mut tx = pool.begin().await?;
     let rec = sqlx::query_as::<_, CreateUserResponse>(
-"INSERT INTO users (username, email, password) VALUES ($1, $2, $3) RETURNING id, username, email",
+"INSERT INTO users (username, email, password, name, email_verified_at, created_by, updated_by) VALUES ($1, $2, $3, $4, $5, $6, $6) RETURNING id, username, email, name, version",
     )
     .bind(new_user.username)
     .bind(new_user.email)
     .bind(new_user.password)
-    .fetch_one(pool)
+    .bind(new_user.name)
+    .bind(email_verified_at)
+    .bind(created_by)
+    .fetch_one(&mut tx)
     .await?;
+    roles::replace_user_roles(&mut tx, rec.id, &user_roles).await?;
+    tx.commit().await?;
     Ok(rec)
 }
 
-pub async fn get_users(
-    pool: &PgPool,
-    pagination: PaginationRequest,
-    filter: HashMap<String, String>
-) -> Result<(Vec<DetailUserResponse>, i64), sqlx::Error> {
-    let valid_sort = match pagination.field.as_str() {
-        "id" | "username" | "email" => pagination.field.clone(),
-        _ => "id".to_string(),
-    };
-    let valid_order = if pagination.sort.to_uppercase() == "DESC" { "DESC" } else { "ASC" };
-    let limit = pagination.limit;
-    let offset = (pagination.page - 1) * pagination.limit;
-
-    // Build dynamic WHERE clause based on filter parameters
-    let mut where_clauses = Vec::new();
-    if let Some(id) = filter.get("id") {
-        // Assuming the id is stored as integer, we cast it to text for comparison
-        where_clauses.push(format!("CAST(id AS TEXT) = '{}'", id));
-    }
-    if let Some(username) = filter.get("username") {
-        where_clauses.push(format!("username ILIKE '%{}%'", username));
+// Embeds the requested relations into listed users.
+async fn embed(pool: &PgPool, users: &mut [Value], includes: &[&str]) -> Result<(), sqlx::Error> {
+    if includes.contains(&"roles") {
+        let ids: Vec<i32> = users.iter().filter_map(|user| user["id"].as_i64()).map(|id| id as i32).collect();
+        let user_roles = roles::get_users_roles(pool, &ids).await?;
+        for user in users.iter_mut() {
+            let id = user["id"].as_i64();
+            let names: Vec<&str> = user_roles.iter()
+                .filter(|(user_id, _)| Some(i64::from(*user_id)) == id)
+                .map(|(_, role)| role.as_str())
+                .collect();
+            user["roles"] = json!(names);
+        }
     }
-    if let Some(email) = filter.get("email") {
-        where_clauses.push(format!("email ILIKE '%{}%'", email));
-    }
-    let where_clause = if where_clauses.is_empty() {
-        "".to_string()
-    } else {
-        format!("WHERE {}", where_clauses.join(" AND "))
-    };
-    
-    let query = format!(
-        "SELECT id, username, email FROM users {} ORDER BY {} {} LIMIT {} OFFSET {}",
-        where_clause, valid_sort, valid_order, limit, offset
-    );
-    let users = sqlx::query_as::<_, DetailUserResponse>(&query)
-        .fetch_all(pool)
-        .await?;
-    let count_query = format!(
-        "SELECT COUNT(id) FROM users {}",
-        where_clause
-    );
-    let count: i64 = sqlx::query_scalar(&count_query)
-        .fetch_one(pool)
-        .await?;
+    Ok(())
+}
+
+pub async fn get_users(pool: &PgPool, query: ListQuery, includes: &[&str]) -> Result<(Vec<Value>, i64), sqlx::Error> {
+    let mut users = query.fetch_all(pool).await?;
+    embed(pool, &mut users, includes).await?;
+    let count = query.total(pool, CountMode::Exact).await?.unwrap_or_default();
     Ok((users, count))
 }
 
+// Keyset variant of get_users for large tables, the total is only computed when asked for.
+pub async fn get_users_page(pool: &PgPool, query: ListQuery, includes: &[&str], count: CountMode) -> Result<(CursorPage<Value>, Option<i64>), sqlx::Error> {
+    let mut page = query.fetch_page(pool).await?;
+    embed(pool, &mut page.items, includes).await?;
+    let total = query.total(pool, count).await?;
+    Ok((page, total))
+}
+
 pub async fn get_user_detail(pool: &PgPool, id: i32) -> Result<DetailUserResponse, sqlx::Error> {
-    let user = sqlx::query_as::<_, DetailUserResponse>("SELECT id, username, email FROM users WHERE id = $1")
+    let user = sqlx::query_as::<_, DetailUserResponse>("SELECT id, username, email, name, version, created_at, updated_at, created_by, updated_by FROM users WHERE id = $1 AND deleted_at IS NULL")
         .bind(id)
         .fetch_one(pool)
         .await?;
     Ok(user)
 }
 
-async fn get_user(pool: &PgPool, id: i32) -> Result<User, sqlx::Error> {
-    let user = sqlx::query_as::<_, User>("SELECT id, username, email, password FROM users WHERE id = $1")
+pub async fn get_user(pool: &PgPool, id: i32) -> Result<User, sqlx::Error> {
+    let user = sqlx::query_as::<_, User>("SELECT id, username, email, password, name, email_verified_at, pending_email FROM users WHERE id = $1 AND deleted_at IS NULL")
         .bind(id)
         .fetch_one(pool)
         .await?;
@@ -92,7 +161,7 @@ async fn get_user(pool: &PgPool, id: i32) -> Result<User, sqlx::Error> {
 }
 
 pub async fn get_user_username(pool: &PgPool, username: &str) -> Result<DetailUserResponse, sqlx::Error> {
-    let user = sqlx::query_as::<_, DetailUserResponse>("SELECT id, username, email FROM users WHERE username = $1")
+    let user = sqlx::query_as::<_, DetailUserResponse>("SELECT id, username, email, name, version, created_at, updated_at, created_by, updated_by FROM users WHERE username = $1 AND deleted_at IS NULL")
         .bind(username)
         .fetch_one(pool)
         .await?;
@@ -100,7 +169,7 @@ pub async fn get_user_username(pool: &PgPool, username: &str) -> Result<DetailUs
 }
 
 pub async fn get_user_email(pool: &PgPool, email: &str) -> Result<DetailUserResponse, sqlx::Error> {
-    let user = sqlx::query_as::<_, DetailUserResponse>("SELECT id, username, email FROM users WHERE email = $1")
+    let user = sqlx::query_as::<_, DetailUserResponse>("SELECT id, username, email, name, version, created_at, updated_at, created_by, updated_by FROM users WHERE email = $1 AND deleted_at IS NULL")
         .bind(email)
         .fetch_one(pool)
         .await?;
@@ -108,7 +177,7 @@ pub async fn get_user_email(pool: &PgPool, email: &str) -> Result<DetailUserResp
 }
 
 pub async fn get_user_or(pool: &PgPool, username: &str, email: &str) -> Result<User, sqlx::Error> {
-    let user = sqlx::query_as::<_, User>("SELECT id, username, email, password FROM users WHERE username = $1 OR email = $2")
+    let user = sqlx::query_as::<_, User>("SELECT id, username, email, password, name, email_verified_at, pending_email FROM users WHERE (username = $1 OR email = $2) AND deleted_at IS NULL")
         .bind(username)
         .bind(email)
         .fetch_one(pool)
@@ -116,41 +185,323 @@ pub async fn get_user_or(pool: &PgPool, username: &str, email: &str) -> Result<U
     Ok(user)
 }
 
+// Partial update, unset fields keep their value. With `versions` the user has to be
+// at one of them, the If-Match of the request. `updated_by` is the user making the change.
+// A rename revokes the user's tokens, so tokens another account was issued under the
+// new name never pass for this one. `roles` replaces the user's roles. All of it
+// happens in one transaction, together with ending the refresh sessions of a
+// password change and revoking tokens and sessions when the roles changed, as
+// permissions are part of the issued tokens.
 pub async fn update_user(
     pool: &PgPool,
     id: i32,
     req: UpdateUserRequest,
-) -> Result<UpdateUserResponse, sqlx::Error> {
-    // Retrieve current item first.
-    let current = get_user(pool, id).await?;
-    let new_username = req.username.unwrap_or(current.username);
-    let new_email = req.email.unwrap_or(current.email);
-    let new_password = match req.password {
-        Some(ref p) if !p.trim().is_empty() => p.clone(),
-        _ => current.password,
-    };
+    versions: Option<Vec<i32>>,
+    updated_by: Option<i32>,
+) -> Result<UpdateUserResponse, UpdateUserError> {
+    let now = Utc::now().naive_utc();
+    let password = req.password.filter(|p| !p.trim().is_empty());
+    let password_changed_at = password.as_ref().map(|_| now);
 
+    let mut tx = pool.begin().await?;
     let user = sqlx::query_as::<_, UpdateUserResponse>(
-        "UPDATE users SET username = $1, email = $2, password = $3 WHERE id = $4 RETURNING id, username, email"
+        "UPDATE users SET username = COALESCE($1, username), email = COALESCE($2, email), password = COALESCE($3, password), \
+         password_changed_at = COALESCE($4, password_changed_at), name = COALESCE($5, name), updated_by = $6, version = version + 1, \
+         tokens_revoked_at = CASE WHEN username <> COALESCE($1, username) THEN $9 ELSE tokens_revoked_at END \
+         WHERE id = $7 AND deleted_at IS NULL AND ($8::INTEGER[] IS NULL OR version = ANY($8)) RETURNING id, username, email, name, version"
+    )
+    .bind(req.username)
+    .bind(req.email)
+    .bind(password)
+    .bind(password_changed_at)
+    .bind(req.name)
+    .bind(updated_by)
+    .bind(id)
+    .bind(versions)
+    .bind(now)
+    .fetch_optional(&mut tx)
+    .await?;
+
+    let user = match user {
+        Some(user) => user,
+        None if user_exists(pool, id).await? => return Err(UpdateUserError::VersionMismatch),
+        None => return Err(UpdateUserError::NotFound),
+    };
+
+    // Access tokens are cut off by password_changed_at.
+    let mut end_sessions = password_changed_at.is_some();
+    if let Some(user_roles) = req.roles.as_deref()
+        && roles::replace_user_roles(&mut tx, id, user_roles).await? {
+        sqlx::query("UPDATE users SET tokens_revoked_at = $1 WHERE id = $2")
+            .bind(now)
+            .bind(id)
+            .execute(&mut tx)
+            .await?;
+        end_sessions = true;
+    }
+    if end_sessions {
+        sessions::end_user_sessions(&mut tx, id).await?;
+    }
+    tx.commit().await?;
+    Ok(user)
+}
+
+// Tells a version mismatch from a missing user once a conditional write matched no row.
+async fn user_exists(pool: &PgPool, id: i32) -> Result<bool, sqlx::Error> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND deleted_at IS NULL)")
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+    Ok(exists)
+}
+
+// The user's own password change or reset, so they are the last editor.
+pub async fn update_password(pool: &PgPool, id: i32, password: &str, changed_at: NaiveDateTime) -> Result<(), sqlx::Error> {
+    let result = sqlx::query("UPDATE users SET password = $1, password_changed_at = $2, updated_by = id, version = version + 1 WHERE id = $3 AND deleted_at IS NULL")
+        .bind(password)
+        .bind(changed_at)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    Ok(())
+}
+
+// Swap the stored hash for an upgraded one of the same password. password_changed_at
+// stays as it is so issued tokens remain valid, and a concurrent password change wins.
+pub async fn rehash_password(pool: &PgPool, id: i32, old_hash: &str, new_hash: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE users SET password = $1 WHERE id = $2 AND password = $3 AND deleted_at IS NULL")
+        .bind(new_hash)
+        .bind(id)
+        .bind(old_hash)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_user_by_email(pool: &PgPool, email: &str) -> Result<User, sqlx::Error> {
+    let user = sqlx::query_as::<_, User>("SELECT id, username, email, password, name, email_verified_at, pending_email FROM users WHERE email = $1 AND deleted_at IS NULL")
+        .bind(email)
+        .fetch_one(pool)
+        .await?;
+    Ok(user)
+}
+
+// Self service profile update. A new email address only becomes pending, the
+// current one stays until the new one is verified. None drops a pending address.
+pub async fn update_profile(pool: &PgPool, id: i32, name: Option<&str>, pending_email: Option<&str>) -> Result<User, sqlx::Error> {
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET name = $1, pending_email = $2, updated_by = id, version = version + 1 WHERE id = $3 AND deleted_at IS NULL RETURNING id, username, email, password, name, email_verified_at, pending_email"
     )
-    .bind(new_username)
-    .bind(new_email)
-    .bind(new_password)
+    .bind(name)
+    .bind(pending_email)
     .bind(id)
     .fetch_one(pool)
     .await?;
     Ok(user)
 }
 
-pub async fn delete_user(pool: &PgPool, id: i32) -> Result<(), DeleteItemError> {
-    let result = sqlx::query("DELETE FROM users WHERE id = $1")
+// Verify the address a link was sent to. The current address is marked verified,
+// a pending one replaces it. False when the address is neither any more.
+pub async fn mark_email_verified(pool: &PgPool, id: i32, email: &str, verified_at: NaiveDateTime) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE users SET email = $2, \
+            pending_email = CASE WHEN email = $2 THEN pending_email END, \
+            email_verified_at = CASE WHEN email = $2 THEN COALESCE(email_verified_at, $3) ELSE $3 END, \
+            version = version + 1 \
+        WHERE id = $1 AND (email = $2 OR pending_email = $2) AND deleted_at IS NULL"
+    )
+    .bind(id)
+    .bind(email)
+    .bind(verified_at)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Tokens issued before this moment are no longer accepted. RowNotFound means the user is gone.
+// created_at keeps tokens of a deleted account from passing for a new one with its username,
+// tokens_revoked_at, set on renames, does the same for an existing account renamed to it.
+pub async fn get_user_token_cutoff(pool: &PgPool, username: &str) -> Result<Option<NaiveDateTime>, sqlx::Error> {
+    let cutoff: Option<NaiveDateTime> = sqlx::query_scalar(
+        "SELECT GREATEST(created_at, password_changed_at, tokens_revoked_at) FROM users WHERE username = $1 AND deleted_at IS NULL"
+    )
+    .bind(username)
+    .fetch_one(pool)
+    .await?;
+    Ok(cutoff)
+}
+
+pub async fn revoke_user_tokens(pool: &PgPool, id: i32, revoked_at: NaiveDateTime) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE users SET tokens_revoked_at = $1 WHERE id = $2")
+        .bind(revoked_at)
         .bind(id)
         .execute(pool)
         .await?;
+    Ok(())
+}
+
+// Move a user to the trash. `deleted_by` is the user doing it, when known, and
+// `versions` works as in update_user. Its tokens, refresh sessions and pending
+// password resets end in the same transaction, resets would otherwise still be
+// usable once the user is restored.
+pub async fn delete_user(pool: &PgPool, id: i32, deleted_by: Option<i32>, versions: Option<Vec<i32>>) -> Result<(), DeleteUserError> {
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query(
+        "UPDATE users SET deleted_at = now(), deleted_by = $1, tokens_revoked_at = now(), version = version + 1 \
+         WHERE id = $2 AND deleted_at IS NULL AND ($3::INTEGER[] IS NULL OR version = ANY($3))"
+    )
+    .bind(deleted_by)
+    .bind(id)
+    .bind(versions)
+    .execute(&mut tx)
+    .await?;
 
     if result.rows_affected() == 0 {
-        return Err(DeleteItemError::NotFound);
+        return Err(if user_exists(pool, id).await? { DeleteUserError::VersionMismatch } else { DeleteUserError::NotFound });
     }
 
+    sessions::end_user_sessions(&mut tx, id).await?;
+    password_resets::delete_pending_resets(&mut tx, id).await?;
+    tx.commit().await?;
     Ok(())
 }
+
+pub async fn get_deleted_user(pool: &PgPool, id: i32) -> Result<DetailUserResponse, sqlx::Error> {
+    let user = sqlx::query_as::<_, DetailUserResponse>("SELECT id, username, email, name, version, created_at, updated_at, created_by, updated_by FROM users WHERE id = $1 AND deleted_at IS NOT NULL")
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+    Ok(user)
+}
+
+// Take a user back out of the trash, `restored_by` becomes its last editor. Tokens
+// issued before the delete stay revoked.
+pub async fn restore_user(pool: &PgPool, id: i32, restored_by: Option<i32>) -> Result<DetailUserResponse, sqlx::Error> {
+    let user = sqlx::query_as::<_, DetailUserResponse>(
+        "UPDATE users SET deleted_at = NULL, deleted_by = NULL, updated_by = $1, version = version + 1 \
+         WHERE id = $2 AND deleted_at IS NOT NULL RETURNING id, username, email, name, version, created_at, updated_at, created_by, updated_by"
+    )
+    .bind(restored_by)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+    Ok(user)
+}
+
+// Hard delete users that have been in the trash since before `deleted_before`.
+pub async fn purge_deleted_users(pool: &PgPool, deleted_before: NaiveDateTime) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM users WHERE deleted_at < $1")
+        .bind(deleted_before)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::domain::entities::auth::session::CreateSession;
+    use crate::internal::pkg::database::sql::migrate::migrate_up;
+    use crate::internal::pkg::database::sql::scratch::ScratchDatabase;
+
+    fn change(name: Option<&str>, password: Option<&str>, user_roles: Option<&[&str]>) -> UpdateUserRequest {
+        UpdateUserRequest {
+            username: None,
+            email: None,
+            password: password.map(str::to_string),
+            name: name.map(str::to_string),
+            roles: user_roles.map(|user_roles| user_roles.iter().map(|role| role.to_string()).collect()),
+        }
+    }
+
+    async fn open_session(pool: &PgPool, user_id: i32, token_hash: &str) {
+        let session = CreateSession {
+            user_id,
+            family_id: token_hash.to_string(),
+            refresh_token_hash: token_hash.to_string(),
+            device: None,
+            ip: None,
+            expires_at: Utc::now().naive_utc() + chrono::Duration::hours(1),
+        };
+        sessions::create_session(pool, session).await.unwrap();
+    }
+
+    async fn session_open(pool: &PgPool, token_hash: &str) -> bool {
+        sessions::get_session_by_hash(pool, token_hash).await.unwrap().revoked_at.is_none()
+    }
+
+    async fn tokens_revoked(pool: &PgPool, id: i32) -> bool {
+        let revoked_at: Option<NaiveDateTime> = sqlx::query_scalar("SELECT tokens_revoked_at FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        revoked_at.is_some()
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn an_update_revokes_with_its_roles_and_password() {
+        let database = ScratchDatabase::create("user_update").await;
+        let pool = &database.pool;
+        migrate_up(pool).await.unwrap();
+        let user = CreateUserRequest {
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            password: "hash".to_string(),
+            name: None,
+            roles: None,
+        };
+        let id = create_user(pool, user, None, None).await.unwrap().id;
+        open_session(pool, id, "first").await;
+
+        // The same roles again change nothing a token carries.
+        let updated = update_user(pool, id, change(Some("Alice"), None, Some(&["user"])), None, None).await.ok().unwrap();
+        assert!(session_open(pool, "first").await);
+        assert!(!tokens_revoked(pool, id).await);
+
+        // A stale version applies none of the change.
+        let stale = update_user(pool, id, change(None, None, Some(&["admin", "user"])), Some(vec![updated.version - 1]), None).await;
+        assert!(matches!(stale, Err(UpdateUserError::VersionMismatch)));
+        assert_eq!(roles::get_user_roles(pool, id).await.unwrap(), ["user"]);
+        assert!(session_open(pool, "first").await);
+
+        update_user(pool, id, change(None, None, Some(&["admin", "user"])), Some(vec![updated.version]), None).await.ok().unwrap();
+        assert_eq!(roles::get_user_roles(pool, id).await.unwrap(), ["admin", "user"]);
+        assert!(!session_open(pool, "first").await);
+        assert!(tokens_revoked(pool, id).await);
+
+        open_session(pool, id, "second").await;
+        update_user(pool, id, change(None, Some("new hash"), None), None, None).await.ok().unwrap();
+        assert!(!session_open(pool, "second").await);
+        database.drop().await;
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn a_delete_revokes_tokens_and_sessions() {
+        let database = ScratchDatabase::create("user_delete").await;
+        let pool = &database.pool;
+        migrate_up(pool).await.unwrap();
+        let user = CreateUserRequest {
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            password: "hash".to_string(),
+            name: None,
+            roles: None,
+        };
+        let id = create_user(pool, user, None, None).await.unwrap().id;
+        open_session(pool, id, "first").await;
+
+        assert!(matches!(delete_user(pool, id, None, Some(vec![0])).await, Err(DeleteUserError::VersionMismatch)));
+        assert!(session_open(pool, "first").await);
+
+        assert!(delete_user(pool, id, None, None).await.is_ok());
+        assert!(!session_open(pool, "first").await);
+        assert!(tokens_revoked(pool, id).await);
+        database.drop().await;
+    }
+}
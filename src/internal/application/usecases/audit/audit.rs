@@ -0,0 +1,210 @@
+use crate::internal::domain::entities::audit::audit::{AuditEventFilter, AuditEventsQuery, ListAuditEvents, NewAuditEvent};
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::internal::domain::entities::response::Response;
+use crate::internal::constant::status::{SUCCESS, FAILED_INTERNAL, FAILED_REQUIRED};
+use crate::internal::application::repositories::audit::audit_events::{self, AuditEventRepository};
+use crate::internal::application::repositories::audit::postgres::PgAuditEventRepository;
+use crate::internal::application::repositories::users::postgres::PgUserRepository;
+use crate::internal::application::repositories::users::users::UserRepository;
+use crate::internal::pkg::utils::request::{client_ip, user_agent};
+use crate::internal::pkg::utils::time::parse_time;
+use crate::middlewares::request_id::request_id;
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use log::error;
+use serde_json::{json, Map, Value};
+use sqlx::postgres::PgPool;
+use std::sync::Arc;
+
+pub const OUTCOME_SUCCESS: &str = "success";
+pub const OUTCOME_FAILURE: &str = "failure";
+
+const MAX_LIMIT: i64 = 100;
+
+// One audit event, filled in by the use case and completed with the request
+// context when recorded.
+pub struct AuditEntry {
+    action: &'static str,
+    outcome: &'static str,
+    actor_id: Option<i32>,
+    actor: Option<String>,
+    api_key_id: Option<i32>,
+    // The actor came from a token, its id is looked up when recording.
+    resolve_actor: bool,
+    target_type: Option<&'static str>,
+    target_id: Option<String>,
+    changes: Option<Value>,
+}
+
+impl AuditEntry {
+    pub fn new(action: &'static str, outcome: &'static str) -> Self {
+        AuditEntry {
+            action,
+            outcome,
+            actor_id: None,
+            actor: None,
+            api_key_id: None,
+            resolve_actor: false,
+            target_type: None,
+            target_id: None,
+            changes: None,
+        }
+    }
+
+    pub fn by_user(mut self, id: Option<i32>, name: &str) -> Self {
+        self.actor_id = id;
+        self.actor = Some(name.to_string());
+        self
+    }
+
+    pub fn by_identity(mut self, identity: &Identity) -> Self {
+        self.actor = Some(identity.sub.clone());
+        self.api_key_id = identity.api_key_id;
+        self.resolve_actor = true;
+        self
+    }
+
+    pub fn on(mut self, target_type: &'static str, target_id: impl ToString) -> Self {
+        self.target_type = Some(target_type);
+        self.target_id = Some(target_id.to_string());
+        self
+    }
+
+    pub fn changes(mut self, changes: Value) -> Self {
+        self.changes = Some(changes).filter(|changes| changes.as_object().is_none_or(|fields| !fields.is_empty()));
+        self
+    }
+}
+
+// Field level diff of two JSON objects, {"field": {"from": .., "to": ..}} for every
+// field that differs. None stands for the row not existing (before a create, after a delete).
+pub fn diff(before: Option<&Value>, after: Option<&Value>) -> Value {
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut changes = Map::new();
+    for key in before.keys().chain(after.keys().filter(|key| !before.contains_key(*key))) {
+        let from = before.get(key).unwrap_or(&Value::Null);
+        let to = after.get(key).unwrap_or(&Value::Null);
+        if from != to {
+            changes.insert(key.clone(), json!({ "from": from, "to": to }));
+        }
+    }
+    Value::Object(changes)
+}
+
+// Records audit events and resolves the users behind identities, shared with the
+// item and user handlers as `web::Data<Auditor>`.
+pub struct Auditor {
+    events: Arc<dyn AuditEventRepository>,
+    users: Arc<dyn UserRepository>,
+}
+
+impl Auditor {
+    pub fn new(events: Arc<dyn AuditEventRepository>, users: Arc<dyn UserRepository>) -> Self {
+        Auditor { events, users }
+    }
+
+    pub fn postgres(pool: PgPool) -> Self {
+        Auditor::new(Arc::new(PgAuditEventRepository::new(pool.clone())), Arc::new(PgUserRepository::new(pool)))
+    }
+
+    // The user behind an identity, for the created_by and updated_by columns.
+    pub async fn actor_id(&self, identity: &Identity) -> Option<i32> {
+        self.users.get_user_username(&identity.sub).await.ok().map(|user| user.id)
+    }
+
+    // Audit writes never fail the request they describe, a failed write is logged instead.
+    pub async fn record(&self, http_req: &HttpRequest, entry: AuditEntry) {
+        let mut actor_id = entry.actor_id;
+        if entry.resolve_actor
+            && let Some(actor) = entry.actor.as_deref()
+        {
+            actor_id = self.users.get_user_username(actor).await.ok().map(|user| user.id);
+        }
+
+        let event = NewAuditEvent {
+            actor_id,
+            actor: entry.actor,
+            api_key_id: entry.api_key_id,
+            action: entry.action.to_string(),
+            target_type: entry.target_type.map(str::to_string),
+            target_id: entry.target_id,
+            outcome: entry.outcome.to_string(),
+            ip: client_ip(http_req),
+            user_agent: user_agent(http_req),
+            request_id: request_id(http_req),
+            changes: entry.changes,
+        };
+        let action = event.action.clone();
+        if let Err(err) = self.events.insert_audit_event(event).await {
+            error!("failed to record audit event {}: {}", action, err);
+        }
+    }
+}
+
+// Auditor::record for the flows that work on the pool directly.
+pub async fn record(pool: &PgPool, http_req: &HttpRequest, entry: AuditEntry) {
+    Auditor::postgres(pool.clone()).record(http_req, entry).await
+}
+
+fn bad_request(desc: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_REQUIRED.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+pub async fn list_audit_events(
+    pool: web::Data<PgPool>,
+    query: web::Query<AuditEventsQuery>,
+) -> impl Responder {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, MAX_LIMIT);
+    let offset = match (page - 1).checked_mul(limit) {
+        Some(offset) => offset,
+        None => return bad_request(format!("Invalid page: {}", page)),
+    };
+
+    let mut filter = AuditEventFilter::default();
+    for (name, value) in [("from", &query.from), ("to", &query.to)] {
+        let time = match value.as_deref().filter(|value| !value.trim().is_empty()) {
+            None => continue,
+            Some(value) => match parse_time(value) {
+                Some(time) => time,
+                None => return bad_request(format!("Invalid {}: {}", name, value)),
+            },
+        };
+        if name == "from" { filter.from = Some(time) } else { filter.to = Some(time) }
+    }
+    let text = |value: &Option<String>| value.as_deref().map(str::trim).filter(|value| !value.is_empty()).map(str::to_string);
+    filter.actor_id = query.actor_id;
+    filter.actor = text(&query.actor);
+    filter.action = text(&query.action);
+    filter.target_type = text(&query.target_type);
+    filter.target_id = text(&query.target_id);
+    filter.outcome = text(&query.outcome);
+
+    match audit_events::list_audit_events(pool.get_ref(), &filter, limit, offset).await {
+        Ok((events, count)) => {
+            let total_page = if count % limit == 0 { count / limit } else { count / limit + 1 };
+            HttpResponse::Ok().json(
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
+                    response_data: Some(json!(ListAuditEvents { page, limit, total: count, total_page, events })),
+                }
+            )
+        }
+        Err(err) => HttpResponse::InternalServerError().json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_INTERNAL.to_string(),
+                response_desc: err.to_string(),
+                response_data: None,
+            }
+        ),
+    }
+}
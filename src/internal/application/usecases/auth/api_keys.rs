@@ -0,0 +1,193 @@
+use crate::internal::domain::entities::auth::api_key::{CreateApiKeyRequest, CreateApiKeyResponse};
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::internal::domain::entities::response::Response;
+use crate::internal::constant::status::{SUCCESS, FAILED_AUTHORIZED, FAILED_INTERNAL, FAILED_NOT_FOUND, FAILED_REQUIRED};
+use crate::internal::application::repositories::auth::{api_keys, roles};
+use crate::internal::application::repositories::users::users;
+use crate::internal::pkg::utils::crypto::{generate_token, hash_token, hashes_match};
+use crate::middlewares::jwt::parse_jwt_exp;
+use actix_web::{http::StatusCode, HttpResponse, Responder, web};
+use chrono::Utc;
+use rand::{RngCore, rngs::OsRng};
+use serde_json::json;
+use sqlx::{Error, postgres::PgPool};
+
+// Keys look like "rcb_<prefix>.<secret>", the prefix identifies the key and is safe to show.
+const KEY_PREFIX: &str = "rcb_";
+
+#[allow(dead_code)]
+pub enum ApiKeyError {
+    Invalid(&'static str),
+    DatabaseError(Error),
+}
+
+impl From<Error> for ApiKeyError {
+    fn from(err: Error) -> Self {
+        ApiKeyError::DatabaseError(err)
+    }
+}
+
+fn error_response(status: StatusCode, code: &str, desc: String) -> HttpResponse {
+    HttpResponse::build(status).json(
+        Response::<serde_json::Value> {
+            response_code: code.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+fn internal_error(desc: String) -> HttpResponse {
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, FAILED_INTERNAL, desc)
+}
+
+async fn caller_id(pool: &PgPool, identity: &Identity) -> Result<i32, HttpResponse> {
+    match users::get_user_username(pool, &identity.sub).await {
+        Ok(user) => Ok(user.id),
+        Err(Error::RowNotFound) => Err(error_response(StatusCode::UNAUTHORIZED, FAILED_AUTHORIZED, "Unauthorized".to_string())),
+        Err(err) => Err(internal_error(err.to_string())),
+    }
+}
+
+// Resolve the identity behind a presented key. The key gets the intersection of its
+// scopes and the permissions its owner holds right now.
+pub async fn authenticate_api_key(pool: &PgPool, key: &str) -> Result<Identity, ApiKeyError> {
+    let prefix = match key.trim().strip_prefix(KEY_PREFIX).and_then(|rest| rest.split_once('.')) {
+        Some((prefix, _)) => prefix,
+        None => return Err(ApiKeyError::Invalid("API key is invalid.")),
+    };
+
+    let api_key = match api_keys::get_api_key_by_prefix(pool, prefix).await {
+        Ok(api_key) => api_key,
+        Err(Error::RowNotFound) => return Err(ApiKeyError::Invalid("API key is invalid.")),
+        Err(err) => return Err(err.into()),
+    };
+    if !hashes_match(&hash_token(key.trim()), &api_key.key_hash) {
+        return Err(ApiKeyError::Invalid("API key is invalid."));
+    }
+    let now = Utc::now().naive_utc();
+    if api_key.revoked_at.is_some() {
+        return Err(ApiKeyError::Invalid("API key has been revoked."));
+    }
+    if api_key.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(ApiKeyError::Invalid("API key has expired."));
+    }
+
+    let owner = match users::get_user(pool, api_key.user_id).await {
+        Ok(owner) => owner,
+        Err(Error::RowNotFound) => return Err(ApiKeyError::Invalid("API key owner has been deleted.")),
+        Err(err) => return Err(err.into()),
+    };
+    let owner_permissions = roles::get_user_permissions(pool, api_key.user_id).await?;
+    let scopes = api_key.scopes.into_iter()
+        .filter(|scope| owner_permissions.contains(scope))
+        .collect();
+    api_keys::touch_api_key(pool, api_key.id, now).await?;
+
+    Ok(Identity::from_api_key(api_key.id, owner.username, scopes))
+}
+
+pub async fn create_api_key(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+    req: web::Json<CreateApiKeyRequest>,
+) -> impl Responder {
+    // A leaked key must not be able to mint more keys.
+    if identity.api_key_id.is_some() {
+        return error_response(StatusCode::FORBIDDEN, FAILED_AUTHORIZED, "API keys cannot create API keys.".to_string());
+    }
+    if req.name.trim().is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, "Name required".to_string());
+    }
+    if req.scopes.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, "Scopes required".to_string());
+    }
+    let not_granted: Vec<&str> = req.scopes.iter()
+        .filter(|scope| !identity.has_permission(scope))
+        .map(String::as_str)
+        .collect();
+    if !not_granted.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, format!("Scope not granted: {}", not_granted.join(", ")));
+    }
+
+    let expires_at = match req.expires_in.as_deref() {
+        None => None,
+        Some(expires_in) => match parse_jwt_exp(expires_in)
+            .and_then(|duration| chrono::Duration::from_std(duration).ok())
+            .and_then(|duration| Utc::now().naive_utc().checked_add_signed(duration))
+        {
+            Some(expires_at) => Some(expires_at),
+            None => {
+                return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, format!("Invalid expiresIn format: {}", expires_in));
+            }
+        },
+    };
+
+    let user_id = match caller_id(pool.get_ref(), &identity).await {
+        Ok(user_id) => user_id,
+        Err(resp) => return resp,
+    };
+
+    let mut prefix_bytes = [0u8; 6];
+    OsRng.fill_bytes(&mut prefix_bytes);
+    let prefix = hex::encode(prefix_bytes);
+    let key = format!("{}{}.{}", KEY_PREFIX, prefix, generate_token(32));
+
+    let mut scopes = req.scopes.clone();
+    scopes.sort();
+    scopes.dedup();
+    match api_keys::create_api_key(pool.get_ref(), user_id, req.name.trim(), &prefix, &hash_token(&key), &scopes, expires_at).await {
+        Ok(api_key) => HttpResponse::Ok().json(
+            Response {
+                response_code: SUCCESS.to_string(),
+                response_desc: "OK".to_string(),
+                response_data: Some(json!(CreateApiKeyResponse { api_key, key })),
+            }
+        ),
+        Err(err) => internal_error(err.to_string()),
+    }
+}
+
+pub async fn list_api_keys(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+) -> impl Responder {
+    let user_id = match caller_id(pool.get_ref(), &identity).await {
+        Ok(user_id) => user_id,
+        Err(resp) => return resp,
+    };
+
+    match api_keys::list_api_keys(pool.get_ref(), user_id).await {
+        Ok(keys) => HttpResponse::Ok().json(
+            Response {
+                response_code: SUCCESS.to_string(),
+                response_desc: "OK".to_string(),
+                response_data: Some(json!(keys)),
+            }
+        ),
+        Err(err) => internal_error(err.to_string()),
+    }
+}
+
+pub async fn revoke_api_key(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+    path: web::Path<i32>,
+) -> impl Responder {
+    let user_id = match caller_id(pool.get_ref(), &identity).await {
+        Ok(user_id) => user_id,
+        Err(resp) => return resp,
+    };
+
+    match api_keys::revoke_api_key(pool.get_ref(), path.into_inner(), user_id).await {
+        Ok(true) => HttpResponse::Ok().json(
+            Response::<serde_json::Value> {
+                response_code: SUCCESS.to_string(),
+                response_desc: "OK".to_string(),
+                response_data: None,
+            }
+        ),
+        Ok(false) => error_response(StatusCode::NOT_FOUND, FAILED_NOT_FOUND, "API key not found".to_string()),
+        Err(err) => internal_error(err.to_string()),
+    }
+}
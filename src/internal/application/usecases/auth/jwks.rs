@@ -0,0 +1,9 @@
+use crate::internal::pkg::utils::jwt_keys::JWT_KEYS;
+use actix_web::{http::header, HttpResponse, Responder};
+
+// Served as a bare JWK Set so standard JWT libraries can consume it.
+pub async fn jwks() -> impl Responder {
+    HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "public, max-age=300"))
+        .json(JWT_KEYS.jwks())
+}
@@ -0,0 +1,162 @@
+use crate::internal::domain::entities::auth::lockout::LockoutsQuery;
+use crate::internal::domain::entities::response::Response;
+use crate::internal::constant::status::{SUCCESS, FAILED_INTERNAL, FAILED_NOT_FOUND, FAILED_REQUIRED};
+use crate::internal::application::repositories::auth::login_throttles::{self, KIND_ACCOUNT, KIND_IP};
+use crate::internal::application::repositories::users::users::MAX_EMAIL_LEN;
+use crate::internal::pkg::utils::crypto::hash_token;
+use crate::config::settings::CONFIG;
+use crate::middlewares::jwt::parse_jwt_exp;
+use actix_web::{HttpResponse, Responder, web};
+use chrono::{Duration, NaiveDateTime, Utc};
+use serde_json::json;
+use sqlx::postgres::PgPool;
+
+fn config_duration(value: &str, fallback: Duration) -> Duration {
+    parse_jwt_exp(value)
+        .and_then(|duration| Duration::from_std(duration).ok())
+        .unwrap_or(fallback)
+}
+
+fn window_start(now: NaiveDateTime) -> NaiveDateTime {
+    now - config_duration(&CONFIG.login_attempt_window, Duration::minutes(15))
+}
+
+// Lockout for the nth failure once `max_attempts` is reached: base, 2x base, 4x base... capped.
+fn lockout_duration(failures: i32, max_attempts: i32) -> Option<Duration> {
+    if failures < max_attempts {
+        return None;
+    }
+    let base = config_duration(&CONFIG.login_lockout_base, Duration::seconds(30));
+    let max = config_duration(&CONFIG.login_lockout_max, Duration::minutes(15));
+    let exponent = (failures - max_attempts).min(20) as u32;
+    Some((base * 2i32.pow(exponent)).min(max))
+}
+
+// Normalise what the caller typed so "Bob" and "bob " share a counter. Logins too
+// long to name any account are hashed, so they still fit the key column.
+pub fn account_key(login: &str) -> String {
+    let login = login.trim().to_lowercase();
+    if login.chars().count() > MAX_EMAIL_LEN {
+        return hash_token(&login);
+    }
+    login
+}
+
+// Seconds until the account or IP may try again, None when neither is locked.
+pub async fn retry_after(pool: &PgPool, account: &str, ip: Option<&str>) -> Result<Option<i64>, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let mut locked_until = login_throttles::get_locked_until(pool, KIND_ACCOUNT, account, now).await?;
+    if let Some(ip) = ip {
+        locked_until = locked_until.max(login_throttles::get_locked_until(pool, KIND_IP, ip, now).await?);
+    }
+    Ok(locked_until.map(|until| (until - now).num_seconds().max(1)))
+}
+
+pub async fn record_failed_login(pool: &PgPool, account: &str, ip: Option<&str>) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let window_start = window_start(now);
+
+    let mut counters = vec![(KIND_ACCOUNT, account, CONFIG.login_max_attempts)];
+    if let Some(ip) = ip {
+        counters.push((KIND_IP, ip, CONFIG.login_ip_max_attempts));
+    }
+    for (kind, key, max_attempts) in counters {
+        let failures = login_throttles::record_failure(pool, kind, key, now, window_start).await?;
+        if let Some(duration) = lockout_duration(failures, max_attempts) {
+            login_throttles::lock(pool, kind, key, now + duration).await?;
+        }
+    }
+
+    login_throttles::delete_stale(pool, window_start, now).await
+}
+
+// A successful login clears the account counter. The IP counter is left alone so
+// one known password cannot be used to keep guessing others from the same address.
+pub async fn record_successful_login(pool: &PgPool, account: &str) -> Result<(), sqlx::Error> {
+    login_throttles::clear(pool, KIND_ACCOUNT, account).await?;
+    Ok(())
+}
+
+pub async fn list_lockouts(
+    pool: web::Data<PgPool>,
+    query: web::Query<LockoutsQuery>,
+) -> impl Responder {
+    let kind = query.kind.as_deref().map(str::trim);
+    if let Some(kind) = kind
+        && kind != KIND_ACCOUNT
+        && kind != KIND_IP
+    {
+        return HttpResponse::BadRequest().json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_REQUIRED.to_string(),
+                response_desc: format!("Invalid kind: {}", kind),
+                response_data: None,
+            }
+        );
+    }
+
+    let now = Utc::now().naive_utc();
+    match login_throttles::list_throttles(pool.get_ref(), kind, query.locked.unwrap_or(false), window_start(now), now).await {
+        Ok(throttles) => HttpResponse::Ok().json(
+            Response {
+                response_code: SUCCESS.to_string(),
+                response_desc: "OK".to_string(),
+                response_data: Some(json!(throttles)),
+            }
+        ),
+        Err(err) => HttpResponse::InternalServerError().json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_INTERNAL.to_string(),
+                response_desc: err.to_string(),
+                response_data: None,
+            }
+        ),
+    }
+}
+
+pub async fn clear_lockout(
+    pool: web::Data<PgPool>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (kind, key) = path.into_inner();
+    let key = if kind == KIND_ACCOUNT { account_key(&key) } else { key };
+    match login_throttles::clear(pool.get_ref(), &kind, &key).await {
+        Ok(true) => HttpResponse::Ok().json(
+            Response::<serde_json::Value> {
+                response_code: SUCCESS.to_string(),
+                response_desc: "OK".to_string(),
+                response_data: None,
+            }
+        ),
+        Ok(false) => HttpResponse::NotFound().json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_NOT_FOUND.to_string(),
+                response_desc: "Lockout not found".to_string(),
+                response_data: None,
+            }
+        ),
+        Err(err) => HttpResponse::InternalServerError().json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_INTERNAL.to_string(),
+                response_desc: err.to_string(),
+                response_data: None,
+            }
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn account_keys_fit_the_key_column() {
+        assert_eq!(account_key(" Bob "), "bob");
+        assert_eq!(account_key("Alice@Example.com"), "alice@example.com");
+
+        let long = "x".repeat(1000);
+        assert_eq!(account_key(&long), hash_token(&long));
+        assert_eq!(account_key(&format!(" {} ", long.to_uppercase())), account_key(&long));
+        assert!(account_key(&long).len() <= 255);
+    }
+}
@@ -1,101 +1,159 @@
-use crate::internal::domain::entities::auth::login::{LoginRequest, Claims, Token};
+use crate::internal::domain::entities::auth::login::LoginRequest;
 use crate::internal::domain::entities::response::Response;
 use crate::internal::constant::status::{SUCCESS, FAILED_AUTHORIZED, FAILED_INTERNAL, FAILED_REQUIRED};
-use crate::internal::application::repositories::users::users::get_user_or;
-use crate::config::settings::CONFIG;
-use crate::middlewares::jwt::parse_jwt_exp;
-use actix_web::{HttpResponse, Responder, web, Error};
-use jsonwebtoken::{encode, EncodingKey, Header};
-use sqlx::postgres::PgPool;
+use crate::internal::application::repositories::users::users::{get_user_or, rehash_password};
+use crate::internal::application::usecases::audit::audit::{record, AuditEntry, OUTCOME_FAILURE, OUTCOME_SUCCESS};
+use crate::internal::application::usecases::auth::lockout::{account_key, record_failed_login, record_successful_login, retry_after};
+use crate::internal::application::usecases::auth::mfa::start_challenge;
+use crate::internal::application::usecases::auth::token::issue_token;
+use crate::internal::pkg::password::password::{hash_password, verify_password, Verification};
+use crate::internal::pkg::utils::request::client_ip;
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use log::warn;
+use sqlx::{Error, postgres::PgPool};
 use serde_json::json;
-use tokio::task;
-use bcrypt::verify;
-use std::time::{SystemTime, UNIX_EPOCH};
+
+fn unauthorized() -> HttpResponse {
+    HttpResponse::Unauthorized().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_AUTHORIZED.to_string(),
+            response_desc: "Unauthorized".to_string(),
+            response_data: None,
+        }
+    )
+}
+
+fn internal_error(desc: String) -> HttpResponse {
+    HttpResponse::InternalServerError().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_INTERNAL.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+fn too_many_attempts(retry_after: i64) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", retry_after.to_string()))
+        .json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_AUTHORIZED.to_string(),
+                response_desc: "Too many failed login attempts, try again later.".to_string(),
+                response_data: None,
+            }
+        )
+}
 
 pub async fn login(
     pool: web::Data<PgPool>,
+    http_req: HttpRequest,
     req: web::Json<LoginRequest>,
 ) -> impl Responder {
-    if req.username.trim().len() > 0 || req.password.trim().len() > 0 {
-        if let Ok(user) = get_user_or(pool.get_ref(), req.username.as_str(), req.username.as_str()).await {
-            let verified = match task::spawn_blocking(move || verify(req.password.as_str(), &user.password)).await {
-                Ok(Ok(true)) => true,
-                _ => false,
-            };
+    if req.username.trim().is_empty() || req.password.trim().is_empty() {
+        return HttpResponse::BadRequest()
+        .json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_REQUIRED.to_string(),
+                response_desc: "username or password required".to_string(),
+                response_data: None,
+            }
+        );
+    }
 
-            if verified {
-                let jwt_exp = CONFIG.jwt_exp.clone();
-                if let Some(exp_duration) = parse_jwt_exp(&jwt_exp) {
-                    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-                    let exp_timestamp = now + exp_duration;
-                    // println!("JWT expires at UNIX timestamp: {}", exp_timestamp.as_secs());
-                    let claims = Claims {
-                        sub: user.username,
-                        name: "test".to_string(),
-                        exp: exp_timestamp.as_secs() as usize, // Expiration timestamp
-                    };
+    let user = match get_user_or(pool.get_ref(), req.username.as_str(), req.username.as_str()).await {
+        Ok(user) => Some(user),
+        Err(Error::RowNotFound) => None,
+        Err(err) => return internal_error(err.to_string()),
+    };
 
-                    let token: String = match encode(
-                        &Header::default(),
-                        &claims,
-                        &EncodingKey::from_secret(CONFIG.secret_key_jwt.clone().as_ref())
-                    ) {
-                        Ok(token) => token,
-                        Err(err) => {
-                            return Ok::<HttpResponse, Error>(HttpResponse::InternalServerError().json(
-                                Response::<serde_json::Value> {
-                                    response_code: FAILED_INTERNAL.to_string(),
-                                    response_desc: err.to_string(),
-                                    response_data: None,
-                                }
-                            ));
-                        }
-                    };
+    // Unknown names are throttled the same way, a lockout says nothing about whether the account exists.
+    let account = account_key(user.as_ref().map_or(req.username.as_str(), |user| user.username.as_str()));
+    let ip = client_ip(&http_req);
+    let audit = |outcome| {
+        let entry = AuditEntry::new("auth.login", outcome);
+        match &user {
+            Some(user) => entry.by_user(Some(user.id), &user.username).on("user", user.id),
+            None => entry.by_user(None, req.username.trim()),
+        }
+    };
+    match retry_after(pool.get_ref(), &account, ip.as_deref()).await {
+        Ok(Some(seconds)) => {
+            record(pool.get_ref(), &http_req, audit("locked")).await;
+            return too_many_attempts(seconds);
+        }
+        Ok(None) => {}
+        Err(err) => return internal_error(err.to_string()),
+    }
 
-                    let data: Token = Token { token };
+    let hashed = user.as_ref().map(|user| user.password.clone());
+    let verification = verify_password(req.password.clone(), hashed).await;
 
-                    Ok(HttpResponse::Ok().json(
-                        Response {
-                            response_code: SUCCESS.to_string(),
-                            response_desc: "OK".to_string(),
-                            response_data: Some(json!(data)),
-                        }
-                    ))
-                } else {
-                    Ok::<HttpResponse, actix_web::Error>(HttpResponse::InternalServerError().json(
-                        Response::<serde_json::Value> {
-                            response_code: FAILED_INTERNAL.to_string(),
-                            response_desc: format!("Invalid JWT_EXP format: {}", jwt_exp),
-                            response_data: None,
-                        }
-                    ))
-                }
-            } else {
-                Ok::<HttpResponse, actix_web::Error>(HttpResponse::Unauthorized().json(
-                    Response::<serde_json::Value> {
-                        response_code: FAILED_AUTHORIZED.to_string(),
-                        response_desc: "Unauthorized".to_string(),
-                        response_data: None,
-                    }
-                ))
+    let user = match user {
+        Some(user) if verification != Verification::Failed => user,
+        _ => {
+            record(pool.get_ref(), &http_req, audit(OUTCOME_FAILURE)).await;
+            if let Err(err) = record_failed_login(pool.get_ref(), &account, ip.as_deref()).await {
+                return internal_error(err.to_string());
             }
-        } else {
-            Ok::<HttpResponse, actix_web::Error>(HttpResponse::Unauthorized().json(
-                Response::<serde_json::Value> {
-                    response_code: FAILED_AUTHORIZED.to_string(),
-                    response_desc: "Unauthorized".to_string(),
-                    response_data: None,
-                }
-            ))
+            return unauthorized();
         }
-    } else {
-        Ok::<HttpResponse, actix_web::Error>(HttpResponse::BadRequest()
-        .json(
+    };
+
+    if let Err(err) = record_successful_login(pool.get_ref(), &account).await {
+        return internal_error(err.to_string());
+    }
+
+    // Legacy bcrypt hashes, or ones made with old parameters, are upgraded while we
+    // have the plain password. A failed upgrade is retried on the next login.
+    if verification == Verification::NeedsRehash {
+        match hash_password(req.password.clone()).await {
+            Ok(upgraded) => if let Err(err) = rehash_password(pool.get_ref(), user.id, &user.password, &upgraded).await {
+                warn!("Could not upgrade the password hash of user {}: {}", user.id, err);
+            },
+            Err(err) => warn!("Could not upgrade the password hash of user {}: {}", user.id, err),
+        }
+    }
+
+    let audit = |outcome| AuditEntry::new("auth.login", outcome).by_user(Some(user.id), &user.username).on("user", user.id);
+    if user.email_verified_at.is_none() {
+        record(pool.get_ref(), &http_req, audit("unverified")).await;
+        return HttpResponse::Forbidden().json(
             Response::<serde_json::Value> {
-                response_code: FAILED_REQUIRED.to_string(),
-                    response_desc: "username or password required".to_string(),
-                    response_data: None,
+                response_code: FAILED_AUTHORIZED.to_string(),
+                response_desc: "Email address is not verified.".to_string(),
+                response_data: None,
             }
-        ))
+        );
+    }
+
+    // Users with MFA get a challenge to complete at /auth/mfa/verify instead of a token.
+    match start_challenge(pool.get_ref(), user.id).await {
+        Ok(Some(challenge)) => {
+            record(pool.get_ref(), &http_req, audit("mfa_required")).await;
+            return HttpResponse::Ok().json(
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "MFA required".to_string(),
+                    response_data: Some(json!(challenge)),
+                }
+            );
+        }
+        Ok(None) => {}
+        Err(err) => return internal_error(err),
+    }
+
+    match issue_token(pool.get_ref(), &http_req, &user, None).await {
+        Ok(data) => {
+            record(pool.get_ref(), &http_req, audit(OUTCOME_SUCCESS)).await;
+            HttpResponse::Ok().json(
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
+                    response_data: Some(json!(data)),
+                }
+            )
+        }
+        Err(err) => internal_error(err),
     }
 }
@@ -0,0 +1,103 @@
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::internal::domain::entities::auth::login::Claims;
+use crate::internal::domain::entities::response::Response;
+use crate::internal::constant::status::{SUCCESS, FAILED_AUTHORIZED, FAILED_INTERNAL};
+use crate::internal::application::repositories::auth::{revocations, sessions};
+use crate::internal::application::repositories::users::users;
+use crate::internal::application::usecases::auth::revocation::{forget_user, mark_token_revoked};
+use actix_web::{HttpResponse, Responder, web};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::postgres::PgPool;
+
+fn unauthorized() -> HttpResponse {
+    HttpResponse::Unauthorized().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_AUTHORIZED.to_string(),
+            response_desc: "Unauthorized".to_string(),
+            response_data: None,
+        }
+    )
+}
+
+fn internal_error(desc: String) -> HttpResponse {
+    HttpResponse::InternalServerError().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_INTERNAL.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+fn ok() -> HttpResponse {
+    HttpResponse::Ok().json(
+        Response::<serde_json::Value> {
+            response_code: SUCCESS.to_string(),
+            response_desc: "OK".to_string(),
+            response_data: None,
+        }
+    )
+}
+
+fn token_expiry(claims: &Claims) -> NaiveDateTime {
+    DateTime::from_timestamp(claims.exp as i64, 0)
+        .unwrap_or_else(Utc::now)
+        .naive_utc()
+}
+
+// Revoke the presented access token and the refresh session it was issued with.
+pub async fn logout(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+) -> impl Responder {
+    // Only access tokens carry a jti and refresh session to revoke.
+    let claims = match identity.claims {
+        Some(c) => c,
+        None => return unauthorized(),
+    };
+
+    let user_id = users::get_user_username(pool.get_ref(), &claims.sub).await.ok().map(|user| user.id);
+    if let Err(err) = revocations::revoke_token(pool.get_ref(), &claims.jti, user_id, token_expiry(&claims)).await {
+        return internal_error(err.to_string());
+    }
+    mark_token_revoked(&claims.jti);
+
+    if let Err(err) = sessions::revoke_family(pool.get_ref(), &claims.sid).await {
+        return internal_error(err.to_string());
+    }
+
+    let _ = revocations::delete_expired_revocations(pool.get_ref(), Utc::now().naive_utc()).await;
+
+    ok()
+}
+
+// Invalidate every access token and refresh session of the caller.
+pub async fn logout_all(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+) -> impl Responder {
+    // Only access tokens carry a jti and refresh session to revoke.
+    let claims = match identity.claims {
+        Some(c) => c,
+        None => return unauthorized(),
+    };
+
+    let user = match users::get_user_username(pool.get_ref(), &claims.sub).await {
+        Ok(user) => user,
+        Err(_) => return unauthorized(),
+    };
+
+    if let Err(err) = users::revoke_user_tokens(pool.get_ref(), user.id, Utc::now().naive_utc()).await {
+        return internal_error(err.to_string());
+    }
+    if let Err(err) = revocations::revoke_token(pool.get_ref(), &claims.jti, Some(user.id), token_expiry(&claims)).await {
+        return internal_error(err.to_string());
+    }
+    if let Err(err) = sessions::revoke_user_sessions(pool.get_ref(), user.id).await {
+        return internal_error(err.to_string());
+    }
+    mark_token_revoked(&claims.jti);
+    forget_user(&claims.sub);
+
+    ok()
+}
@@ -0,0 +1,271 @@
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::internal::domain::entities::auth::mfa::{
+    MfaChallengeResponse, MfaCodeRequest, MfaEnrollResponse, MfaRecoveryCodesResponse, MfaVerifyRequest, UserMfa,
+};
+use crate::internal::domain::entities::response::Response;
+use crate::internal::constant::status::{SUCCESS, FAILED_AUTHORIZED, FAILED_EXIST, FAILED_INTERNAL, FAILED_NOT_FOUND, FAILED_REQUIRED};
+use crate::internal::application::repositories::auth::mfa;
+use crate::internal::application::repositories::users::users;
+use crate::internal::application::usecases::audit::audit::{record, AuditEntry, OUTCOME_FAILURE, OUTCOME_SUCCESS};
+use crate::internal::application::usecases::auth::token::issue_token;
+use crate::internal::pkg::utils::crypto::{generate_token, hash_token};
+use crate::internal::pkg::utils::totp;
+use crate::config::settings::CONFIG;
+use crate::middlewares::jwt::parse_jwt_exp;
+use actix_web::{http::StatusCode, HttpRequest, HttpResponse, Responder, web};
+use chrono::Utc;
+use rand::{Rng, rngs::OsRng};
+use serde_json::json;
+use sqlx::{Error, postgres::PgPool};
+
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+
+fn error_response(status: StatusCode, code: &str, desc: &str) -> HttpResponse {
+    HttpResponse::build(status).json(
+        Response::<serde_json::Value> {
+            response_code: code.to_string(),
+            response_desc: desc.to_string(),
+            response_data: None,
+        }
+    )
+}
+
+fn internal_error(desc: String) -> HttpResponse {
+    HttpResponse::InternalServerError().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_INTERNAL.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+fn invalid_code() -> HttpResponse {
+    error_response(StatusCode::UNAUTHORIZED, FAILED_AUTHORIZED, "Invalid code.")
+}
+
+fn ok(data: Option<serde_json::Value>) -> HttpResponse {
+    HttpResponse::Ok().json(
+        Response {
+            response_code: SUCCESS.to_string(),
+            response_desc: "OK".to_string(),
+            response_data: data,
+        }
+    )
+}
+
+fn unix_now() -> u64 {
+    Utc::now().timestamp() as u64
+}
+
+// Recovery codes look like "k7m2p-x9q4r", they are compared without dashes or case.
+fn generate_recovery_code() -> String {
+    let chars: String = (0..10)
+        .map(|_| RECOVERY_CODE_ALPHABET[OsRng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+        .collect();
+    format!("{}-{}", &chars[..5], &chars[5..])
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    hash_token(&normalized)
+}
+
+// Accept a TOTP code for an unused time step, or an unused recovery code.
+async fn check_code(pool: &PgPool, mfa: &UserMfa, code: &str) -> Result<bool, Error> {
+    if let Some(step) = totp::verify(&mfa.secret, code, unix_now()) {
+        return mfa::use_totp_step(pool, mfa.user_id, step as i64).await;
+    }
+    mfa::use_recovery_code(pool, mfa.user_id, &hash_recovery_code(code)).await
+}
+
+async fn caller_id(pool: &PgPool, identity: &Identity) -> Result<i32, HttpResponse> {
+    if identity.api_key_id.is_some() {
+        return Err(error_response(StatusCode::FORBIDDEN, FAILED_AUTHORIZED, "MFA cannot be managed with an API key."));
+    }
+    match users::get_user_username(pool, &identity.sub).await {
+        Ok(user) => Ok(user.id),
+        Err(Error::RowNotFound) => Err(error_response(StatusCode::UNAUTHORIZED, FAILED_AUTHORIZED, "Unauthorized")),
+        Err(err) => Err(internal_error(err.to_string())),
+    }
+}
+
+// Called by login once the password checks out. Returns a challenge when the
+// user has MFA enabled, None when a token can be issued right away.
+pub async fn start_challenge(pool: &PgPool, user_id: i32) -> Result<Option<MfaChallengeResponse>, String> {
+    match mfa::get_user_mfa(pool, user_id).await {
+        Ok(mfa) if mfa.enabled_at.is_some() => {}
+        Ok(_) | Err(Error::RowNotFound) => return Ok(None),
+        Err(err) => return Err(err.to_string()),
+    }
+
+    let challenge_exp = CONFIG.mfa_challenge_exp.clone();
+    let challenge_duration = parse_jwt_exp(&challenge_exp)
+        .and_then(|duration| chrono::Duration::from_std(duration).ok())
+        .ok_or_else(|| format!("Invalid MFA_CHALLENGE_EXP format: {}", challenge_exp))?;
+
+    let challenge_token = generate_token(32);
+    let expires_at = Utc::now().naive_utc() + challenge_duration;
+    mfa::create_challenge(pool, user_id, &hash_token(&challenge_token), expires_at)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    Ok(Some(MfaChallengeResponse {
+        mfa_required: true,
+        challenge_token,
+        expires_in: challenge_duration.num_seconds(),
+    }))
+}
+
+pub async fn enroll(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+) -> impl Responder {
+    let user_id = match caller_id(pool.get_ref(), &identity).await {
+        Ok(user_id) => user_id,
+        Err(resp) => return resp,
+    };
+
+    let secret = totp::generate_secret();
+    match mfa::save_pending_mfa(pool.get_ref(), user_id, &secret).await {
+        Ok(true) => {}
+        Ok(false) => return error_response(StatusCode::BAD_REQUEST, FAILED_EXIST, "MFA is already enabled."),
+        Err(err) => return internal_error(err.to_string()),
+    }
+
+    let otpauth_uri = totp::otpauth_uri(&CONFIG.mfa_issuer, &identity.sub, &secret);
+    ok(Some(json!(MfaEnrollResponse { secret, otpauth_uri })))
+}
+
+// Confirm the enrolment with a first code, recovery codes are only shown here.
+pub async fn activate(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+    req: web::Json<MfaCodeRequest>,
+) -> impl Responder {
+    if req.code.trim().is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, "Code required");
+    }
+    let user_id = match caller_id(pool.get_ref(), &identity).await {
+        Ok(user_id) => user_id,
+        Err(resp) => return resp,
+    };
+
+    let pending = match mfa::get_user_mfa(pool.get_ref(), user_id).await {
+        Ok(mfa) if mfa.enabled_at.is_some() => {
+            return error_response(StatusCode::BAD_REQUEST, FAILED_EXIST, "MFA is already enabled.");
+        }
+        Ok(mfa) => mfa,
+        Err(Error::RowNotFound) => {
+            return error_response(StatusCode::BAD_REQUEST, FAILED_NOT_FOUND, "MFA enrolment has not been started.");
+        }
+        Err(err) => return internal_error(err.to_string()),
+    };
+
+    let step = match totp::verify(&pending.secret, &req.code, unix_now()) {
+        Some(step) => step as i64,
+        None => return invalid_code(),
+    };
+
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+    let code_hashes: Vec<String> = recovery_codes.iter().map(|code| hash_recovery_code(code)).collect();
+    match mfa::activate_mfa(pool.get_ref(), user_id, step, &code_hashes).await {
+        Ok(true) => ok(Some(json!(MfaRecoveryCodesResponse { recovery_codes }))),
+        Ok(false) => invalid_code(),
+        Err(err) => internal_error(err.to_string()),
+    }
+}
+
+pub async fn disable(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+    req: web::Json<MfaCodeRequest>,
+) -> impl Responder {
+    if req.code.trim().is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, "Code required");
+    }
+    let user_id = match caller_id(pool.get_ref(), &identity).await {
+        Ok(user_id) => user_id,
+        Err(resp) => return resp,
+    };
+
+    let enabled = match mfa::get_user_mfa(pool.get_ref(), user_id).await {
+        Ok(mfa) if mfa.enabled_at.is_some() => mfa,
+        Ok(_) | Err(Error::RowNotFound) => {
+            return error_response(StatusCode::BAD_REQUEST, FAILED_NOT_FOUND, "MFA is not enabled.");
+        }
+        Err(err) => return internal_error(err.to_string()),
+    };
+
+    match check_code(pool.get_ref(), &enabled, &req.code).await {
+        Ok(true) => {}
+        Ok(false) => return invalid_code(),
+        Err(err) => return internal_error(err.to_string()),
+    }
+    if let Err(err) = mfa::disable_mfa(pool.get_ref(), user_id).await {
+        return internal_error(err.to_string());
+    }
+
+    ok(None)
+}
+
+// Second login step, trades a challenge token and a code for a token pair.
+pub async fn verify(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+    req: web::Json<MfaVerifyRequest>,
+) -> impl Responder {
+    if req.challenge_token.trim().is_empty() || req.code.trim().is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, "challengeToken and code required");
+    }
+
+    let challenge_hash = hash_token(req.challenge_token.trim());
+    let now = Utc::now().naive_utc();
+    let user_id = match mfa::attempt_challenge(pool.get_ref(), &challenge_hash, now, MAX_CHALLENGE_ATTEMPTS).await {
+        Ok(user_id) => user_id,
+        Err(Error::RowNotFound) => {
+            return error_response(StatusCode::UNAUTHORIZED, FAILED_AUTHORIZED, "Invalid or expired MFA challenge.");
+        }
+        Err(err) => return internal_error(err.to_string()),
+    };
+
+    let enabled = match mfa::get_user_mfa(pool.get_ref(), user_id).await {
+        Ok(mfa) if mfa.enabled_at.is_some() => mfa,
+        Ok(_) | Err(Error::RowNotFound) => return invalid_code(),
+        Err(err) => return internal_error(err.to_string()),
+    };
+    let user = match users::get_user(pool.get_ref(), user_id).await {
+        Ok(user) => user,
+        Err(err) => return internal_error(err.to_string()),
+    };
+    let audit = |outcome| AuditEntry::new("auth.mfa.verify", outcome).by_user(Some(user.id), &user.username).on("user", user.id);
+    match check_code(pool.get_ref(), &enabled, &req.code).await {
+        Ok(true) => {}
+        Ok(false) => {
+            record(pool.get_ref(), &http_req, audit(OUTCOME_FAILURE)).await;
+            return invalid_code();
+        }
+        Err(err) => return internal_error(err.to_string()),
+    }
+
+    match mfa::complete_challenge(pool.get_ref(), &challenge_hash).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return error_response(StatusCode::UNAUTHORIZED, FAILED_AUTHORIZED, "Invalid or expired MFA challenge.");
+        }
+        Err(err) => return internal_error(err.to_string()),
+    }
+
+    match issue_token(pool.get_ref(), &http_req, &user, None).await {
+        Ok(data) => {
+            record(pool.get_ref(), &http_req, audit(OUTCOME_SUCCESS)).await;
+            ok(Some(json!(data)))
+        }
+        Err(err) => internal_error(err),
+    }
+}
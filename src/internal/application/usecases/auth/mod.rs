@@ -1 +1,13 @@
-pub mod login;
\ No newline at end of file
+pub mod api_keys;
+pub mod jwks;
+pub mod lockout;
+pub mod login;
+pub mod logout;
+pub mod mfa;
+pub mod oidc;
+pub mod password;
+pub mod profile;
+pub mod refresh;
+pub mod registration;
+pub mod revocation;
+pub mod token;
\ No newline at end of file
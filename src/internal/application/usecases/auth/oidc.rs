@@ -0,0 +1,302 @@
+use crate::internal::domain::entities::auth::oidc::OidcCallbackQuery;
+use crate::internal::domain::entities::users::users::{CreateUserRequest, User};
+use crate::internal::domain::entities::response::Response;
+use crate::internal::constant::status::{SUCCESS, FAILED_AUTHORIZED, FAILED_EXIST, FAILED_INTERNAL, FAILED_NOT_FOUND, FAILED_REQUIRED};
+use crate::internal::application::repositories::auth::oidc;
+use crate::internal::application::repositories::users::users::{self, MAX_EMAIL_LEN, MAX_NAME_LEN, MAX_USERNAME_LEN};
+use crate::internal::application::usecases::audit::audit::{record, AuditEntry, OUTCOME_FAILURE, OUTCOME_SUCCESS};
+use crate::internal::application::usecases::auth::mfa::start_challenge;
+use crate::internal::application::usecases::auth::token::issue_token;
+use crate::internal::pkg::oidc::oidc::{IdTokenClaims, OidcClient, OIDC_CLIENT};
+use crate::internal::pkg::utils::crypto::{generate_token, hash_token};
+use crate::config::settings::CONFIG;
+use crate::middlewares::jwt::parse_jwt_exp;
+use actix_web::{http::{header, StatusCode}, HttpRequest, HttpResponse, Responder, web};
+use chrono::Utc;
+use log::{error, warn};
+use serde_json::json;
+use sqlx::{Error, postgres::PgPool};
+
+fn error_response(status: StatusCode, code: &str, desc: String) -> HttpResponse {
+    HttpResponse::build(status).json(
+        Response::<serde_json::Value> {
+            response_code: code.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+fn internal_error(desc: String) -> HttpResponse {
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, FAILED_INTERNAL, desc)
+}
+
+fn login_failed(desc: &str) -> HttpResponse {
+    error_response(StatusCode::UNAUTHORIZED, FAILED_AUTHORIZED, desc.to_string())
+}
+
+fn not_configured() -> HttpResponse {
+    error_response(StatusCode::NOT_FOUND, FAILED_NOT_FOUND, "OIDC login is not configured.".to_string())
+}
+
+// Redirect the browser to the provider with a fresh state, nonce and PKCE verifier.
+pub async fn authorize(pool: web::Data<PgPool>) -> impl Responder {
+    match OIDC_CLIENT.as_ref() {
+        Some(client) => start_login(pool.get_ref(), client).await,
+        None => not_configured(),
+    }
+}
+
+async fn start_login(pool: &PgPool, client: &OidcClient) -> HttpResponse {
+    let state_exp = CONFIG.oidc_state_exp.clone();
+    let state_duration = match parse_jwt_exp(&state_exp).and_then(|duration| chrono::Duration::from_std(duration).ok()) {
+        Some(duration) => duration,
+        None => return internal_error(format!("Invalid OIDC_STATE_EXP format: {}", state_exp)),
+    };
+
+    let state = generate_token(32);
+    let nonce = generate_token(32);
+    let code_verifier = generate_token(48);
+    let now = Utc::now().naive_utc();
+    if let Err(err) = oidc::create_state(pool, &hash_token(&state), &nonce, &code_verifier, now + state_duration).await {
+        return internal_error(err.to_string());
+    }
+    if let Err(err) = oidc::delete_expired_states(pool, now).await {
+        warn!("Could not delete expired OIDC states: {}", err);
+    }
+
+    match client.authorization_url(&state, &nonce, &code_verifier).await {
+        Ok(url) => HttpResponse::Found().insert_header((header::LOCATION, url)).finish(),
+        Err(err) => {
+            error!("OIDC authorization failed: {}", err);
+            error_response(StatusCode::BAD_GATEWAY, FAILED_INTERNAL, "Identity provider is unavailable.".to_string())
+        }
+    }
+}
+
+// Pick a free username from the ID token, falling back to a random suffix.
+async fn available_username(pool: &PgPool, claims: &IdTokenClaims) -> Result<String, Error> {
+    let source = claims.preferred_username.as_deref()
+        .or_else(|| claims.email.as_deref().and_then(|email| email.split('@').next()))
+        .unwrap_or("user");
+    let mut base: String = source.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        .take(MAX_USERNAME_LEN)
+        .collect();
+    if base.is_empty() {
+        base = "user".to_string();
+    }
+
+    let mut candidate = base.clone();
+    loop {
+        match users::get_user_username(pool, &candidate).await {
+            Err(Error::RowNotFound) => return Ok(candidate),
+            Err(err) => return Err(err),
+            Ok(_) => {
+                let suffix = &hash_token(&generate_token(8))[..6];
+                let keep = base.len().min(MAX_USERNAME_LEN - suffix.len() - 1);
+                candidate = format!("{}-{}", &base[..keep], suffix);
+            }
+        }
+    }
+}
+
+// Find the local user for the ID token: an existing link, then a verified email
+// match, then a new account.
+async fn resolve_user(pool: &PgPool, issuer: &str, claims: &IdTokenClaims) -> Result<User, HttpResponse> {
+    let email = claims.email.as_deref().map(str::trim).filter(|email| !email.is_empty());
+
+    match oidc::get_identity_user(pool, issuer, &claims.sub).await {
+        Ok(user_id) => {
+            if let Err(err) = oidc::touch_identity(pool, issuer, &claims.sub, email).await {
+                return Err(internal_error(err.to_string()));
+            }
+            return match users::get_user(pool, user_id).await {
+                Ok(user) => Ok(user),
+                // The linked account is in the trash.
+                Err(Error::RowNotFound) => Err(login_failed("The linked account has been deleted.")),
+                Err(err) => Err(internal_error(err.to_string())),
+            };
+        }
+        Err(Error::RowNotFound) => {}
+        Err(err) => return Err(internal_error(err.to_string())),
+    }
+
+    let email = match email {
+        Some(email) if email.len() <= MAX_EMAIL_LEN => email,
+        Some(_) => return Err(login_failed("The identity provider returned an email address that is too long.")),
+        None => return Err(login_failed("The identity provider did not return an email address.")),
+    };
+
+    let existing = match users::get_user_by_email(pool, email).await {
+        Ok(user) => Some(user),
+        Err(Error::RowNotFound) => None,
+        Err(err) => return Err(internal_error(err.to_string())),
+    };
+    let user = match existing {
+        // Both sides must have verified the address, or either one could claim the other's account.
+        Some(user) if CONFIG.oidc_link_by_email && claims.email_verified() && user.email_verified_at.is_some() => user,
+        Some(_) => {
+            warn!("OIDC login for {} matches a local account that cannot be linked automatically", claims.sub);
+            return Err(error_response(StatusCode::CONFLICT, FAILED_EXIST, "Email already exist".to_string()));
+        }
+        None if CONFIG.oidc_auto_create_users => {
+            let username = available_username(pool, claims).await.map_err(|err| internal_error(err.to_string()))?;
+            // Never matches a password, the account signs in through the provider or a password reset.
+            let new_user = CreateUserRequest {
+                username,
+                email: email.to_string(),
+                password: format!("!oidc:{}", generate_token(32)),
+                name: claims.name.as_deref()
+                    .map(|name| name.trim().chars().take(MAX_NAME_LEN).collect::<String>())
+                    .filter(|name| !name.is_empty()),
+                roles: None,
+            };
+            let verified_at = claims.email_verified().then(|| Utc::now().naive_utc());
+            let created = users::create_user(pool, new_user, verified_at, None).await.map_err(|err| internal_error(err.to_string()))?;
+            users::get_user(pool, created.id).await.map_err(|err| internal_error(err.to_string()))?
+        }
+        None => return Err(login_failed("No local account is linked to this identity.")),
+    };
+
+    if let Err(err) = oidc::link_identity(pool, user.id, issuer, &claims.sub, Some(email)).await {
+        return Err(internal_error(err.to_string()));
+    }
+    Ok(user)
+}
+
+pub async fn callback(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+    query: web::Query<OidcCallbackQuery>,
+) -> impl Responder {
+    match OIDC_CLIENT.as_ref() {
+        Some(client) => complete_login(pool.get_ref(), client, &http_req, &query).await,
+        None => not_configured(),
+    }
+}
+
+async fn complete_login(pool: &PgPool, client: &OidcClient, http_req: &HttpRequest, query: &OidcCallbackQuery) -> HttpResponse {
+    if let Some(err) = &query.error {
+        let desc = query.error_description.as_deref().unwrap_or("");
+        let desc = format!("Identity provider returned an error: {} {}", err, desc);
+        return login_failed(desc.trim_end());
+    }
+    let (code, state) = match (query.code.as_deref(), query.state.as_deref()) {
+        (Some(code), Some(state)) if !code.is_empty() && !state.is_empty() => (code, state),
+        _ => return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, "code and state required".to_string()),
+    };
+
+    let pending = match oidc::consume_state(pool, &hash_token(state)).await {
+        Ok(pending) if pending.expires_at > Utc::now().naive_utc() => pending,
+        Ok(_) | Err(Error::RowNotFound) => return login_failed("Invalid or expired OIDC state."),
+        Err(err) => return internal_error(err.to_string()),
+    };
+
+    let id_token = match client.exchange_code(code, &pending.code_verifier).await {
+        Ok(id_token) => id_token,
+        Err(err) => {
+            warn!("OIDC code exchange failed: {}", err);
+            return login_failed("Could not complete the login with the identity provider.");
+        }
+    };
+    let claims = match client.verify_id_token(&id_token, &pending.nonce).await {
+        Ok(claims) => claims,
+        Err(err) => {
+            warn!("OIDC ID token rejected: {}", err);
+            record(pool, http_req, AuditEntry::new("auth.oidc.login", OUTCOME_FAILURE)).await;
+            return login_failed("Could not complete the login with the identity provider.");
+        }
+    };
+
+    let user = match resolve_user(pool, &claims.iss, &claims).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    let audit = |outcome| AuditEntry::new("auth.oidc.login", outcome)
+        .by_user(Some(user.id), &user.username)
+        .on("user", user.id)
+        .changes(json!({ "issuer": claims.iss, "subject": claims.sub }));
+
+    // A federated login does not skip the second factor the user enrolled here.
+    match start_challenge(pool, user.id).await {
+        Ok(Some(challenge)) => {
+            record(pool, http_req, audit("mfa_required")).await;
+            return HttpResponse::Ok().json(
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "MFA required".to_string(),
+                    response_data: Some(json!(challenge)),
+                }
+            );
+        }
+        Ok(None) => {}
+        Err(err) => return internal_error(err),
+    }
+
+    match issue_token(pool, http_req, &user, None).await {
+        Ok(data) => {
+            record(pool, http_req, audit(OUTCOME_SUCCESS)).await;
+            HttpResponse::Ok().json(
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
+                    response_data: Some(json!(data)),
+                }
+            )
+        }
+        Err(err) => internal_error(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::testing;
+    use crate::internal::application::repositories::auth::mfa;
+    use crate::internal::pkg::database::sql::migrate::migrate_up;
+    use crate::internal::pkg::database::sql::scratch::ScratchDatabase;
+    use crate::internal::pkg::oidc::mock::MockIssuer;
+    use actix_web::{body::to_bytes, test::TestRequest};
+    use serde_json::Value;
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn an_oidc_login_asks_for_the_enrolled_second_factor() {
+        testing::init();
+        let database = ScratchDatabase::create("oidc_mfa").await;
+        migrate_up(&database.pool).await.unwrap();
+        let mock = MockIssuer::start().await;
+        let client = mock.client();
+
+        let user = CreateUserRequest {
+            username: "mock.user".to_string(),
+            email: "mock.user@example.com".to_string(),
+            password: "hash".to_string(),
+            name: None,
+            roles: None,
+        };
+        let user_id = users::create_user(&database.pool, user, Some(Utc::now().naive_utc()), None).await.unwrap().id;
+        mfa::save_pending_mfa(&database.pool, user_id, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").await.unwrap();
+        assert!(mfa::activate_mfa(&database.pool, user_id, 100, &[]).await.unwrap());
+
+        let redirect = start_login(&database.pool, &client).await;
+        assert_eq!(redirect.status(), StatusCode::FOUND);
+        let location = redirect.headers().get(header::LOCATION).unwrap().to_str().unwrap().to_string();
+        let (code, state) = mock.authorize(&location).await.unwrap();
+
+        let query = OidcCallbackQuery { code: Some(code), state: Some(state), error: None, error_description: None };
+        let http_req = TestRequest::default().to_http_request();
+        let resp = complete_login(&database.pool, &client, &http_req, &query).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Value = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(body["responseDesc"], "MFA required");
+        assert_eq!(body["responseData"]["mfaRequired"], true);
+        assert!(body["responseData"]["challengeToken"].is_string());
+        assert!(body["responseData"].get("token").is_none());
+
+        mock.stop().await;
+        database.drop().await;
+    }
+}
@@ -0,0 +1,156 @@
+use crate::internal::domain::entities::auth::password::{ForgotPasswordRequest, ResetPasswordRequest};
+use crate::internal::domain::entities::response::Response;
+use crate::internal::constant::status::{SUCCESS, FAILED_AUTHORIZED, FAILED_INTERNAL, FAILED_REQUIRED};
+use crate::internal::application::repositories::auth::{password_resets, sessions};
+use crate::internal::application::repositories::users::users;
+use crate::internal::application::usecases::audit::audit::{record, AuditEntry, OUTCOME_SUCCESS};
+use crate::internal::application::usecases::auth::revocation::forget_user;
+use crate::internal::pkg::mailer::mailer::{Email, Mailer};
+use crate::internal::pkg::password::password::hash_password;
+use crate::internal::pkg::password::policy::PASSWORD_POLICY;
+use crate::internal::pkg::utils::crypto::{generate_token, hash_token};
+use crate::internal::pkg::utils::request::client_ip;
+use crate::config::settings::CONFIG;
+use crate::middlewares::jwt::parse_jwt_exp;
+use actix_web::{rt, HttpRequest, HttpResponse, Responder, web};
+use chrono::Utc;
+use log::error;
+use sqlx::{Error, postgres::PgPool};
+
+fn bad_request(code: &str, desc: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(
+        Response::<serde_json::Value> {
+            response_code: code.to_string(),
+            response_desc: desc.to_string(),
+            response_data: None,
+        }
+    )
+}
+
+fn internal_error(desc: String) -> HttpResponse {
+    HttpResponse::InternalServerError().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_INTERNAL.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+// Always answers the same way so the endpoint cannot be used to probe for accounts.
+pub async fn forgot_password(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    http_req: HttpRequest,
+    req: web::Json<ForgotPasswordRequest>,
+) -> impl Responder {
+    if req.email.trim().is_empty() {
+        return bad_request(FAILED_REQUIRED, "Email required");
+    }
+
+    let accepted = HttpResponse::Ok().json(
+        Response::<serde_json::Value> {
+            response_code: SUCCESS.to_string(),
+            response_desc: "If the account exists, a password reset email has been sent.".to_string(),
+            response_data: None,
+        }
+    );
+
+    let reset_exp = CONFIG.password_reset_exp.clone();
+    let reset_duration = match parse_jwt_exp(&reset_exp).and_then(|duration| chrono::Duration::from_std(duration).ok()) {
+        Some(duration) => duration,
+        None => return internal_error(format!("Invalid PASSWORD_RESET_EXP format: {}", reset_exp)),
+    };
+
+    let user = match users::get_user_email(pool.get_ref(), req.email.trim()).await {
+        Ok(user) => user,
+        Err(Error::RowNotFound) => return accepted,
+        Err(err) => return internal_error(err.to_string()),
+    };
+
+    // The reset is stored and mailed after responding, so the response time does not
+    // tell an existing account from a missing one.
+    let ip = client_ip(&http_req);
+    rt::spawn(async move {
+        let token = generate_token(32);
+        let expires_at = Utc::now().naive_utc() + reset_duration;
+        if let Err(err) = password_resets::create_password_reset(pool.get_ref(), user.id, &hash_token(&token), ip, expires_at).await {
+            error!("failed to create password reset for user {}: {}", user.id, err);
+            return;
+        }
+
+        let email = Email {
+            to: user.email,
+            subject: "Reset your password".to_string(),
+            body: format!(
+                "Hi {},\n\nUse the link below to choose a new password. It expires in {}.\n\n{}{}\n\nIf you did not ask for this, you can ignore this email.\n",
+                user.username, reset_exp, CONFIG.password_reset_url, token
+            ),
+        };
+        if let Err(err) = mailer.send(email).await {
+            error!("failed to send password reset email to user {}: {}", user.id, err);
+        }
+    });
+
+    accepted
+}
+
+pub async fn reset_password(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+    req: web::Json<ResetPasswordRequest>,
+) -> impl Responder {
+    if req.token.trim().is_empty() {
+        return bad_request(FAILED_REQUIRED, "Token required");
+    }
+    if req.password.trim().is_empty() {
+        return bad_request(FAILED_REQUIRED, "Password required");
+    }
+
+    let now = Utc::now().naive_utc();
+    let token_hash = hash_token(req.token.trim());
+    // Check the new password before the token is used up, so a rejected one can be retried.
+    let user = match password_resets::get_password_reset_user(pool.get_ref(), &token_hash, now).await {
+        Ok(user_id) => match users::get_user(pool.get_ref(), user_id).await {
+            Ok(user) => user,
+            // The user is in the trash.
+            Err(Error::RowNotFound) => return bad_request(FAILED_AUTHORIZED, "Invalid or expired reset token."),
+            Err(err) => return internal_error(err.to_string()),
+        },
+        Err(Error::RowNotFound) => return bad_request(FAILED_AUTHORIZED, "Invalid or expired reset token."),
+        Err(err) => return internal_error(err.to_string()),
+    };
+    if let Some(reason) = PASSWORD_POLICY.check(&req.password, &user.username) {
+        return bad_request(FAILED_REQUIRED, &reason);
+    }
+
+    let user_id = match password_resets::consume_password_reset(pool.get_ref(), &token_hash, now).await {
+        Ok(user_id) if user_id == user.id => user_id,
+        Ok(_) | Err(Error::RowNotFound) => return bad_request(FAILED_AUTHORIZED, "Invalid or expired reset token."),
+        Err(err) => return internal_error(err.to_string()),
+    };
+
+    let hashed = match hash_password(req.password.clone()).await {
+        Ok(hashed) => hashed,
+        Err(err) => return internal_error(err),
+    };
+
+    if let Err(err) = users::update_password(pool.get_ref(), user_id, &hashed, now).await {
+        return internal_error(err.to_string());
+    }
+    // Whoever held the old password loses every session.
+    if let Err(err) = sessions::revoke_user_sessions(pool.get_ref(), user_id).await {
+        return internal_error(err.to_string());
+    }
+    forget_user(&user.username);
+    let entry = AuditEntry::new("auth.password.reset", OUTCOME_SUCCESS).by_user(Some(user.id), &user.username).on("user", user.id);
+    record(pool.get_ref(), &http_req, entry).await;
+
+    HttpResponse::Ok().json(
+        Response::<serde_json::Value> {
+            response_code: SUCCESS.to_string(),
+            response_desc: "OK".to_string(),
+            response_data: None,
+        }
+    )
+}
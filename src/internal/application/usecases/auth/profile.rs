@@ -0,0 +1,233 @@
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::internal::domain::entities::auth::profile::{ChangePasswordRequest, ProfileResponse, UpdateProfileRequest};
+use crate::internal::domain::entities::users::users::User;
+use crate::internal::domain::entities::response::Response;
+use crate::internal::constant::status::{SUCCESS, FAILED_AUTHORIZED, FAILED_EXIST, FAILED_INTERNAL, FAILED_REQUIRED};
+use crate::internal::application::repositories::auth::{roles, sessions};
+use crate::internal::application::repositories::users::users::{self, MAX_EMAIL_LEN, MAX_NAME_LEN};
+use crate::internal::application::usecases::audit::audit::{diff, record, AuditEntry, OUTCOME_FAILURE, OUTCOME_SUCCESS};
+use crate::internal::application::usecases::auth::lockout::{account_key, record_failed_login, record_successful_login, retry_after};
+use crate::internal::application::usecases::auth::registration::send_verification;
+use crate::internal::application::usecases::auth::revocation::forget_user;
+use crate::internal::application::usecases::auth::token::issue_token;
+use crate::internal::pkg::mailer::mailer::Mailer;
+use crate::internal::pkg::password::password::{hash_password, verify_password, Verification};
+use crate::internal::pkg::password::policy::PASSWORD_POLICY;
+use crate::internal::pkg::utils::request::client_ip;
+use actix_web::{http::StatusCode, HttpRequest, HttpResponse, Responder, web};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::{Error, postgres::PgPool};
+
+fn error_response(status: StatusCode, code: &str, desc: &str) -> HttpResponse {
+    HttpResponse::build(status).json(
+        Response::<serde_json::Value> {
+            response_code: code.to_string(),
+            response_desc: desc.to_string(),
+            response_data: None,
+        }
+    )
+}
+
+fn internal_error(desc: String) -> HttpResponse {
+    HttpResponse::InternalServerError().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_INTERNAL.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+async fn current_user(pool: &PgPool, identity: &Identity) -> Result<User, HttpResponse> {
+    let user = match users::get_user_username(pool, &identity.sub).await {
+        Ok(user) => users::get_user(pool, user.id).await,
+        Err(err) => Err(err),
+    };
+    match user {
+        Ok(user) => Ok(user),
+        Err(Error::RowNotFound) => Err(error_response(StatusCode::UNAUTHORIZED, FAILED_AUTHORIZED, "Unauthorized")),
+        Err(err) => Err(internal_error(err.to_string())),
+    }
+}
+
+// Changing the account needs the owner, not a key acting for them.
+fn reject_api_key(identity: &Identity) -> Option<HttpResponse> {
+    identity.api_key_id.is_some()
+        .then(|| error_response(StatusCode::FORBIDDEN, FAILED_AUTHORIZED, "The account cannot be changed with an API key."))
+}
+
+async fn profile_response(pool: &PgPool, user: User) -> HttpResponse {
+    let user_roles = match roles::get_user_roles(pool, user.id).await {
+        Ok(user_roles) => user_roles,
+        Err(err) => return internal_error(err.to_string()),
+    };
+    let permissions = match roles::get_user_permissions(pool, user.id).await {
+        Ok(permissions) => permissions,
+        Err(err) => return internal_error(err.to_string()),
+    };
+    let profile = ProfileResponse {
+        id: user.id,
+        username: user.username,
+        email: user.email,
+        name: user.name,
+        email_verified_at: user.email_verified_at,
+        pending_email: user.pending_email,
+        roles: user_roles,
+        permissions,
+    };
+    HttpResponse::Ok().json(
+        Response {
+            response_code: SUCCESS.to_string(),
+            response_desc: "OK".to_string(),
+            response_data: Some(json!(profile)),
+        }
+    )
+}
+
+pub async fn get_profile(
+    pool: web::Data<PgPool>,
+    identity: Identity,
+) -> impl Responder {
+    match current_user(pool.get_ref(), &identity).await {
+        Ok(user) => profile_response(pool.get_ref(), user).await,
+        Err(resp) => resp,
+    }
+}
+
+pub async fn update_profile(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    http_req: HttpRequest,
+    identity: Identity,
+    req: web::Json<UpdateProfileRequest>,
+) -> impl Responder {
+    if let Some(resp) = reject_api_key(&identity) {
+        return resp;
+    }
+    let user = match current_user(pool.get_ref(), &identity).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    let name = match req.name.as_deref().map(str::trim) {
+        Some(name) if name.chars().count() > MAX_NAME_LEN => {
+            return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, &format!("Name must be at most {} characters long", MAX_NAME_LEN));
+        }
+        Some("") => None,
+        Some(name) => Some(name.to_string()),
+        None => user.name.clone(),
+    };
+    let email = match req.email.as_deref().map(str::trim) {
+        Some("") => return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, "Email required"),
+        Some(email) if email.len() > MAX_EMAIL_LEN => {
+            return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, &format!("Email must be at most {} characters long", MAX_EMAIL_LEN));
+        }
+        Some(email) => email.to_string(),
+        None => user.email.clone(),
+    };
+    // The current address keeps signing in until the new one is verified. Giving
+    // the current address back drops a pending change.
+    let pending_email = (email != user.email).then_some(email);
+    if let Some(email) = pending_email.as_deref()
+        && let Ok(other) = users::get_user_email(pool.get_ref(), email).await
+        && other.id != user.id {
+        return error_response(StatusCode::BAD_REQUEST, FAILED_EXIST, "Email already exist");
+    }
+
+    let updated = match users::update_profile(pool.get_ref(), user.id, name.as_deref(), pending_email.as_deref()).await {
+        Ok(updated) => updated,
+        Err(err) => return internal_error(err.to_string()),
+    };
+    let changes = diff(
+        Some(&json!({ "name": user.name, "pending_email": user.pending_email })),
+        Some(&json!({ "name": updated.name, "pending_email": updated.pending_email })),
+    );
+    let entry = AuditEntry::new("user.profile.update", OUTCOME_SUCCESS).by_identity(&identity).on("user", user.id).changes(changes);
+    record(pool.get_ref(), &http_req, entry).await;
+    if let Some(email) = updated.pending_email.as_deref()
+        && let Err(err) = send_verification(pool.get_ref(), mailer.get_ref(), updated.id, &updated.username, email).await {
+        return internal_error(err);
+    }
+
+    profile_response(pool.get_ref(), updated).await
+}
+
+// Ends every other session. The caller gets a fresh token pair to carry on with,
+// the one used for this request stops working with the old password.
+pub async fn change_password(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+    identity: Identity,
+    req: web::Json<ChangePasswordRequest>,
+) -> impl Responder {
+    if let Some(resp) = reject_api_key(&identity) {
+        return resp;
+    }
+    if req.current_password.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, "Current password required");
+    }
+    if req.new_password.trim().is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, "New password required");
+    }
+    let user = match current_user(pool.get_ref(), &identity).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    // A stolen access token must not become a way around the login throttle.
+    let account = account_key(&user.username);
+    let ip = client_ip(&http_req);
+    match retry_after(pool.get_ref(), &account, ip.as_deref()).await {
+        Ok(Some(seconds)) => return HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", seconds.to_string()))
+            .json(
+                Response::<serde_json::Value> {
+                    response_code: FAILED_AUTHORIZED.to_string(),
+                    response_desc: "Too many failed login attempts, try again later.".to_string(),
+                    response_data: None,
+                }
+            ),
+        Ok(None) => {}
+        Err(err) => return internal_error(err.to_string()),
+    }
+    let audit = |outcome| AuditEntry::new("auth.password.change", outcome).by_identity(&identity).on("user", user.id);
+    if verify_password(req.current_password.clone(), Some(user.password.clone())).await == Verification::Failed {
+        record(pool.get_ref(), &http_req, audit(OUTCOME_FAILURE)).await;
+        if let Err(err) = record_failed_login(pool.get_ref(), &account, ip.as_deref()).await {
+            return internal_error(err.to_string());
+        }
+        return error_response(StatusCode::BAD_REQUEST, FAILED_AUTHORIZED, "Current password is incorrect.");
+    }
+    if let Err(err) = record_successful_login(pool.get_ref(), &account).await {
+        return internal_error(err.to_string());
+    }
+
+    if let Some(reason) = PASSWORD_POLICY.check(&req.new_password, &user.username) {
+        return error_response(StatusCode::BAD_REQUEST, FAILED_REQUIRED, &reason);
+    }
+    let hashed = match hash_password(req.new_password.clone()).await {
+        Ok(hashed) => hashed,
+        Err(err) => return internal_error(err),
+    };
+
+    if let Err(err) = users::update_password(pool.get_ref(), user.id, &hashed, Utc::now().naive_utc()).await {
+        return internal_error(err.to_string());
+    }
+    if let Err(err) = sessions::revoke_user_sessions(pool.get_ref(), user.id).await {
+        return internal_error(err.to_string());
+    }
+    forget_user(&user.username);
+    record(pool.get_ref(), &http_req, audit(OUTCOME_SUCCESS)).await;
+
+    match issue_token(pool.get_ref(), &http_req, &user, None).await {
+        Ok(data) => HttpResponse::Ok().json(
+            Response {
+                response_code: SUCCESS.to_string(),
+                response_desc: "OK".to_string(),
+                response_data: Some(json!(data)),
+            }
+        ),
+        Err(err) => internal_error(err),
+    }
+}
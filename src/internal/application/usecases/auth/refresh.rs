@@ -0,0 +1,102 @@
+use crate::internal::domain::entities::auth::session::RefreshRequest;
+use crate::internal::domain::entities::response::Response;
+use crate::internal::constant::status::{SUCCESS, FAILED_AUTHORIZED, FAILED_INTERNAL, FAILED_REQUIRED};
+use crate::internal::application::repositories::auth::sessions;
+use crate::internal::application::repositories::users::users::get_user;
+use crate::internal::application::usecases::auth::token::issue_token;
+use crate::internal::pkg::utils::crypto::hash_token;
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use chrono::Utc;
+use log::warn;
+use sqlx::{Error, postgres::PgPool};
+use serde_json::json;
+
+fn unauthorized(desc: &str) -> HttpResponse {
+    HttpResponse::Unauthorized().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_AUTHORIZED.to_string(),
+            response_desc: desc.to_string(),
+            response_data: None,
+        }
+    )
+}
+
+fn internal_error(desc: String) -> HttpResponse {
+    HttpResponse::InternalServerError().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_INTERNAL.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+pub async fn refresh(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+    req: web::Json<RefreshRequest>,
+) -> impl Responder {
+    if req.refresh_token.trim().is_empty() {
+        return HttpResponse::BadRequest()
+        .json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_REQUIRED.to_string(),
+                response_desc: "refreshToken required".to_string(),
+                response_data: None,
+            }
+        );
+    }
+
+    let session = match sessions::get_session_by_hash(pool.get_ref(), &hash_token(req.refresh_token.trim())).await {
+        Ok(session) => session,
+        Err(Error::RowNotFound) => return unauthorized("Invalid refresh token."),
+        Err(err) => return internal_error(err.to_string()),
+    };
+
+    if session.revoked_at.is_some() {
+        return unauthorized("Refresh token has been revoked.");
+    }
+
+    // A rotated token coming back means it was copied, kill the whole chain.
+    if session.rotated_at.is_some() {
+        warn!("refresh token reuse detected for user {} (family {})", session.user_id, session.family_id);
+        if let Err(err) = sessions::revoke_family(pool.get_ref(), &session.family_id).await {
+            return internal_error(err.to_string());
+        }
+        return unauthorized("Refresh token reuse detected.");
+    }
+
+    if session.expires_at <= Utc::now().naive_utc() {
+        return unauthorized("Refresh token has expired.");
+    }
+
+    match sessions::rotate_session(pool.get_ref(), session.id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            warn!("concurrent refresh token reuse for user {} (family {})", session.user_id, session.family_id);
+            if let Err(err) = sessions::revoke_family(pool.get_ref(), &session.family_id).await {
+                return internal_error(err.to_string());
+            }
+            return unauthorized("Refresh token reuse detected.");
+        }
+        Err(err) => return internal_error(err.to_string()),
+    }
+
+    let user = match get_user(pool.get_ref(), session.user_id).await {
+        Ok(user) => user,
+        Err(Error::RowNotFound) => return unauthorized("Unauthorized"),
+        Err(err) => return internal_error(err.to_string()),
+    };
+
+    match issue_token(pool.get_ref(), &http_req, &user, Some(session.family_id)).await {
+        Ok(data) => HttpResponse::Ok()
+        .json(
+            Response {
+                response_code: SUCCESS.to_string(),
+                response_desc: "OK".to_string(),
+                response_data: Some(json!(data)),
+            }
+        ),
+        Err(err) => internal_error(err),
+    }
+}
@@ -0,0 +1,224 @@
+use crate::internal::domain::entities::auth::registration::{RegisterRequest, ResendVerificationRequest, VerifyEmailRequest};
+use crate::internal::domain::entities::users::users::CreateUserRequest;
+use crate::internal::domain::entities::response::Response;
+use crate::internal::constant::status::{SUCCESS, FAILED_AUTHORIZED, FAILED_EXIST, FAILED_INTERNAL, FAILED_NOT_FOUND, FAILED_REQUIRED};
+use crate::internal::application::repositories::auth::email_verifications;
+use crate::internal::application::repositories::users::users::{self, MAX_EMAIL_LEN, MAX_NAME_LEN, MAX_USERNAME_LEN};
+use crate::internal::pkg::mailer::mailer::{Email, Mailer};
+use crate::internal::pkg::password::password::hash_password;
+use crate::internal::pkg::password::policy::PASSWORD_POLICY;
+use crate::internal::pkg::utils::crypto::{generate_token, hash_token};
+use crate::config::settings::CONFIG;
+use crate::middlewares::jwt::parse_jwt_exp;
+use actix_web::{rt, HttpResponse, Responder, web};
+use chrono::Utc;
+use log::error;
+use serde_json::json;
+use sqlx::{Error, postgres::PgPool};
+
+fn bad_request(code: &str, desc: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(
+        Response::<serde_json::Value> {
+            response_code: code.to_string(),
+            response_desc: desc.to_string(),
+            response_data: None,
+        }
+    )
+}
+
+fn internal_error(desc: String) -> HttpResponse {
+    HttpResponse::InternalServerError().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_INTERNAL.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+// Create a fresh verification token and mail it. Delivery failures are only logged,
+// the user can ask for another email.
+pub async fn send_verification(
+    pool: &PgPool,
+    mailer: &dyn Mailer,
+    user_id: i32,
+    username: &str,
+    email: &str,
+) -> Result<(), String> {
+    let verification_exp = CONFIG.email_verification_exp.clone();
+    let verification_duration = parse_jwt_exp(&verification_exp)
+        .and_then(|duration| chrono::Duration::from_std(duration).ok())
+        .ok_or_else(|| format!("Invalid EMAIL_VERIFICATION_EXP format: {}", verification_exp))?;
+
+    let token = generate_token(32);
+    let expires_at = Utc::now().naive_utc() + verification_duration;
+    email_verifications::create_email_verification(pool, user_id, email, &hash_token(&token), expires_at)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let email = Email {
+        to: email.to_string(),
+        subject: "Verify your email address".to_string(),
+        body: format!(
+            "Hi {},\n\nUse the link below to verify your email address. It expires in {}.\n\n{}{}\n\nIf you did not create an account, you can ignore this email.\n",
+            username, verification_exp, CONFIG.email_verification_url, token
+        ),
+    };
+    if let Err(err) = mailer.send(email).await {
+        error!("failed to send verification email to user {}: {}", user_id, err);
+    }
+    Ok(())
+}
+
+pub async fn register(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    req: web::Json<RegisterRequest>,
+) -> impl Responder {
+    if !CONFIG.registration_enabled {
+        return HttpResponse::NotFound().json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_NOT_FOUND.to_string(),
+                response_desc: "Registration is disabled.".to_string(),
+                response_data: None,
+            }
+        );
+    }
+
+    if req.username.trim().is_empty() {
+        return bad_request(FAILED_REQUIRED, "Username required");
+    }
+    if req.email.trim().is_empty() {
+        return bad_request(FAILED_REQUIRED, "Email required");
+    }
+    if req.password.trim().is_empty() {
+        return bad_request(FAILED_REQUIRED, "Password required");
+    }
+    if req.username.chars().count() > MAX_USERNAME_LEN {
+        return bad_request(FAILED_REQUIRED, &format!("Username must be at most {} characters long", MAX_USERNAME_LEN));
+    }
+    if req.email.chars().count() > MAX_EMAIL_LEN {
+        return bad_request(FAILED_REQUIRED, &format!("Email must be at most {} characters long", MAX_EMAIL_LEN));
+    }
+    let name = req.name.as_deref().map(str::trim).filter(|name| !name.is_empty());
+    if name.is_some_and(|name| name.chars().count() > MAX_NAME_LEN) {
+        return bad_request(FAILED_REQUIRED, &format!("Name must be at most {} characters long", MAX_NAME_LEN));
+    }
+    if let Some(reason) = PASSWORD_POLICY.check(&req.password, &req.username) {
+        return bad_request(FAILED_REQUIRED, &reason);
+    }
+
+    if users::get_user_username(pool.get_ref(), req.username.as_str()).await.is_ok() {
+        return bad_request(FAILED_EXIST, "Username already exist");
+    }
+    if users::get_user_email(pool.get_ref(), req.email.as_str()).await.is_ok() {
+        return bad_request(FAILED_EXIST, "Email already exist");
+    }
+
+    let hashed = match hash_password(req.password.clone()).await {
+        Ok(hashed) => hashed,
+        Err(err) => return internal_error(err),
+    };
+
+    let new_user = CreateUserRequest {
+        username: req.username.clone(),
+        email: req.email.clone(),
+        password: hashed,
+        name: name.map(str::to_string),
+        roles: None,
+    };
+    // Self registered accounts stay unverified until /auth/verify is called.
+    let user = match users::create_user(pool.get_ref(), new_user, None, None).await {
+        Ok(user) => user,
+        // Another account took the username or email after the checks above.
+        Err(Error::Database(err)) if err.code().as_deref() == Some("23505") => return bad_request(
+            FAILED_EXIST,
+            match err.constraint() {
+                Some("users_email_key") => "Email already exist",
+                _ => "Username already exist",
+            },
+        ),
+        Err(err) => return internal_error(err.to_string()),
+    };
+    if let Err(err) = send_verification(pool.get_ref(), mailer.get_ref(), user.id, &user.username, &user.email).await {
+        return internal_error(err);
+    }
+
+    HttpResponse::Ok().json(
+        Response {
+            response_code: SUCCESS.to_string(),
+            response_desc: "Registered, check your email to verify your account.".to_string(),
+            response_data: Some(json!(user)),
+        }
+    )
+}
+
+pub async fn verify_email(
+    pool: web::Data<PgPool>,
+    req: web::Json<VerifyEmailRequest>,
+) -> impl Responder {
+    if req.token.trim().is_empty() {
+        return bad_request(FAILED_REQUIRED, "Token required");
+    }
+
+    let now = Utc::now().naive_utc();
+    let (user_id, email) = match email_verifications::consume_email_verification(pool.get_ref(), &hash_token(req.token.trim()), now).await {
+        Ok(verification) => verification,
+        Err(Error::RowNotFound) => return bad_request(FAILED_AUTHORIZED, "Invalid or expired verification token."),
+        Err(err) => return internal_error(err.to_string()),
+    };
+
+    // A pending address can have been taken by another account since it was requested.
+    match users::mark_email_verified(pool.get_ref(), user_id, &email, now).await {
+        Ok(true) => {}
+        Ok(false) => return bad_request(FAILED_AUTHORIZED, "Invalid or expired verification token."),
+        Err(Error::Database(err)) if err.code().as_deref() == Some("23505") => return bad_request(FAILED_EXIST, "Email already exist"),
+        Err(err) => return internal_error(err.to_string()),
+    }
+
+    HttpResponse::Ok().json(
+        Response::<serde_json::Value> {
+            response_code: SUCCESS.to_string(),
+            response_desc: "OK".to_string(),
+            response_data: None,
+        }
+    )
+}
+
+// Always answers the same way so the endpoint cannot be used to probe for accounts.
+// The account is looked up and mailed after responding, so neither the response
+// time nor a failure tells an existing account from a missing one.
+pub async fn resend_verification(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    req: web::Json<ResendVerificationRequest>,
+) -> impl Responder {
+    if req.email.trim().is_empty() {
+        return bad_request(FAILED_REQUIRED, "Email required");
+    }
+
+    let accepted = HttpResponse::Ok().json(
+        Response::<serde_json::Value> {
+            response_code: SUCCESS.to_string(),
+            response_desc: "If the account exists and is not verified yet, a verification email has been sent.".to_string(),
+            response_data: None,
+        }
+    );
+
+    let email = req.email.trim().to_string();
+    rt::spawn(async move {
+        let user = match users::get_user_by_email(pool.get_ref(), &email).await {
+            Ok(user) if user.email_verified_at.is_none() => user,
+            Ok(_) | Err(Error::RowNotFound) => return,
+            Err(err) => {
+                error!("failed to look up the account to resend a verification to: {}", err);
+                return;
+            }
+        };
+        if let Err(err) = send_verification(pool.get_ref(), mailer.get_ref(), user.id, &user.username, &user.email).await {
+            error!("failed to resend the verification of user {}: {}", user.id, err);
+        }
+    });
+
+    accepted
+}
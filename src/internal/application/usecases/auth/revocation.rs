@@ -0,0 +1,121 @@
+use crate::internal::domain::entities::auth::login::Claims;
+use crate::internal::application::repositories::auth::revocations;
+use crate::internal::application::repositories::users::users;
+use crate::internal::pkg::utils::cache::TtlCache;
+use crate::config::settings::CONFIG;
+use crate::middlewares::jwt::parse_jwt_exp;
+use chrono::NaiveDateTime;
+use once_cell::sync::Lazy;
+use sqlx::{Error, postgres::PgPool};
+use std::time::Duration;
+
+const CACHE_CAPACITY: usize = 10_000;
+
+// Lookups are cached so the middleware does not hit Postgres on every request.
+// Revocations done by this instance are applied to the cache immediately, other
+// instances pick them up once the entry expires.
+static REVOKED_TOKENS: Lazy<TtlCache<String, bool>> = Lazy::new(|| TtlCache::new(cache_ttl(), CACHE_CAPACITY));
+static TOKEN_CUTOFFS: Lazy<TtlCache<String, Option<Option<NaiveDateTime>>>> = Lazy::new(|| TtlCache::new(cache_ttl(), CACHE_CAPACITY));
+
+#[allow(dead_code)]
+pub enum RevocationError {
+    Revoked(&'static str),
+    DatabaseError(Error),
+}
+
+impl From<Error> for RevocationError {
+    fn from(err: Error) -> Self {
+        RevocationError::DatabaseError(err)
+    }
+}
+
+fn cache_ttl() -> Duration {
+    parse_jwt_exp(&CONFIG.revocation_cache_ttl).unwrap_or(Duration::from_secs(30))
+}
+
+pub async fn ensure_not_revoked(pool: &PgPool, claims: &Claims) -> Result<(), RevocationError> {
+    let revoked = match REVOKED_TOKENS.get(&claims.jti) {
+        Some(revoked) => revoked,
+        None => {
+            let revoked = revocations::is_token_revoked(pool, &claims.jti).await?;
+            REVOKED_TOKENS.insert(claims.jti.clone(), revoked);
+            revoked
+        }
+    };
+    if revoked {
+        return Err(RevocationError::Revoked("Token has been revoked."));
+    }
+
+    let cutoff = match TOKEN_CUTOFFS.get(&claims.sub) {
+        Some(cutoff) => cutoff,
+        None => {
+            let cutoff = match users::get_user_token_cutoff(pool, &claims.sub).await {
+                Ok(cutoff) => Some(cutoff),
+                Err(Error::RowNotFound) => None,
+                Err(err) => return Err(err.into()),
+            };
+            TOKEN_CUTOFFS.insert(claims.sub.clone(), cutoff);
+            cutoff
+        }
+    };
+    match cutoff {
+        None => Err(RevocationError::Revoked("Token is no longer valid.")),
+        Some(Some(cutoff)) if issued_before(claims, cutoff) => Err(RevocationError::Revoked("Token has been revoked.")),
+        Some(_) => Ok(()),
+    }
+}
+
+// A token issued in the same second as the cutoff is only let through when it
+// carries the microseconds to show it came after it.
+fn issued_before(claims: &Claims, cutoff: NaiveDateTime) -> bool {
+    let cutoff = cutoff.and_utc();
+    match claims.iat_us {
+        Some(iat_us) => iat_us < cutoff.timestamp_micros(),
+        None => (claims.iat as i64) <= cutoff.timestamp(),
+    }
+}
+
+pub fn mark_token_revoked(jti: &str) {
+    REVOKED_TOKENS.insert(jti.to_string(), true);
+}
+
+pub fn forget_user(username: &str) {
+    TOKEN_CUTOFFS.remove(&username.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn claims(iat: usize, iat_us: Option<i64>) -> Claims {
+        Claims {
+            sub: "alice".to_string(),
+            name: "alice".to_string(),
+            iat,
+            iat_us,
+            exp: iat + 900,
+            jti: "jti".to_string(),
+            sid: "sid".to_string(),
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn tokens_from_the_cutoffs_second_are_told_apart_by_their_microseconds() {
+        let cutoff = DateTime::from_timestamp_micros(1_800_000_000_500_000).unwrap().naive_utc();
+        assert!(issued_before(&claims(1_800_000_000, Some(1_800_000_000_499_999)), cutoff));
+        assert!(!issued_before(&claims(1_800_000_000, Some(1_800_000_000_500_000)), cutoff));
+        assert!(!issued_before(&claims(1_800_000_000, Some(1_800_000_000_900_000)), cutoff));
+        assert!(!issued_before(&claims(1_800_000_001, Some(1_800_000_001_000_000)), cutoff));
+    }
+
+    #[test]
+    fn tokens_without_microseconds_from_the_cutoffs_second_are_revoked() {
+        let cutoff = DateTime::from_timestamp_micros(1_800_000_000_500_000).unwrap().naive_utc();
+        assert!(issued_before(&claims(1_799_999_999, None), cutoff));
+        assert!(issued_before(&claims(1_800_000_000, None), cutoff));
+        assert!(!issued_before(&claims(1_800_000_001, None), cutoff));
+    }
+}
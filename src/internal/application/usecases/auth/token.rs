@@ -0,0 +1,63 @@
+use crate::internal::domain::entities::auth::login::{Claims, Token};
+use crate::internal::domain::entities::auth::session::CreateSession;
+use crate::internal::domain::entities::users::users::User;
+use crate::internal::application::repositories::auth::{roles, sessions};
+use crate::internal::pkg::utils::crypto::{generate_token, hash_token};
+use crate::internal::pkg::utils::jwt_keys::JWT_KEYS;
+use crate::internal::pkg::utils::request::{client_ip, user_agent};
+use crate::config::settings::CONFIG;
+use crate::middlewares::jwt::parse_jwt_exp;
+use actix_web::HttpRequest;
+use chrono::Utc;
+use sqlx::postgres::PgPool;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Issue an access token together with a refresh token persisted as a session.
+// Pass the family of the rotated session to keep reuse detection working across refreshes.
+pub async fn issue_token(
+    pool: &PgPool,
+    http_req: &HttpRequest,
+    user: &User,
+    family_id: Option<String>,
+) -> Result<Token, String> {
+    let jwt_exp = CONFIG.jwt_exp.clone();
+    let exp_duration = parse_jwt_exp(&jwt_exp)
+        .ok_or_else(|| format!("Invalid JWT_EXP format: {}", jwt_exp))?;
+    let refresh_exp = CONFIG.refresh_token_exp.clone();
+    let refresh_duration = parse_jwt_exp(&refresh_exp)
+        .and_then(|duration| chrono::Duration::from_std(duration).ok())
+        .ok_or_else(|| format!("Invalid REFRESH_TOKEN_EXP format: {}", refresh_exp))?;
+
+    let user_roles = roles::get_user_roles(pool, user.id).await.map_err(|err| err.to_string())?;
+    let permissions = roles::get_user_permissions(pool, user.id).await.map_err(|err| err.to_string())?;
+
+    let family_id = family_id.unwrap_or_else(|| generate_token(16));
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let exp_timestamp = now + exp_duration;
+    let claims = Claims {
+        sub: user.username.clone(),
+        name: user.name.clone().unwrap_or_else(|| user.username.clone()),
+        iat: now.as_secs() as usize,
+        iat_us: Some(now.as_micros() as i64),
+        exp: exp_timestamp.as_secs() as usize, // Expiration timestamp
+        jti: generate_token(16),
+        sid: family_id.clone(),
+        roles: user_roles,
+        permissions,
+    };
+
+    let token = JWT_KEYS.encode(&claims).map_err(|err| err.to_string())?;
+
+    let refresh_token = generate_token(32);
+    let new_session = CreateSession {
+        user_id: user.id,
+        family_id,
+        refresh_token_hash: hash_token(&refresh_token),
+        device: user_agent(http_req),
+        ip: client_ip(http_req),
+        expires_at: Utc::now().naive_utc() + refresh_duration,
+    };
+    sessions::create_session(pool, new_session).await.map_err(|err| err.to_string())?;
+
+    Ok(Token { token, refresh_token })
+}
@@ -1,19 +1,27 @@
-use crate::internal::domain::entities::items::items::{CreateItem, UpdateItem, Items, ItemsQuery};
+use crate::internal::domain::entities::items::items::{CreateItem, UpdateItem, Items, ItemsPage, ItemsQuery};
+use crate::config::settings::CONFIG;
 use crate::internal::domain::entities::response::Response;
-use crate::internal::application::repositories::items::items::{self, DeleteItemError};
+use crate::internal::application::repositories::items::items::{DeleteItemError, ItemRepository, UpdateItemError, ITEM_SCHEMA};
+use crate::internal::application::usecases::audit::audit::{diff, AuditEntry, Auditor, OUTCOME_FAILURE, OUTCOME_SUCCESS};
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::internal::pkg::query::cursor::CountMode;
+use crate::internal::pkg::query::query::ListQuery;
+use crate::internal::pkg::query::search::Search;
+use crate::internal::pkg::utils::etag::{entity_tag, if_match, not_modified};
 use crate::internal::pkg::utils::pagination::PaginationRequest;
-use crate::internal::constant::status::{SUCCESS, FAILED_INTERNAL, FAILED_NOT_FOUND, FAILED_EXIST};
-use actix_web::{HttpRequest, HttpResponse, Responder, web};
-use sqlx::{Error, postgres::PgPool};
+use crate::internal::constant::status::{SUCCESS, FAILED_INTERNAL, FAILED_NOT_FOUND, FAILED_EXIST, FAILED_PRECONDITION, FAILED_REQUIRED};
+use actix_web::{HttpRequest, HttpResponse, Responder, http::header::ETag, web};
+use sqlx::Error;
 use serde_json::json;
-use std::collections::HashMap;
-use url::form_urlencoded;
 
 pub async fn create_item(
-    pool: web::Data<PgPool>,
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn ItemRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
     item: web::Json<CreateItem>,
 ) -> impl Responder {
-    if let Ok(_) = items::get_item_name(pool.get_ref(), item.name.as_str()).await {
+    if repository.get_item_name(item.name.as_str()).await.is_ok() {
         return HttpResponse::BadRequest()
         .json(
             Response::<serde_json::Value> {
@@ -24,15 +32,97 @@ pub async fn create_item(
         );
     }
 
-    match items::create_item(&pool, item.into_inner()).await {
-        Ok(new_item) => HttpResponse::Ok()
-        .json(
-            Response {
-                response_code: SUCCESS.to_string(),
-                response_desc: "OK".to_string(),
-                response_data: Some(json!(new_item)),
+    match repository.create_item(item.into_inner(), auditor.actor_id(&identity).await).await {
+        Ok(new_item) => {
+            let entry = AuditEntry::new("item.create", OUTCOME_SUCCESS).by_identity(&identity).on("item", new_item.id).changes(diff(None, Some(&json!(new_item))));
+            auditor.record(&http_req, entry).await;
+            HttpResponse::Ok()
+            .json(
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
+                    response_data: Some(json!(new_item)),
+                }
+            )
+        },
+        Err(err) => {
+            auditor.record(&http_req, AuditEntry::new("item.create", OUTCOME_FAILURE).by_identity(&identity)).await;
+            HttpResponse::InternalServerError()
+            .json(
+                Response::<serde_json::Value> {
+                    response_code: FAILED_INTERNAL.to_string(),
+                    response_desc: err.to_string(),
+                    response_data: None,
+                }
+            )
+        },
+    }
+}
+
+fn bad_request(desc: String) -> HttpResponse {
+    HttpResponse::BadRequest()
+    .json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_REQUIRED.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+// If-Match named a version the item is no longer at.
+fn precondition_failed() -> HttpResponse {
+    HttpResponse::PreconditionFailed()
+    .json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_PRECONDITION.to_string(),
+            response_desc: "Precondition Failed".to_string(),
+            response_data: None,
+        }
+    )
+}
+
+// Cursor mode of get_items, asked for with a `cursor` parameter.
+async fn get_items_page(
+    repository: &dyn ItemRepository,
+    query: ListQuery,
+    includes: &[&str],
+    count: Option<&str>,
+) -> HttpResponse {
+    let count = match CountMode::parse(count) {
+        Ok(count) => count,
+        Err(desc) => return bad_request(desc),
+    };
+    let limit = query.limit();
+    match repository.get_items_page(query, includes, count).await {
+        Ok((page, total)) => {
+            if page.items.is_empty() {
+                return HttpResponse::NotFound()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_NOT_FOUND.to_string(),
+                        response_desc: "Not Found".to_string(),
+                        response_data: None,
+                    }
+                );
             }
-        ),
+
+            let paginated = ItemsPage {
+                limit,
+                total,
+                next_cursor: page.next_cursor,
+                prev_cursor: page.prev_cursor,
+                items: page.items,
+            };
+            HttpResponse::Ok()
+            .json(
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
+                    response_data: Some(json!(paginated)),
+                }
+            )
+        },
         Err(err) => HttpResponse::InternalServerError()
         .json(
             Response::<serde_json::Value> {
@@ -44,30 +134,69 @@ pub async fn create_item(
     }
 }
 
-pub async fn get_items(
-    pool: web::Data<PgPool>,
+// The live items or, with `trashed`, the deleted ones.
+async fn list_items(
+    repository: web::Data<dyn ItemRepository>,
     req: HttpRequest,
-    params: web::Query<ItemsQuery>
-) -> impl Responder {
+    params: web::Query<ItemsQuery>,
+    trashed: bool,
+) -> HttpResponse {
     let page = params.page.unwrap_or(1);
     let limit = params.limit.unwrap_or(10);
-    let sort = params.sort.as_deref().unwrap_or("name");
-    let field = params.field.as_deref().unwrap_or("ASC");
-    let pagination = PaginationRequest::new(limit, page, field, sort);
-    let mut filter_map = params.filter.clone().unwrap_or_default();
-    let query_str = req.query_string();
-    let query: HashMap<String, String> = form_urlencoded::parse(query_str.as_bytes()).into_owned().collect();
-    for (key, value) in query {
-        if key.starts_with("filter[") && key.ends_with("]") {
-            let inner_key = &key[7..key.len()-1];
-            filter_map.insert(inner_key.to_string(), value.clone());
-            // println!("Received filter {}: {}", inner_key, value);
-        }
+    let pagination = PaginationRequest::new(limit, page, params.field.as_deref(), params.sort.as_deref());
+    let filters = match ITEM_SCHEMA.parse_filters(req.query_string()) {
+        Ok(filters) => filters,
+        Err(desc) => return bad_request(desc),
+    };
+    let search = match params.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        Some(q) => match Search::parse(q, &CONFIG.search_language) {
+            Ok(search) => Some(search),
+            Err(desc) => return bad_request(desc),
+        },
+        None => None,
+    };
+    // Search results come most relevant first and the trash most recently deleted first,
+    // unless another sort is asked for.
+    let default_sort = if search.is_some() {
+        "-rank"
+    } else if trashed {
+        "-deletedAt"
+    } else {
+        ITEM_SCHEMA.default_sort
+    };
+    let order = match ITEM_SCHEMA.parse_sort(&pagination.sort_or(default_sort)) {
+        Ok(order) => order,
+        Err(desc) => return bad_request(desc),
+    };
+    let mut fields = match ITEM_SCHEMA.parse_fields(params.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(desc) => return bad_request(desc),
+    };
+    if trashed && params.fields.is_none() {
+        fields.extend(["deletedAt", "deletedBy"].into_iter().filter_map(|name| ITEM_SCHEMA.column(name)));
+    }
+    if params.highlight.unwrap_or(false)
+        && !fields.iter().any(|field| field.name == "headline")
+    {
+        fields.extend(ITEM_SCHEMA.column("headline"));
+    }
+    let includes = match ITEM_SCHEMA.parse_includes(params.include.as_deref()) {
+        Ok(includes) => includes,
+        Err(desc) => return bad_request(desc),
+    };
+    let cursor = match params.cursor.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(cursor) => match ITEM_SCHEMA.decode_cursor(cursor, &order) {
+            Ok(cursor) => Some(cursor),
+            Err(desc) => return bad_request(desc),
+        },
+    };
+
+    let query = ListQuery::new(&ITEM_SCHEMA).fields(fields).filters(filters).order_by(order).search(search).trashed(trashed);
+    if params.cursor.is_some() {
+        return get_items_page(repository.get_ref(), query.paginate(pagination.limit, 0).after(cursor), &includes, params.count.as_deref()).await;
     }
-    // if filter_map.is_empty() {
-    //     println!("Filter is not provided");
-    // }
-    match items::get_items(pool.get_ref(), pagination, filter_map).await {
+    match repository.get_items(query.paginate(pagination.limit, (pagination.page - 1) * pagination.limit), &includes).await {
         Ok((items, count)) => {
             if count == 0 {
                 return HttpResponse::NotFound()
@@ -108,12 +237,33 @@ pub async fn get_items(
     }
 }
 
+pub async fn get_items(
+    repository: web::Data<dyn ItemRepository>,
+    req: HttpRequest,
+    params: web::Query<ItemsQuery>
+) -> impl Responder {
+    list_items(repository, req, params, false).await
+}
+
+pub async fn get_trashed_items(
+    repository: web::Data<dyn ItemRepository>,
+    req: HttpRequest,
+    params: web::Query<ItemsQuery>
+) -> impl Responder {
+    list_items(repository, req, params, true).await
+}
+
 pub async fn get_item(
-    pool: web::Data<PgPool>,
+    repository: web::Data<dyn ItemRepository>,
+    http_req: HttpRequest,
     item_id: web::Path<i32>
 ) -> impl Responder {
-    match items::get_item(pool.get_ref(), item_id.into_inner()).await {
+    match repository.get_item(item_id.into_inner()).await {
+        Ok(item) if not_modified(&http_req, item.version) => HttpResponse::NotModified()
+        .insert_header(ETag(entity_tag(item.version)))
+        .finish(),
         Ok(item) => HttpResponse::Ok()
+        .insert_header(ETag(entity_tag(item.version)))
         .json(
             Response {
                 response_code: SUCCESS.to_string(),
@@ -143,86 +293,441 @@ pub async fn get_item(
 }
 
 pub async fn update_item(
-    pool: web::Data<PgPool>,
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn ItemRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
     item_id: web::Path<i32>,
     update: web::Json<UpdateItem>,
 ) -> impl Responder {
     let id = item_id.into_inner();
-    if let Some(name) = update.name.as_deref() {
-        if let Ok(item) = items::get_item_name(pool.get_ref(), name).await {
-            if item.id != id {
-                return HttpResponse::BadRequest()
+    if let Some(name) = update.name.as_deref()
+        && let Ok(item) = repository.get_item_name(name).await
+        && item.id != id {
+        return HttpResponse::BadRequest()
+        .json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_EXIST.to_string(),
+                response_desc: "Name already exist".to_string(),
+                response_data: None,
+            }
+        );
+    }
+
+    let before = repository.get_item(id).await.ok().map(|item| json!(item));
+    match repository.update_item(id, update.into_inner(), if_match(&http_req), auditor.actor_id(&identity).await).await {
+        Ok(item) => {
+            let entry = AuditEntry::new("item.update", OUTCOME_SUCCESS).by_identity(&identity).on("item", item.id).changes(diff(before.as_ref(), Some(&json!(item))));
+            auditor.record(&http_req, entry).await;
+            HttpResponse::Ok()
+            .insert_header(ETag(entity_tag(item.version)))
+            .json(
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
+                    response_data: Some(json!(item)),
+                }
+            )
+        },
+        Err(err) => {
+            auditor.record(&http_req, AuditEntry::new("item.update", OUTCOME_FAILURE).by_identity(&identity).on("item", id)).await;
+            match err {
+                UpdateItemError::NotFound => HttpResponse::NotFound()
                 .json(
                     Response::<serde_json::Value> {
-                        response_code: FAILED_EXIST.to_string(),
-                        response_desc: "Name already exist".to_string(),
+                        response_code: FAILED_NOT_FOUND.to_string(),
+                        response_desc: "Not Found".to_string(),
                         response_data: None,
                     }
-                );
+                ),
+                UpdateItemError::VersionMismatch => precondition_failed(),
+                UpdateItemError::DatabaseError(err) => HttpResponse::InternalServerError()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_INTERNAL.to_string(),
+                        response_desc: err.to_string(),
+                        response_data: None,
+                    }
+                ),
             }
-        }
+        },
     }
+}
 
-    match items::update_item(pool.get_ref(), id, update.into_inner()).await {
-        Ok(item) => HttpResponse::Ok()
-        .json(
-            Response {
-                response_code: SUCCESS.to_string(),
-                response_desc: "OK".to_string(),
-                response_data: Some(json!(item)),
-            }
-        ),
-        Err(err) => match err {
-            Error::RowNotFound => HttpResponse::NotFound()
-            .json(
-                Response::<serde_json::Value> {
-                    response_code: FAILED_NOT_FOUND.to_string(),
-                    response_desc: "Not Found".to_string(),
-                    response_data: None,
-                }
-            ),
-            _ => HttpResponse::InternalServerError()
+pub async fn delete_item(
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn ItemRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
+    item_id: web::Path<i32>,
+) -> impl Responder {
+    let id = item_id.into_inner();
+    let before = repository.get_item(id).await.ok().map(|item| json!(item));
+    let deleted_by = auditor.actor_id(&identity).await;
+    match repository.delete_item(id, deleted_by, if_match(&http_req)).await {
+        Ok(_) => {
+            let entry = AuditEntry::new("item.delete", OUTCOME_SUCCESS).by_identity(&identity).on("item", id).changes(diff(before.as_ref(), None));
+            auditor.record(&http_req, entry).await;
+            HttpResponse::Ok()
             .json(
                 Response::<serde_json::Value> {
-                    response_code: FAILED_INTERNAL.to_string(),
-                    response_desc: err.to_string(),
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
                     response_data: None,
                 }
-            ),
+            )
+        },
+        Err(err) => {
+            auditor.record(&http_req, AuditEntry::new("item.delete", OUTCOME_FAILURE).by_identity(&identity).on("item", id)).await;
+            match err {
+                DeleteItemError::NotFound => HttpResponse::NotFound()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_NOT_FOUND.to_string(),
+                        response_desc: "Not Found".to_string(),
+                        response_data: None,
+                    }
+                ),
+                DeleteItemError::VersionMismatch => precondition_failed(),
+                _ => HttpResponse::InternalServerError()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_INTERNAL.to_string(),
+                        response_desc: "Internal Server Error".to_string(),
+                        response_data: None,
+                    }
+                ),
+            }
         },
     }
 }
 
-pub async fn delete_item(
-    pool: web::Data<PgPool>,
+pub async fn restore_item(
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn ItemRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
     item_id: web::Path<i32>,
 ) -> impl Responder {
-    match items::delete_item(pool.get_ref(), item_id.into_inner()).await {
-        Ok(_) => HttpResponse::Ok()
+    let id = item_id.into_inner();
+    // The name may have been taken by another item while this one was in the trash.
+    if let Ok(deleted) = repository.get_deleted_item(id).await
+        && repository.get_item_name(&deleted.name).await.is_ok() {
+        return HttpResponse::BadRequest()
         .json(
             Response::<serde_json::Value> {
-                response_code: SUCCESS.to_string(),
-                response_desc: "OK".to_string(),
+                response_code: FAILED_EXIST.to_string(),
+                response_desc: "Name already exist".to_string(),
                 response_data: None,
             }
-        ),
-        Err(err) => match err {
-            DeleteItemError::NotFound => HttpResponse::NotFound()
-            .json(
-                Response::<serde_json::Value> {
-                    response_code: FAILED_NOT_FOUND.to_string(),
-                    response_desc: "Not Found".to_string(),
-                    response_data: None,
-                }
-            ),
-            _ => HttpResponse::InternalServerError()
+        );
+    }
+
+    match repository.restore_item(id, auditor.actor_id(&identity).await).await {
+        Ok(item) => {
+            let entry = AuditEntry::new("item.restore", OUTCOME_SUCCESS).by_identity(&identity).on("item", item.id).changes(diff(None, Some(&json!(item))));
+            auditor.record(&http_req, entry).await;
+            HttpResponse::Ok()
             .json(
-                Response::<serde_json::Value> {
-                    response_code: FAILED_INTERNAL.to_string(),
-                    response_desc: "Internal Server Error".to_string(),
-                    response_data: None,
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
+                    response_data: Some(json!(item)),
                 }
-            ),
+            )
         },
+        Err(err) => {
+            auditor.record(&http_req, AuditEntry::new("item.restore", OUTCOME_FAILURE).by_identity(&identity).on("item", id)).await;
+            match err {
+                Error::RowNotFound => HttpResponse::NotFound()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_NOT_FOUND.to_string(),
+                        response_desc: "Not Found".to_string(),
+                        response_data: None,
+                    }
+                ),
+                // Another item took the name after the check above.
+                Error::Database(err) if err.code().as_deref() == Some("23505") => HttpResponse::BadRequest()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_EXIST.to_string(),
+                        response_desc: "Name already exist".to_string(),
+                        response_data: None,
+                    }
+                ),
+                _ => HttpResponse::InternalServerError()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_INTERNAL.to_string(),
+                        response_desc: err.to_string(),
+                        response_data: None,
+                    }
+                ),
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::testing;
+    use crate::internal::application::controllers::items::items::*;
+    use crate::internal::application::repositories::audit::memory::MemoryAuditEventRepository;
+    use crate::internal::application::repositories::items::items::ItemRepository;
+    use crate::internal::application::repositories::items::memory::MemoryItemRepository;
+    use crate::internal::application::repositories::users::memory::MemoryUserRepository;
+    use crate::internal::application::repositories::users::users::UserRepository;
+    use crate::internal::application::usecases::audit::audit::Auditor;
+    use crate::internal::constant::status::{FAILED_EXIST, FAILED_PRECONDITION};
+    use crate::internal::domain::entities::auth::identity::Identity;
+    use crate::internal::domain::entities::items::items::CreateItem;
+    use crate::internal::domain::entities::users::users::CreateUserRequest;
+    use actix_http::Request;
+    use actix_web::dev::{Service, ServiceResponse};
+    use actix_web::http::{header, StatusCode};
+    use actix_web::{test, web, App, HttpMessage};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    struct Backends {
+        users: Arc<MemoryUserRepository>,
+        items: Arc<MemoryItemRepository>,
+        events: Arc<MemoryAuditEventRepository>,
+    }
+
+    // Memory repositories with an admin user, and the given items created by them.
+    async fn backends(items: &[(&str, Option<&str>)]) -> Backends {
+        testing::init();
+        let users = Arc::new(MemoryUserRepository::new(&["admin", "user"]));
+        let admin = CreateUserRequest {
+            username: "admin".to_string(),
+            email: "admin@example.com".to_string(),
+            password: String::new(),
+            name: None,
+            roles: Some(vec!["admin".to_string()]),
+        };
+        users.create_user(admin, None, None).await.unwrap();
+        let backends = Backends {
+            items: Arc::new(MemoryItemRepository::new(users.clone())),
+            users,
+            events: Arc::new(MemoryAuditEventRepository::default()),
+        };
+        for (name, description) in items {
+            let item = CreateItem { name: name.to_string(), description: description.map(str::to_string) };
+            backends.items.create_item(item, Some(1)).await.unwrap();
+        }
+        backends
+    }
+
+    fn admin() -> Identity {
+        Identity {
+            sub: "admin".to_string(),
+            name: "admin".to_string(),
+            roles: vec!["admin".to_string()],
+            permissions: Vec::new(),
+            claims: None,
+            api_key_id: None,
+        }
+    }
+
+    // The item routes without their policies, called as the admin.
+    async fn app(backends: &Backends) -> impl Service<Request, Response = ServiceResponse, Error = actix_web::Error> {
+        let items: Arc<dyn ItemRepository> = backends.items.clone();
+        let auditor = Auditor::new(backends.events.clone(), backends.users.clone());
+        test::init_service(
+            App::new()
+                .app_data(web::Data::from(items))
+                .app_data(web::Data::new(auditor))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(admin());
+                    srv.call(req)
+                })
+                .service(
+                    web::scope("/items")
+                        .route("", web::post().to(create_item_controller))
+                        .route("", web::get().to(get_items_controller))
+                        .route("/trash", web::get().to(get_trashed_items_controller))
+                        .route("/{id}", web::get().to(get_item_controller))
+                        .route("/{id}", web::put().to(update_item_controller))
+                        .route("/{id}", web::delete().to(delete_item_controller))
+                        .route("/{id}/restore", web::post().to(restore_item_controller)),
+                ),
+        )
+        .await
+    }
+
+    async fn send(
+        app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>,
+        req: test::TestRequest,
+    ) -> (StatusCode, Value) {
+        let response = test::call_service(app, req.to_request()).await;
+        let status = response.status();
+        let body = test::read_body(response).await;
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    async fn get(app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>, uri: &str) -> (StatusCode, Value) {
+        send(app, test::TestRequest::get().uri(uri)).await
+    }
+
+    // The ids of a listed page.
+    fn ids(body: &Value) -> Vec<i64> {
+        body["responseData"]["items"].as_array().unwrap().iter().map(|item| item["id"].as_i64().unwrap()).collect()
+    }
+
+    const FRUITS: [(&str, Option<&str>); 5] = [
+        ("cherry", Some("red")),
+        ("apple", Some("red or green")),
+        ("elderberry", None),
+        ("banana", Some("yellow")),
+        ("date", Some("brown")),
+    ];
+
+    #[actix_web::test]
+    async fn filters_and_sorts_the_list() {
+        let backends = backends(&FRUITS).await;
+        let app = app(&backends).await;
+
+        let (status, body) = get(&app, "/items?filter[description]=red").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&body), [1, 2]);
+        let (_, body) = get(&app, "/items?filter[nameLength][gte]=6&sort=-nameLength,name").await;
+        assert_eq!(ids(&body), [3, 4, 1]);
+        let (_, body) = get(&app, "/items?filter[id][in]=1,3,5&filter[description][null]=false").await;
+        assert_eq!(ids(&body), [1, 5]);
+        let (_, body) = get(&app, "/items?sort=name&fields=name").await;
+        assert_eq!(ids(&body), [2, 4, 1, 5, 3]);
+        assert_eq!(body["responseData"]["items"][0], json!({ "id": 2, "name": "apple" }));
+        let (_, body) = get(&app, "/items?sort=desc").await;
+        assert_eq!(ids(&body), [5, 4, 3, 2, 1]);
+
+        let (status, _) = get(&app, "/items?filter[name][gt]=apple").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get(&app, "/items?filter[description]=purple").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn pages_by_offset() {
+        let backends = backends(&FRUITS).await;
+        let app = app(&backends).await;
+
+        let (status, body) = get(&app, "/items?limit=2&page=2").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&body), [3, 4]);
+        assert_eq!(body["responseData"]["total"], 5);
+        assert_eq!(body["responseData"]["totalPage"], 3);
+        let (_, body) = get(&app, "/items?limit=2&page=3").await;
+        assert_eq!(ids(&body), [5]);
+        // Past the last page is an empty page, only an empty list is not found.
+        let (status, body) = get(&app, "/items?limit=2&page=4").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&body), Vec::<i64>::new());
+    }
+
+    #[actix_web::test]
+    async fn pages_by_cursor() {
+        let backends = backends(&FRUITS).await;
+        let app = app(&backends).await;
+
+        let (status, first) = get(&app, "/items?limit=2&sort=name&cursor=&count=exact").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&first), [2, 4]);
+        assert_eq!(first["responseData"]["total"], 5);
+        assert_eq!(first["responseData"]["prevCursor"], Value::Null);
+
+        let next = first["responseData"]["nextCursor"].as_str().unwrap();
+        let (_, second) = get(&app, &format!("/items?limit=2&sort=name&cursor={}", next)).await;
+        assert_eq!(ids(&second), [1, 5]);
+        let next = second["responseData"]["nextCursor"].as_str().unwrap();
+        let (_, last) = get(&app, &format!("/items?limit=2&sort=name&cursor={}", next)).await;
+        assert_eq!(ids(&last), [3]);
+        assert_eq!(last["responseData"]["nextCursor"], Value::Null);
+
+        let prev = last["responseData"]["prevCursor"].as_str().unwrap();
+        let (_, back) = get(&app, &format!("/items?limit=2&sort=name&cursor={}", prev)).await;
+        assert_eq!(ids(&back), [1, 5]);
+
+        // A cursor only fits the order it was made for.
+        let (status, _) = get(&app, &format!("/items?limit=2&sort=-name&cursor={}", next)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn trashes_and_restores() {
+        let backends = backends(&FRUITS).await;
+        let app = app(&backends).await;
+
+        let (status, _) = send(&app, test::TestRequest::delete().uri("/items/2")).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = get(&app, "/items/2").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, body) = get(&app, "/items").await;
+        assert_eq!(ids(&body), [1, 3, 4, 5]);
+        let (_, body) = get(&app, "/items/trash").await;
+        assert_eq!(ids(&body), [2]);
+        assert_eq!(body["responseData"]["items"][0]["deletedBy"], 1);
+
+        let (status, body) = send(&app, test::TestRequest::post().uri("/items/2/restore")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["responseData"]["name"], "apple");
+        let (status, _) = get(&app, "/items/2").await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = get(&app, "/items/trash").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(&app, test::TestRequest::post().uri("/items/2/restore")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let recorded = backends.events.recorded();
+        assert_eq!(recorded[0], ("item.delete".to_string(), "success".to_string(), Some("2".to_string())));
+        assert_eq!(recorded[1], ("item.restore".to_string(), "success".to_string(), Some("2".to_string())));
+        assert_eq!(recorded[2], ("item.restore".to_string(), "failure".to_string(), Some("2".to_string())));
+    }
+
+    #[actix_web::test]
+    async fn writes_with_a_stale_if_match_fail_their_precondition() {
+        let backends = backends(&FRUITS).await;
+        let app = app(&backends).await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/items/1").to_request()).await;
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), "\"1\"");
+
+        let update = || test::TestRequest::put().uri("/items/1").set_json(json!({ "description": "dark red" }));
+        let (status, body) = send(&app, update().insert_header((header::IF_MATCH, "\"1\""))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["responseData"]["version"], 2);
+        let (status, body) = send(&app, update().insert_header((header::IF_MATCH, "\"1\""))).await;
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+        assert_eq!(body["responseCode"], FAILED_PRECONDITION);
+        let (status, _) = send(&app, test::TestRequest::delete().uri("/items/1").insert_header((header::IF_MATCH, "\"1\""))).await;
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+        let (status, _) = send(&app, test::TestRequest::delete().uri("/items/1").insert_header((header::IF_MATCH, "\"2\""))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let outcomes: Vec<String> = backends.events.recorded().into_iter().map(|(action, outcome, _)| format!("{} {}", action, outcome)).collect();
+        assert_eq!(outcomes, ["item.update success", "item.update failure", "item.delete failure", "item.delete success"]);
+    }
+
+    #[actix_web::test]
+    async fn refuses_duplicate_names() {
+        let backends = backends(&FRUITS).await;
+        let app = app(&backends).await;
+
+        let (status, body) = send(&app, test::TestRequest::post().uri("/items").set_json(json!({ "name": "apple" }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["responseCode"], FAILED_EXIST);
+        let (status, body) = send(&app, test::TestRequest::put().uri("/items/1").set_json(json!({ "name": "apple" }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["responseDesc"], "Name already exist");
+
+        // A name is free again while its item is in the trash, and then blocks the restore.
+        send(&app, test::TestRequest::delete().uri("/items/2")).await;
+        let (status, body) = send(&app, test::TestRequest::post().uri("/items").set_json(json!({ "name": "apple" }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["responseData"]["id"], 6);
+        let (status, body) = send(&app, test::TestRequest::post().uri("/items/2/restore")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["responseCode"], FAILED_EXIST);
     }
 }
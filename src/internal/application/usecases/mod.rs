@@ -1,3 +1,5 @@
 pub mod items;
+pub mod audit;
 pub mod auth;
+pub mod trash;
 pub mod users;
\ No newline at end of file
@@ -0,0 +1 @@
+pub mod trash;
\ No newline at end of file
@@ -0,0 +1,41 @@
+use crate::config::settings::CONFIG;
+use crate::internal::application::repositories::items::items::ItemRepository;
+use crate::internal::application::repositories::users::users::UserRepository;
+use crate::middlewares::jwt::parse_jwt_exp;
+use actix_web::{rt, web};
+use chrono::Utc;
+use log::{error, info};
+use std::time::Duration;
+
+// Hard deletes the items and users deleted longer than `retention` ago.
+async fn purge_trash(items: &dyn ItemRepository, users: &dyn UserRepository, retention: Duration) -> Result<(u64, u64), sqlx::Error> {
+    let Ok(retention) = chrono::Duration::from_std(retention) else {
+        return Ok((0, 0));
+    };
+    let deleted_before = Utc::now().naive_utc() - retention;
+    let items = items.purge_deleted_items(deleted_before).await?;
+    let users = users.purge_deleted_users(deleted_before).await?;
+    Ok((items, users))
+}
+
+// Runs the trash purge every TRASH_PURGE_INTERVAL, starting now. An empty
+// TRASH_RETENTION keeps deleted rows until they are restored.
+pub fn spawn_trash_purge(items: web::Data<dyn ItemRepository>, users: web::Data<dyn UserRepository>) {
+    if CONFIG.trash_retention.trim().is_empty() {
+        return;
+    }
+    let retention = parse_jwt_exp(&CONFIG.trash_retention).expect("Invalid TRASH_RETENTION");
+    let every = parse_jwt_exp(&CONFIG.trash_purge_interval).expect("Invalid TRASH_PURGE_INTERVAL");
+
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(every);
+        loop {
+            interval.tick().await;
+            match purge_trash(items.get_ref(), users.get_ref(), retention).await {
+                Ok((0, 0)) => {}
+                Ok((items, users)) => info!("trash purge removed {} items and {} users", items, users),
+                Err(err) => error!("trash purge failed: {}", err),
+            }
+        }
+    });
+}
@@ -1,71 +1,104 @@
-use crate::internal::domain::entities::users::users::{CreateUserRequest, UpdateUserRequest, ListUser, UsersQuery};
+use crate::internal::domain::entities::users::users::{CreateUserRequest, UpdateUserRequest, ListUser, UsersPage, UsersQuery};
 use crate::internal::domain::entities::response::Response;
-use crate::internal::application::repositories::users::users::{self, DeleteItemError};
+use crate::internal::application::repositories::users::users::{DeleteUserError, UpdateUserError, UserRepository, USER_SCHEMA};
+use crate::internal::application::usecases::audit::audit::{diff, AuditEntry, Auditor, OUTCOME_FAILURE, OUTCOME_SUCCESS};
+use crate::internal::application::usecases::auth::revocation::forget_user;
+use crate::internal::pkg::password::password::hash_password;
+use crate::internal::pkg::password::policy::PASSWORD_POLICY;
+use crate::internal::pkg::query::cursor::CountMode;
+use crate::internal::pkg::query::query::ListQuery;
+use crate::internal::pkg::utils::etag::{entity_tag, if_match, not_modified};
 use crate::internal::pkg::utils::pagination::PaginationRequest;
-use crate::internal::constant::status::{FAILED_EXIST, FAILED_INTERNAL, FAILED_NOT_FOUND, FAILED_REQUIRED, SUCCESS, FAILED_AUTHORIZED};
-use crate::internal::domain::entities::auth::login::Claims;
-use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
-use sqlx::{Error, postgres::PgPool};
+use crate::internal::constant::status::{FAILED_EXIST, FAILED_INTERNAL, FAILED_NOT_FOUND, FAILED_PRECONDITION, FAILED_REQUIRED, SUCCESS};
+use crate::internal::domain::entities::auth::identity::Identity;
+use actix_web::{web, http::header::ETag, HttpRequest, HttpResponse, Responder};
+use sqlx::Error;
 use serde_json::json;
-use std::collections::HashMap;
-use url::form_urlencoded;
-use tokio::task;
-use bcrypt::{hash, DEFAULT_COST};
+use chrono::Utc;
+
+// The audited view of a user, without the password hash.
+async fn audit_snapshot(repository: &dyn UserRepository, id: i32) -> Option<serde_json::Value> {
+    let user = repository.get_user_detail(id).await.ok()?;
+    let user_roles = repository.get_user_roles(id).await.ok()?;
+    Some(json!({ "username": user.username, "email": user.email, "name": user.name, "roles": user_roles }))
+}
+
+// Reject role names that are not defined in the roles table.
+async fn validate_roles(repository: &dyn UserRepository, requested: Option<&[String]>) -> Option<HttpResponse> {
+    let requested = requested?;
+    match repository.find_unknown_roles(requested).await {
+        Ok(unknown) if unknown.is_empty() => None,
+        Ok(unknown) => Some(HttpResponse::BadRequest()
+        .json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_REQUIRED.to_string(),
+                response_desc: format!("Unknown role: {}", unknown.join(", ")),
+                response_data: None,
+            }
+        )),
+        Err(err) => Some(HttpResponse::InternalServerError()
+        .json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_INTERNAL.to_string(),
+                response_desc: err.to_string(),
+                response_data: None,
+            }
+        )),
+    }
+}
 
 pub async fn create_user(
-    pool: web::Data<PgPool>,
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn UserRepository>,
     http_req: HttpRequest,
+    identity: Identity,
     payload: web::Json<CreateUserRequest>,
 ) -> impl Responder {
-    let ext = http_req.extensions();
-    let claims = match ext.get::<Claims>() {
-        Some(c) => c,
-        None => return HttpResponse::Unauthorized()
+    if payload.username.trim().is_empty() {
+        return HttpResponse::BadRequest()
         .json(
             Response::<serde_json::Value> {
-                response_code: FAILED_AUTHORIZED.to_string(),
-                response_desc: "Unauthorized".to_string(),
+                response_code: FAILED_REQUIRED.to_string(),
+                response_desc: "Username required".to_string(),
                 response_data: None,
             }
-        ),
-    };
-
-    println!("sub: {:?}", claims.sub);
+        );
+    }
 
-    if payload.username.trim().len() <= 0 {
+    if payload.email.trim().is_empty() {
         return HttpResponse::BadRequest()
         .json(
             Response::<serde_json::Value> {
                 response_code: FAILED_REQUIRED.to_string(),
-                response_desc: "Username required".to_string(),
+                response_desc: "Email required".to_string(),
                 response_data: None,
             }
         );
     }
 
-    if payload.email.trim().len() <= 0 {
+    if payload.password.trim().is_empty() {
         return HttpResponse::BadRequest()
         .json(
             Response::<serde_json::Value> {
                 response_code: FAILED_REQUIRED.to_string(),
-                response_desc: "Email required".to_string(),
+                response_desc: "Password required".to_string(),
                 response_data: None,
             }
         );
     }
 
-    if payload.password.trim().len() <= 0 {
+    if let Some(reason) = PASSWORD_POLICY.check(&payload.password, &payload.username) {
         return HttpResponse::BadRequest()
         .json(
             Response::<serde_json::Value> {
                 response_code: FAILED_REQUIRED.to_string(),
-                response_desc: "Password required".to_string(),
+                response_desc: reason,
                 response_data: None,
             }
         );
     }
 
-    if let Ok(_) = users::get_user_username(pool.get_ref(), payload.username.as_str()).await {
+    if repository.get_user_username(payload.username.as_str()).await.is_ok() {
         return HttpResponse::BadRequest()
         .json(
             Response::<serde_json::Value> {
@@ -76,7 +109,7 @@ pub async fn create_user(
         );
     }
 
-    if let Ok(_) = users::get_user_email(pool.get_ref(), payload.email.as_str()).await {
+    if repository.get_user_email(payload.email.as_str()).await.is_ok() {
         return HttpResponse::BadRequest()
         .json(
             Response::<serde_json::Value> {
@@ -87,10 +120,13 @@ pub async fn create_user(
         );
     }
 
-    let password = payload.password.clone();
-    let hashed = match task::spawn_blocking(move || hash(password, DEFAULT_COST)).await {
-        Ok(Ok(hash)) => hash,
-        _ => return HttpResponse::InternalServerError()
+    if let Some(resp) = validate_roles(repository.get_ref(), payload.roles.as_deref()).await {
+        return resp;
+    }
+
+    let hashed = match hash_password(payload.password.clone()).await {
+        Ok(hash) => hash,
+        Err(_) => return HttpResponse::InternalServerError()
         .json(
             Response::<serde_json::Value> {
                 response_code: FAILED_INTERNAL.to_string(),
@@ -102,15 +138,99 @@ pub async fn create_user(
 
     let mut new_req = payload.into_inner();
     new_req.password = hashed;
-    match users::create_user(&pool, new_req).await {
-        Ok(new_user) => HttpResponse::Ok()
-        .json(
-            Response {
-                response_code: SUCCESS.to_string(),
-                response_desc: "OK".to_string(),
-                response_data: Some(json!(new_user)),
+    // Accounts created by an admin do not go through email verification.
+    match repository.create_user(new_req, Some(Utc::now().naive_utc()), auditor.actor_id(&identity).await).await {
+        Ok(new_user) => {
+            let changes = diff(None, audit_snapshot(repository.get_ref(), new_user.id).await.as_ref());
+            let entry = AuditEntry::new("user.create", OUTCOME_SUCCESS).by_identity(&identity).on("user", new_user.id).changes(changes);
+            auditor.record(&http_req, entry).await;
+            HttpResponse::Ok()
+            .json(
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
+                    response_data: Some(json!(new_user)),
+                }
+            )
+        },
+        Err(err) => {
+            auditor.record(&http_req, AuditEntry::new("user.create", OUTCOME_FAILURE).by_identity(&identity)).await;
+            HttpResponse::InternalServerError()
+            .json(
+                Response::<serde_json::Value> {
+                    response_code: FAILED_INTERNAL.to_string(),
+                    response_desc: err.to_string(),
+                    response_data: None,
+                }
+            )
+        },
+    }
+}
+
+fn bad_request(desc: String) -> HttpResponse {
+    HttpResponse::BadRequest()
+    .json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_REQUIRED.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+// If-Match named a version the user is no longer at.
+fn precondition_failed() -> HttpResponse {
+    HttpResponse::PreconditionFailed()
+    .json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_PRECONDITION.to_string(),
+            response_desc: "Precondition Failed".to_string(),
+            response_data: None,
+        }
+    )
+}
+
+// Cursor mode of get_users, asked for with a `cursor` parameter.
+async fn get_users_page(
+    repository: &dyn UserRepository,
+    query: ListQuery,
+    includes: &[&str],
+    count: Option<&str>,
+) -> HttpResponse {
+    let count = match CountMode::parse(count) {
+        Ok(count) => count,
+        Err(desc) => return bad_request(desc),
+    };
+    let limit = query.limit();
+    match repository.get_users_page(query, includes, count).await {
+        Ok((page, total)) => {
+            if page.items.is_empty() {
+                return HttpResponse::NotFound()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_NOT_FOUND.to_string(),
+                        response_desc: "Not Found".to_string(),
+                        response_data: None,
+                    }
+                );
             }
-        ),
+
+            let paginated = UsersPage {
+                limit,
+                total,
+                next_cursor: page.next_cursor,
+                prev_cursor: page.prev_cursor,
+                users: page.items,
+            };
+            HttpResponse::Ok()
+            .json(
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
+                    response_data: Some(json!(paginated)),
+                }
+            )
+        },
         Err(err) => HttpResponse::InternalServerError()
         .json(
             Response::<serde_json::Value> {
@@ -122,30 +242,50 @@ pub async fn create_user(
     }
 }
 
-pub async fn get_users(
-    pool: web::Data<PgPool>,
+// The live users or, with `trashed`, the deleted ones.
+async fn list_users(
+    repository: web::Data<dyn UserRepository>,
     http_req: HttpRequest,
-    params: web::Query<UsersQuery>
-) -> impl Responder {
+    params: web::Query<UsersQuery>,
+    trashed: bool,
+) -> HttpResponse {
     let page = params.page.unwrap_or(1);
     let limit = params.limit.unwrap_or(10);
-    let sort = params.sort.as_deref().unwrap_or("name");
-    let field = params.field.as_deref().unwrap_or("ASC");
-    let pagination = PaginationRequest::new(limit, page, field, sort);
-    let mut filter_map = params.filter.clone().unwrap_or_default();
-    let query_str = http_req.query_string();
-    let query: HashMap<String, String> = form_urlencoded::parse(query_str.as_bytes()).into_owned().collect();
-    for (key, value) in query {
-        if key.starts_with("filter[") && key.ends_with("]") {
-            let inner_key = &key[7..key.len()-1];
-            filter_map.insert(inner_key.to_string(), value.clone());
-            // println!("Received filter {}: {}", inner_key, value);
-        }
+    let pagination = PaginationRequest::new(limit, page, params.field.as_deref(), params.sort.as_deref());
+    let filters = match USER_SCHEMA.parse_filters(http_req.query_string()) {
+        Ok(filters) => filters,
+        Err(desc) => return bad_request(desc),
+    };
+    // The trash comes most recently deleted first unless another sort is asked for.
+    let default_sort = if trashed { "-deletedAt" } else { USER_SCHEMA.default_sort };
+    let order = match USER_SCHEMA.parse_sort(&pagination.sort_or(default_sort)) {
+        Ok(order) => order,
+        Err(desc) => return bad_request(desc),
+    };
+    let mut fields = match USER_SCHEMA.parse_fields(params.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(desc) => return bad_request(desc),
+    };
+    if trashed && params.fields.is_none() {
+        fields.extend(["deletedAt", "deletedBy"].into_iter().filter_map(|name| USER_SCHEMA.column(name)));
+    }
+    let includes = match USER_SCHEMA.parse_includes(params.include.as_deref()) {
+        Ok(includes) => includes,
+        Err(desc) => return bad_request(desc),
+    };
+    let cursor = match params.cursor.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(cursor) => match USER_SCHEMA.decode_cursor(cursor, &order) {
+            Ok(cursor) => Some(cursor),
+            Err(desc) => return bad_request(desc),
+        },
+    };
+
+    let query = ListQuery::new(&USER_SCHEMA).fields(fields).filters(filters).order_by(order).trashed(trashed);
+    if params.cursor.is_some() {
+        return get_users_page(repository.get_ref(), query.paginate(pagination.limit, 0).after(cursor), &includes, params.count.as_deref()).await;
     }
-    // if filter_map.is_empty() {
-    //     println!("Filter is not provided");
-    // }
-    match users::get_users(pool.get_ref(), pagination, filter_map).await {
+    match repository.get_users(query.paginate(pagination.limit, (pagination.page - 1) * pagination.limit), &includes).await {
         Ok((users, count)) => {
             if count == 0 {
                 return HttpResponse::NotFound()
@@ -186,12 +326,33 @@ pub async fn get_users(
     }
 }
 
+pub async fn get_users(
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    params: web::Query<UsersQuery>
+) -> impl Responder {
+    list_users(repository, http_req, params, false).await
+}
+
+pub async fn get_trashed_users(
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    params: web::Query<UsersQuery>
+) -> impl Responder {
+    list_users(repository, http_req, params, true).await
+}
+
 pub async fn get_user(
-    pool: web::Data<PgPool>,
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
     id: web::Path<i32>
 ) -> impl Responder {
-    match users::get_user_detail(pool.get_ref(), id.into_inner()).await {
+    match repository.get_user_detail(id.into_inner()).await {
+        Ok(user) if not_modified(&http_req, user.version) => HttpResponse::NotModified()
+        .insert_header(ETag(entity_tag(user.version)))
+        .finish(),
         Ok(user) => HttpResponse::Ok()
+        .insert_header(ETag(entity_tag(user.version)))
         .json(
             Response {
                 response_code: SUCCESS.to_string(),
@@ -220,12 +381,19 @@ pub async fn get_user(
     }
 }
 
-pub async fn update_user(
-    pool: web::Data<PgPool>,
+// PUT replaces the username and email so both are required, PATCH (`partial`)
+// changes only what it is given.
+async fn save_user(
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
     id: web::Path<i32>,
     payload: web::Json<UpdateUserRequest>,
-) -> impl Responder {
-    if payload.username.as_deref().map_or(true, |s| s.trim().is_empty()) {
+    partial: bool,
+) -> HttpResponse {
+    let missing = |value: Option<&str>| value.map_or(!partial, |value| value.trim().is_empty());
+    if missing(payload.username.as_deref()) {
         return HttpResponse::BadRequest()
         .json(
             Response::<serde_json::Value> {
@@ -236,7 +404,7 @@ pub async fn update_user(
         );
     }
 
-    if payload.email.as_deref().map_or(true, |s| s.trim().is_empty()) {
+    if missing(payload.email.as_deref()) {
         return HttpResponse::BadRequest()
         .json(
             Response::<serde_json::Value> {
@@ -248,113 +416,550 @@ pub async fn update_user(
     }
 
     let id = id.into_inner();
-    if let Some(username) = payload.username.as_deref() {
-        if let Ok(user) = users::get_user_username(pool.get_ref(), username).await {
-            if user.id != id {
-                return HttpResponse::BadRequest()
-                .json(
-                    Response::<serde_json::Value> {
-                        response_code: FAILED_EXIST.to_string(),
-                        response_desc: "Username already exist".to_string(),
-                        response_data: None,
-                    }
-                );
+    if let Some(username) = payload.username.as_deref()
+        && let Ok(user) = repository.get_user_username(username).await
+        && user.id != id {
+        return HttpResponse::BadRequest()
+        .json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_EXIST.to_string(),
+                response_desc: "Username already exist".to_string(),
+                response_data: None,
             }
-        }
+        );
     }
 
-    if let Some(email) = payload.email.as_deref() {
-        if let Ok(user) = users::get_user_email(pool.get_ref(), email).await {
-            if user.id != id {
-                return HttpResponse::BadRequest()
-                .json(
-                    Response::<serde_json::Value> {
-                        response_code: FAILED_EXIST.to_string(),
-                        response_desc: "Email already exist".to_string(),
-                        response_data: None,
-                    }
-                );
+    if let Some(email) = payload.email.as_deref()
+        && let Ok(user) = repository.get_user_email(email).await
+        && user.id != id {
+        return HttpResponse::BadRequest()
+        .json(
+            Response::<serde_json::Value> {
+                response_code: FAILED_EXIST.to_string(),
+                response_desc: "Email already exist".to_string(),
+                response_data: None,
             }
-        }
+        );
     }
 
-    let mut new_req = payload.into_inner();
-    if let Some(pwd) = new_req.password.clone() {
-        if !pwd.is_empty() {
-            let hashed = match task::spawn_blocking(move || hash(pwd, DEFAULT_COST)).await {
-                Ok(Ok(h)) => h,
-                _ => return HttpResponse::InternalServerError()
-                        .json(
-                            Response::<serde_json::Value> {
-                                response_code: FAILED_INTERNAL.to_string(),
-                                response_desc: "Internal Server Error".to_string(),
-                                response_data: None,
-                            }
-                        ),
-            };
-            new_req.password = Some(hashed);
-        }
+    if let Some(resp) = validate_roles(repository.get_ref(), payload.roles.as_deref()).await {
+        return resp;
     }
-    match users::update_user(pool.get_ref(), id, new_req).await {
-        Ok(user) => HttpResponse::Ok()
-        .json(
-            Response {
-                response_code: SUCCESS.to_string(),
-                response_desc: "OK".to_string(),
-                response_data: Some(json!(user)),
-            }
-        ),
-        Err(err) => match err {
-            Error::RowNotFound => HttpResponse::NotFound()
+
+    if let Some(pwd) = payload.password.as_deref()
+        && !pwd.is_empty() {
+        let username = match payload.username.clone() {
+            Some(username) => username,
+            None => match repository.get_user_detail(id).await {
+                Ok(user) => user.username,
+                Err(Error::RowNotFound) => return HttpResponse::NotFound()
+                    .json(
+                        Response::<serde_json::Value> {
+                            response_code: FAILED_NOT_FOUND.to_string(),
+                            response_desc: "Not Found".to_string(),
+                            response_data: None,
+                        }
+                    ),
+                Err(err) => return HttpResponse::InternalServerError()
+                    .json(
+                        Response::<serde_json::Value> {
+                            response_code: FAILED_INTERNAL.to_string(),
+                            response_desc: err.to_string(),
+                            response_data: None,
+                        }
+                    ),
+            },
+        };
+        if let Some(reason) = PASSWORD_POLICY.check(pwd, &username) {
+            return HttpResponse::BadRequest()
             .json(
                 Response::<serde_json::Value> {
-                    response_code: FAILED_NOT_FOUND.to_string(),
-                    response_desc: "Not Found".to_string(),
+                    response_code: FAILED_REQUIRED.to_string(),
+                    response_desc: reason,
                     response_data: None,
                 }
-            ),
-            _ => HttpResponse::InternalServerError()
+            );
+        }
+    }
+
+    let mut new_req = payload.into_inner();
+    if let Some(pwd) = new_req.password.clone()
+        && !pwd.is_empty() {
+        let hashed = match hash_password(pwd).await {
+            Ok(h) => h,
+            Err(_) => return HttpResponse::InternalServerError()
+                    .json(
+                        Response::<serde_json::Value> {
+                            response_code: FAILED_INTERNAL.to_string(),
+                            response_desc: "Internal Server Error".to_string(),
+                            response_data: None,
+                        }
+                    ),
+        };
+        new_req.password = Some(hashed);
+    }
+    let password_changed = new_req.password.as_deref().is_some_and(|p| !p.trim().is_empty());
+    let before = audit_snapshot(repository.get_ref(), id).await;
+    // Sessions and tokens a new password or new roles end are revoked along with the update.
+    match repository.update_user(id, new_req, if_match(&http_req), auditor.actor_id(&identity).await).await {
+        Ok(user) => {
+            // Tokens name their user by username, the cached cutoffs of the old and new one have to go now.
+            if let Some(old_username) = before.as_ref().and_then(|user| user["username"].as_str()) {
+                forget_user(old_username);
+            }
+            forget_user(&user.username);
+            let mut changes = diff(before.as_ref(), audit_snapshot(repository.get_ref(), user.id).await.as_ref());
+            if password_changed && let Some(fields) = changes.as_object_mut() {
+                fields.insert("password".to_string(), json!({ "from": "[redacted]", "to": "[redacted]" }));
+            }
+            let entry = AuditEntry::new("user.update", OUTCOME_SUCCESS).by_identity(&identity).on("user", user.id).changes(changes);
+            auditor.record(&http_req, entry).await;
+            HttpResponse::Ok()
+            .insert_header(ETag(entity_tag(user.version)))
             .json(
-                Response::<serde_json::Value> {
-                    response_code: FAILED_INTERNAL.to_string(),
-                    response_desc: err.to_string(),
-                    response_data: None,
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
+                    response_data: Some(json!(user)),
                 }
-            ),
+            )
+        },
+        Err(err) => {
+            auditor.record(&http_req, AuditEntry::new("user.update", OUTCOME_FAILURE).by_identity(&identity).on("user", id)).await;
+            match err {
+                UpdateUserError::NotFound => HttpResponse::NotFound()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_NOT_FOUND.to_string(),
+                        response_desc: "Not Found".to_string(),
+                        response_data: None,
+                    }
+                ),
+                UpdateUserError::VersionMismatch => precondition_failed(),
+                UpdateUserError::DatabaseError(err) => HttpResponse::InternalServerError()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_INTERNAL.to_string(),
+                        response_desc: err.to_string(),
+                        response_data: None,
+                    }
+                ),
+            }
         },
     }
 }
 
+pub async fn update_user(
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
+    id: web::Path<i32>,
+    payload: web::Json<UpdateUserRequest>,
+) -> impl Responder {
+    save_user(auditor, repository, http_req, identity, id, payload, false).await
+}
+
+pub async fn patch_user(
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
+    id: web::Path<i32>,
+    payload: web::Json<UpdateUserRequest>,
+) -> impl Responder {
+    save_user(auditor, repository, http_req, identity, id, payload, true).await
+}
+
 pub async fn delete_user(
-    pool: web::Data<PgPool>,
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
     id: web::Path<i32>,
 ) -> impl Responder {
-    match users::delete_user(pool.get_ref(), id.into_inner()).await {
-        Ok(_) => HttpResponse::Ok()
-        .json(
-            Response::<serde_json::Value> {
-                response_code: SUCCESS.to_string(),
-                response_desc: "OK".to_string(),
-                response_data: None,
+    let id = id.into_inner();
+    let before = audit_snapshot(repository.get_ref(), id).await;
+    let deleted_by = auditor.actor_id(&identity).await;
+    match repository.delete_user(id, deleted_by, if_match(&http_req)).await {
+        Ok(_) => {
+            // The user's tokens and sessions were revoked along with the delete.
+            if let Some(username) = before.as_ref().and_then(|user| user["username"].as_str()) {
+                forget_user(username);
             }
-        ),
-        Err(err) => match err {
-            DeleteItemError::NotFound => HttpResponse::NotFound()
+            let entry = AuditEntry::new("user.delete", OUTCOME_SUCCESS).by_identity(&identity).on("user", id).changes(diff(before.as_ref(), None));
+            auditor.record(&http_req, entry).await;
+            HttpResponse::Ok()
             .json(
                 Response::<serde_json::Value> {
-                    response_code: FAILED_NOT_FOUND.to_string(),
-                    response_desc: "Not Found".to_string(),
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
                     response_data: None,
                 }
-            ),
-            _ => HttpResponse::InternalServerError()
+            )
+        },
+        Err(err) => {
+            auditor.record(&http_req, AuditEntry::new("user.delete", OUTCOME_FAILURE).by_identity(&identity).on("user", id)).await;
+            match err {
+                DeleteUserError::NotFound => HttpResponse::NotFound()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_NOT_FOUND.to_string(),
+                        response_desc: "Not Found".to_string(),
+                        response_data: None,
+                    }
+                ),
+                DeleteUserError::VersionMismatch => precondition_failed(),
+                _ => HttpResponse::InternalServerError()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_INTERNAL.to_string(),
+                        response_desc: "Internal Server Error".to_string(),
+                        response_data: None,
+                    }
+                ),
+            }
+        },
+    }
+}
+
+pub async fn restore_user(
+    auditor: web::Data<Auditor>,
+    repository: web::Data<dyn UserRepository>,
+    http_req: HttpRequest,
+    identity: Identity,
+    id: web::Path<i32>,
+) -> impl Responder {
+    let id = id.into_inner();
+    // The username or email may have been taken by a new account while this one was in the trash.
+    if let Ok(deleted) = repository.get_deleted_user(id).await {
+        let conflict = if repository.get_user_username(&deleted.username).await.is_ok() {
+            Some("Username already exist")
+        } else if repository.get_user_email(&deleted.email).await.is_ok() {
+            Some("Email already exist")
+        } else {
+            None
+        };
+        if let Some(desc) = conflict {
+            return HttpResponse::BadRequest()
             .json(
                 Response::<serde_json::Value> {
-                    response_code: FAILED_INTERNAL.to_string(),
-                    response_desc: "Internal Server Error".to_string(),
+                    response_code: FAILED_EXIST.to_string(),
+                    response_desc: desc.to_string(),
                     response_data: None,
                 }
-            ),
+            );
+        }
+    }
+
+    match repository.restore_user(id, auditor.actor_id(&identity).await).await {
+        Ok(user) => {
+            let changes = diff(None, audit_snapshot(repository.get_ref(), user.id).await.as_ref());
+            let entry = AuditEntry::new("user.restore", OUTCOME_SUCCESS).by_identity(&identity).on("user", user.id).changes(changes);
+            auditor.record(&http_req, entry).await;
+            HttpResponse::Ok()
+            .json(
+                Response {
+                    response_code: SUCCESS.to_string(),
+                    response_desc: "OK".to_string(),
+                    response_data: Some(json!(user)),
+                }
+            )
+        },
+        Err(err) => {
+            auditor.record(&http_req, AuditEntry::new("user.restore", OUTCOME_FAILURE).by_identity(&identity).on("user", id)).await;
+            match err {
+                Error::RowNotFound => HttpResponse::NotFound()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_NOT_FOUND.to_string(),
+                        response_desc: "Not Found".to_string(),
+                        response_data: None,
+                    }
+                ),
+                // Another account took the username or email after the checks above.
+                Error::Database(err) if err.code().as_deref() == Some("23505") => HttpResponse::BadRequest()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_EXIST.to_string(),
+                        response_desc: match err.constraint() {
+                            Some("users_email_key") => "Email already exist",
+                            _ => "Username already exist",
+                        }.to_string(),
+                        response_data: None,
+                    }
+                ),
+                _ => HttpResponse::InternalServerError()
+                .json(
+                    Response::<serde_json::Value> {
+                        response_code: FAILED_INTERNAL.to_string(),
+                        response_desc: err.to_string(),
+                        response_data: None,
+                    }
+                ),
+            }
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::testing;
+    use crate::internal::application::controllers::users::users::*;
+    use crate::internal::application::repositories::audit::memory::MemoryAuditEventRepository;
+    use crate::internal::application::repositories::users::memory::MemoryUserRepository;
+    use crate::internal::application::repositories::users::users::UserRepository;
+    use crate::internal::application::usecases::audit::audit::Auditor;
+    use crate::internal::constant::status::{FAILED_EXIST, FAILED_PRECONDITION};
+    use crate::internal::domain::entities::auth::identity::Identity;
+    use crate::internal::domain::entities::users::users::CreateUserRequest;
+    use actix_http::Request;
+    use actix_web::dev::{Service, ServiceResponse};
+    use actix_web::http::{header, StatusCode};
+    use actix_web::{test, web, App, HttpMessage};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    struct Backends {
+        users: Arc<MemoryUserRepository>,
+        events: Arc<MemoryAuditEventRepository>,
+    }
+
+    fn new_user(username: &str, email: &str, name: Option<&str>) -> CreateUserRequest {
+        CreateUserRequest {
+            username: username.to_string(),
+            email: email.to_string(),
+            password: String::new(),
+            name: name.map(str::to_string),
+            roles: None,
+        }
+    }
+
+    // Memory repositories holding an admin and three users.
+    async fn backends() -> Backends {
+        testing::init();
+        let users = Arc::new(MemoryUserRepository::new(&["admin", "user"]));
+        let admin = CreateUserRequest { roles: Some(vec!["admin".to_string()]), ..new_user("admin", "admin@example.com", None) };
+        users.create_user(admin, None, None).await.unwrap();
+        for (username, email, name) in [("alice", "alice@example.org", Some("Alice")), ("bob", "bob@example.com", None), ("carol", "carol@example.org", Some("Carol"))] {
+            users.create_user(new_user(username, email, name), None, Some(1)).await.unwrap();
+        }
+        Backends { users, events: Arc::new(MemoryAuditEventRepository::default()) }
+    }
+
+    fn admin() -> Identity {
+        Identity {
+            sub: "admin".to_string(),
+            name: "admin".to_string(),
+            roles: vec!["admin".to_string()],
+            permissions: Vec::new(),
+            claims: None,
+            api_key_id: None,
+        }
+    }
+
+    // The user routes without their policies, called as the admin.
+    async fn app(backends: &Backends) -> impl Service<Request, Response = ServiceResponse, Error = actix_web::Error> {
+        let users: Arc<dyn UserRepository> = backends.users.clone();
+        let auditor = Auditor::new(backends.events.clone(), users.clone());
+        test::init_service(
+            App::new()
+                .app_data(web::Data::from(users))
+                .app_data(web::Data::new(auditor))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(admin());
+                    srv.call(req)
+                })
+                .service(
+                    web::scope("/users")
+                        .route("", web::post().to(create_user_controller))
+                        .route("", web::get().to(get_users_controller))
+                        .route("/trash", web::get().to(get_trashed_users_controller))
+                        .route("/{id}", web::get().to(get_user_controller))
+                        .route("/{id}", web::put().to(update_user_controller))
+                        .route("/{id}", web::patch().to(patch_user_controller))
+                        .route("/{id}", web::delete().to(delete_user_controller))
+                        .route("/{id}/restore", web::post().to(restore_user_controller)),
+                ),
+        )
+        .await
+    }
+
+    async fn send(
+        app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>,
+        req: test::TestRequest,
+    ) -> (StatusCode, Value) {
+        let response = test::call_service(app, req.to_request()).await;
+        let status = response.status();
+        let body = test::read_body(response).await;
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    async fn get(app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>, uri: &str) -> (StatusCode, Value) {
+        send(app, test::TestRequest::get().uri(uri)).await
+    }
+
+    // The ids of a listed page.
+    fn ids(body: &Value) -> Vec<i64> {
+        body["responseData"]["users"].as_array().unwrap().iter().map(|user| user["id"].as_i64().unwrap()).collect()
+    }
+
+    #[actix_web::test]
+    async fn filters_and_sorts_the_list() {
+        let backends = backends().await;
+        let app = app(&backends).await;
+
+        let (status, body) = get(&app, "/users?filter[emailDomain]=example.org").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&body), [2, 4]);
+        let (_, body) = get(&app, "/users?filter[username][in]=alice,bob&sort=-username").await;
+        assert_eq!(ids(&body), [3, 2]);
+        let (_, body) = get(&app, "/users?filter[name][null]=true&filter[createdBy]=1").await;
+        assert_eq!(ids(&body), [3]);
+        let (_, body) = get(&app, "/users?sort=desc&fields=username&include=roles").await;
+        assert_eq!(ids(&body), [4, 3, 2, 1]);
+        assert_eq!(body["responseData"]["users"][3], json!({ "id": 1, "username": "admin", "roles": ["admin"] }));
+
+        let (status, _) = get(&app, "/users?filter[password]=secret").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn pages_by_offset_and_cursor() {
+        let backends = backends().await;
+        let app = app(&backends).await;
+
+        let (status, body) = get(&app, "/users?limit=3&page=2").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&body), [4]);
+        assert_eq!(body["responseData"]["total"], 4);
+        assert_eq!(body["responseData"]["totalPage"], 2);
+
+        let (_, first) = get(&app, "/users?limit=3&sort=email&cursor=").await;
+        assert_eq!(ids(&first), [1, 2, 3]);
+        let next = first["responseData"]["nextCursor"].as_str().unwrap();
+        let (_, last) = get(&app, &format!("/users?limit=3&sort=email&cursor={}", next)).await;
+        assert_eq!(ids(&last), [4]);
+        assert_eq!(last["responseData"]["nextCursor"], Value::Null);
+        let prev = last["responseData"]["prevCursor"].as_str().unwrap();
+        let (_, back) = get(&app, &format!("/users?limit=3&sort=email&cursor={}", prev)).await;
+        assert_eq!(ids(&back), [1, 2, 3]);
+    }
+
+    #[actix_web::test]
+    async fn trashes_and_restores() {
+        let backends = backends().await;
+        let app = app(&backends).await;
+
+        let (status, _) = send(&app, test::TestRequest::delete().uri("/users/2")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(backends.users.revoked_at(2).1.is_some());
+        let (status, _) = get(&app, "/users/2").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, body) = get(&app, "/users/trash").await;
+        assert_eq!(ids(&body), [2]);
+        assert_eq!(body["responseData"]["users"][0]["deletedBy"], 1);
+
+        // The username is free while its user is in the trash, and then blocks the restore.
+        backends.users.create_user(new_user("alice", "alice@example.net", None), None, Some(1)).await.unwrap();
+        let (status, body) = send(&app, test::TestRequest::post().uri("/users/2/restore")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["responseDesc"], "Username already exist");
+
+        send(&app, test::TestRequest::delete().uri("/users/5")).await;
+        let (status, body) = send(&app, test::TestRequest::post().uri("/users/2/restore")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["responseData"]["username"], "alice");
+        let (_, body) = get(&app, "/users").await;
+        assert_eq!(ids(&body), [1, 2, 3, 4]);
+
+        let outcomes: Vec<String> = backends.events.recorded().into_iter().map(|(action, outcome, _)| format!("{} {}", action, outcome)).collect();
+        assert_eq!(outcomes, ["user.delete success", "user.delete success", "user.restore success"]);
+    }
+
+    #[actix_web::test]
+    async fn writes_with_a_stale_if_match_fail_their_precondition() {
+        let backends = backends().await;
+        let app = app(&backends).await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/users/2").to_request()).await;
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), "\"1\"");
+
+        let rename = || test::TestRequest::patch().uri("/users/2").set_json(json!({ "name": "Alice Liddell" }));
+        let (status, body) = send(&app, rename().insert_header((header::IF_MATCH, "\"1\""))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["responseData"]["version"], 2);
+        let (status, body) = send(&app, rename().insert_header((header::IF_MATCH, "\"1\""))).await;
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+        assert_eq!(body["responseCode"], FAILED_PRECONDITION);
+        let (status, _) = send(&app, test::TestRequest::delete().uri("/users/2").insert_header((header::IF_MATCH, "\"1\""))).await;
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+        let (_, body) = get(&app, "/users/2").await;
+        assert_eq!(body["responseData"]["name"], "Alice Liddell");
+
+        let outcomes: Vec<String> = backends.events.recorded().into_iter().map(|(action, outcome, _)| format!("{} {}", action, outcome)).collect();
+        assert_eq!(outcomes, ["user.update success", "user.update failure", "user.delete failure"]);
+    }
+
+    #[actix_web::test]
+    async fn refuses_duplicate_usernames_and_emails() {
+        let backends = backends().await;
+        let app = app(&backends).await;
+
+        let create = |username: &str, email: &str| test::TestRequest::post().uri("/users")
+            .set_json(json!({ "username": username, "email": email, "password": "correct-horse-battery" }));
+        let (status, body) = send(&app, create("alice", "alice@example.net")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["responseCode"], FAILED_EXIST);
+        assert_eq!(body["responseDesc"], "Username already exist");
+        let (_, body) = send(&app, create("dave", "bob@example.com")).await;
+        assert_eq!(body["responseDesc"], "Email already exist");
+
+        let (status, body) = send(&app, test::TestRequest::patch().uri("/users/3").set_json(json!({ "username": "carol" }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["responseDesc"], "Username already exist");
+        // Keeping its own username is no conflict.
+        let (status, _) = send(&app, test::TestRequest::patch().uri("/users/3").set_json(json!({ "username": "bob" }))).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn a_role_change_revokes_access() {
+        let backends = backends().await;
+        let app = app(&backends).await;
+
+        let (status, _) = send(&app, test::TestRequest::patch().uri("/users/3").set_json(json!({ "name": "Bob" }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(backends.users.revoked_at(3), (None, None));
+
+        let (status, _) = send(&app, test::TestRequest::patch().uri("/users/3").set_json(json!({ "roles": ["admin"] }))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (tokens_revoked_at, sessions_revoked_at) = backends.users.revoked_at(3);
+        assert!(tokens_revoked_at.is_some() && sessions_revoked_at.is_some());
+        let (_, body) = get(&app, "/users?filter[id]=3&include=roles").await;
+        assert_eq!(body["responseData"]["users"][0]["roles"], json!(["admin"]));
+    }
+
+    #[actix_web::test]
+    async fn a_rename_revokes_tokens() {
+        let backends = backends().await;
+        let app = app(&backends).await;
+
+        let (status, _) = send(&app, test::TestRequest::patch().uri("/users/3").set_json(json!({ "username": "robert" }))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (tokens_revoked_at, sessions_revoked_at) = backends.users.revoked_at(3);
+        assert!(tokens_revoked_at.is_some());
+        assert_eq!(sessions_revoked_at, None);
+    }
+
+    #[actix_web::test]
+    async fn a_delete_revokes_access() {
+        let backends = backends().await;
+        let app = app(&backends).await;
+
+        let (status, _) = send(&app, test::TestRequest::delete().uri("/users/3")).await;
+        assert_eq!(status, StatusCode::OK);
+        let (tokens_revoked_at, sessions_revoked_at) = backends.users.revoked_at(3);
+        assert!(tokens_revoked_at.is_some() && sessions_revoked_at.is_some());
+    }
+}
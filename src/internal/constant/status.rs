@@ -5,3 +5,4 @@ pub const FAILED_NOT_FOUND: &str = "02";
 pub const FAILED_REQUIRED: &str = "03";
 pub const FAILED_AUTHORIZED: &str = "04";
 pub const FAILED_EXIST: &str = "05";
+pub const FAILED_PRECONDITION: &str = "06";
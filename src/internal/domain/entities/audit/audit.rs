@@ -0,0 +1,81 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuditEvent {
+    pub id: i64,
+    #[serde(rename = "occurredAt")]
+    pub occurred_at: NaiveDateTime,
+    #[serde(rename = "actorId")]
+    pub actor_id: Option<i32>,
+    pub actor: Option<String>,
+    #[serde(rename = "apiKeyId")]
+    pub api_key_id: Option<i32>,
+    pub action: String,
+    #[serde(rename = "targetType")]
+    pub target_type: Option<String>,
+    #[serde(rename = "targetId")]
+    pub target_id: Option<String>,
+    pub outcome: String,
+    pub ip: Option<String>,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    #[serde(rename = "requestId")]
+    pub request_id: Option<String>,
+    pub changes: Option<serde_json::Value>,
+}
+
+#[derive(Debug)]
+pub struct NewAuditEvent {
+    pub actor_id: Option<i32>,
+    pub actor: Option<String>,
+    pub api_key_id: Option<i32>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub outcome: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub changes: Option<serde_json::Value>,
+}
+
+// `from` and `to` accept RFC 3339 timestamps or plain dates.
+#[derive(Debug, Deserialize)]
+pub struct AuditEventsQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    #[serde(rename = "actorId")]
+    pub actor_id: Option<i32>,
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    #[serde(rename = "targetType")]
+    pub target_type: Option<String>,
+    #[serde(rename = "targetId")]
+    pub target_id: Option<String>,
+    pub outcome: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct AuditEventFilter {
+    pub actor_id: Option<i32>,
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub outcome: Option<String>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct ListAuditEvents {
+    pub page: i64,
+    pub limit: i64,
+    pub total: i64,
+    #[serde(rename = "totalPage")]
+    pub total_page: i64,
+    pub events: Vec<AuditEvent>,
+}
@@ -0,0 +1,39 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: i32,
+    #[serde(skip_serializing)]
+    pub user_id: i32,
+    pub name: String,
+    pub prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scopes: Vec<String>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<NaiveDateTime>,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<NaiveDateTime>,
+    #[serde(rename = "createdAt")]
+    pub created_at: NaiveDateTime,
+    #[serde(rename = "revokedAt")]
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    // Lifetime such as "90d", the key does not expire when omitted.
+    #[serde(rename = "expiresIn")]
+    pub expires_in: Option<String>,
+}
+
+// The full key is only returned once, at creation.
+#[derive(Debug, Serialize)]
+pub struct CreateApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    pub key: String,
+}
@@ -0,0 +1,45 @@
+use crate::internal::domain::entities::auth::login::Claims;
+
+// The caller resolved by the auth policy middleware, extracted by handlers as `Identity`
+// (or `Option<Identity>` on public routes).
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct Identity {
+    pub sub: String,
+    pub name: String,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+    // Present when the caller authenticated with an access token.
+    pub claims: Option<Claims>,
+    // Present when the caller authenticated with an API key.
+    pub api_key_id: Option<i32>,
+}
+
+impl Identity {
+    pub fn from_claims(claims: Claims) -> Self {
+        Identity {
+            sub: claims.sub.clone(),
+            name: claims.name.clone(),
+            roles: claims.roles.clone(),
+            permissions: claims.permissions.clone(),
+            claims: Some(claims),
+            api_key_id: None,
+        }
+    }
+
+    // API keys act for their owner, limited to the key scopes.
+    pub fn from_api_key(api_key_id: i32, username: String, scopes: Vec<String>) -> Self {
+        Identity {
+            sub: username.clone(),
+            name: username,
+            roles: Vec::new(),
+            permissions: scopes,
+            claims: None,
+            api_key_id: Some(api_key_id),
+        }
+    }
+
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+}
@@ -0,0 +1,21 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LoginThrottle {
+    // "account" or "ip".
+    pub kind: String,
+    pub key: String,
+    pub failures: i32,
+    #[serde(rename = "lastFailureAt")]
+    pub last_failure_at: NaiveDateTime,
+    #[serde(rename = "lockedUntil")]
+    pub locked_until: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LockoutsQuery {
+    pub kind: Option<String>,
+    // Only list entries that are locked right now.
+    pub locked: Option<bool>,
+}
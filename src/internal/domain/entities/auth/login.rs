@@ -1,15 +1,29 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub name: String,
+    pub iat: usize,
+    // iat in microseconds, as precise as the cutoffs it is checked against. Tokens
+    // issued before it was added only have iat.
+    #[serde(default)]
+    pub iat_us: Option<i64>,
     pub exp: usize,
+    pub jti: String,
+    // Refresh session family the token was issued with.
+    pub sid: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Token {
     pub token: String,
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
 }
 
 #[derive(Debug, Deserialize)]
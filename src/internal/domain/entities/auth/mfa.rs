@@ -0,0 +1,45 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct UserMfa {
+    pub user_id: i32,
+    pub secret: String,
+    pub enabled_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MfaEnrollResponse {
+    pub secret: String,
+    #[serde(rename = "otpauthUri")]
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MfaRecoveryCodesResponse {
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MfaChallengeResponse {
+    #[serde(rename = "mfaRequired")]
+    pub mfa_required: bool,
+    #[serde(rename = "challengeToken")]
+    pub challenge_token: String,
+    #[serde(rename = "expiresIn")]
+    pub expires_in: i64,
+}
+
+// `code` is either a TOTP code or one of the recovery codes.
+#[derive(Debug, Deserialize)]
+pub struct MfaCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MfaVerifyRequest {
+    #[serde(rename = "challengeToken")]
+    pub challenge_token: String,
+    pub code: String,
+}
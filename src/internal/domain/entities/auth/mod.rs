@@ -1 +1,10 @@
-pub mod login;
\ No newline at end of file
+pub mod api_key;
+pub mod identity;
+pub mod lockout;
+pub mod login;
+pub mod mfa;
+pub mod oidc;
+pub mod password;
+pub mod profile;
+pub mod registration;
+pub mod session;
\ No newline at end of file
@@ -0,0 +1,18 @@
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct OidcState {
+    pub nonce: String,
+    pub code_verifier: String,
+    pub expires_at: NaiveDateTime,
+}
+
+// Query of the redirect back from the provider, either a code or an error.
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
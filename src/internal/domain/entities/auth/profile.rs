@@ -0,0 +1,32 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub struct ProfileResponse {
+    pub id: i32,
+    pub username: String,
+    pub email: String,
+    pub name: Option<String>,
+    #[serde(rename = "emailVerifiedAt")]
+    pub email_verified_at: Option<NaiveDateTime>,
+    // A new address waiting for its verification link to be used.
+    #[serde(rename = "pendingEmail")]
+    pub pending_email: Option<String>,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+// The username is the token subject and cannot be changed here. An empty name clears it.
+#[derive(Debug, Deserialize)]
+pub struct UpdateProfileRequest {
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    #[serde(rename = "currentPassword")]
+    pub current_password: String,
+    #[serde(rename = "newPassword")]
+    pub new_password: String,
+}
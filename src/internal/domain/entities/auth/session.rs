@@ -0,0 +1,34 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+// Represents a refresh token session stored in the database.
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Session {
+    pub id: i32,
+    pub user_id: i32,
+    pub family_id: String,
+    pub refresh_token_hash: String,
+    pub device: Option<String>,
+    pub ip: Option<String>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub rotated_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+// Model for creating a new session.
+#[derive(Debug)]
+pub struct CreateSession {
+    pub user_id: i32,
+    pub family_id: String,
+    pub refresh_token_hash: String,
+    pub device: Option<String>,
+    pub ip: Option<String>,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
+}
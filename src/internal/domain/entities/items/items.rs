@@ -1,5 +1,5 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize)]
 pub struct ItemsQuery {
@@ -7,7 +7,18 @@ pub struct ItemsQuery {
     pub limit: Option<i64>,
     pub sort: Option<String>,
     pub field: Option<String>,
-    pub filter: Option<HashMap<String, String>>
+    // Keyset paging instead of pages, empty for the first page.
+    pub cursor: Option<String>,
+    // none (default), exact or estimate, only used with a cursor.
+    pub count: Option<String>,
+    // Comma separated fields to return instead of the defaults.
+    pub fields: Option<String>,
+    // Comma separated relations to embed.
+    pub include: Option<String>,
+    // Full-text search, a word ending in * matches as a prefix.
+    pub q: Option<String>,
+    // Adds a `headline` with the matches marked to every searched item.
+    pub highlight: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -17,7 +28,19 @@ pub struct Items {
     pub total: i64,
     #[serde(rename = "totalPage")]
     pub total_page: i64,
-    pub items: Vec<Item>
+    pub items: Vec<serde_json::Value>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ItemsPage {
+    pub limit: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+    #[serde(rename = "prevCursor")]
+    pub prev_cursor: Option<String>,
+    pub items: Vec<serde_json::Value>
 }
 
 // Represents an item stored in the database.
@@ -26,6 +49,16 @@ pub struct Item {
     pub id: i32,
     pub name: String,
     pub description: Option<String>,
+    // Bumped on every write, the ETag of the item.
+    pub version: i32,
+    #[serde(rename = "createdAt")]
+    pub created_at: NaiveDateTime,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: NaiveDateTime,
+    #[serde(rename = "createdBy")]
+    pub created_by: Option<i32>,
+    #[serde(rename = "updatedBy")]
+    pub updated_by: Option<i32>,
 }
 
 // Model for creating a new item.
@@ -1,4 +1,5 @@
 pub mod items;
 pub mod response;
+pub mod audit;
 pub mod auth;
 pub mod users;
\ No newline at end of file
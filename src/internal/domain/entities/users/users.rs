@@ -1,5 +1,5 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct User {
@@ -7,6 +7,9 @@ pub struct User {
     pub username: String,
     pub email: String,
     pub password: String,
+    pub name: Option<String>,
+    pub email_verified_at: Option<NaiveDateTime>,
+    pub pending_email: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -15,7 +18,14 @@ pub struct UsersQuery {
     pub limit: Option<i64>,
     pub sort: Option<String>,
     pub field: Option<String>,
-    pub filter: Option<HashMap<String, String>>
+    // Keyset paging instead of pages, empty for the first page.
+    pub cursor: Option<String>,
+    // none (default), exact or estimate, only used with a cursor.
+    pub count: Option<String>,
+    // Comma separated fields to return instead of the defaults.
+    pub fields: Option<String>,
+    // Comma separated relations to embed.
+    pub include: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -23,6 +33,16 @@ pub struct DetailUserResponse {
     pub id: i32,
     pub username: String,
     pub email: String,
+    pub name: Option<String>,
+    pub version: i32,
+    #[serde(rename = "createdAt")]
+    pub created_at: NaiveDateTime,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: NaiveDateTime,
+    #[serde(rename = "createdBy")]
+    pub created_by: Option<i32>,
+    #[serde(rename = "updatedBy")]
+    pub updated_by: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -32,7 +52,19 @@ pub struct ListUser {
     pub total: i64,
     #[serde(rename = "totalPage")]
     pub total_page: i64,
-    pub users: Vec<DetailUserResponse>
+    pub users: Vec<serde_json::Value>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UsersPage {
+    pub limit: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+    #[serde(rename = "prevCursor")]
+    pub prev_cursor: Option<String>,
+    pub users: Vec<serde_json::Value>
 }
 
 #[derive(Debug, Deserialize)]
@@ -40,6 +72,8 @@ pub struct CreateUserRequest {
     pub username: String,
     pub email: String,
     pub password: String,
+    pub name: Option<String>,
+    pub roles: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -47,6 +81,8 @@ pub struct CreateUserResponse {
     pub id: i32,
     pub username: String,
     pub email: String,
+    pub name: Option<String>,
+    pub version: i32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -54,6 +90,8 @@ pub struct UpdateUserRequest {
     pub username: Option<String>,
     pub email: Option<String>,
     pub password: Option<String>,
+    pub name: Option<String>,
+    pub roles: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -61,4 +99,6 @@ pub struct UpdateUserResponse {
     pub id: i32,
     pub username: String,
     pub email: String,
+    pub name: Option<String>,
+    pub version: i32,
 }
\ No newline at end of file
@@ -0,0 +1,73 @@
+use sqlx::error::DatabaseError;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+
+// An error of the in-memory repositories for a write Postgres would have refused,
+// with the same SQLSTATE, constraint name and message.
+#[derive(Debug)]
+pub struct MemoryDatabaseError {
+    code: &'static str,
+    constraint: Option<String>,
+    message: String,
+}
+
+impl MemoryDatabaseError {
+    pub fn unique_violation(constraint: &str) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(MemoryDatabaseError {
+            code: "23505",
+            constraint: Some(constraint.to_string()),
+            message: format!("duplicate key value violates unique constraint \"{}\"", constraint),
+        }))
+    }
+
+    pub fn foreign_key_violation(table: &str, constraint: &str) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(MemoryDatabaseError {
+            code: "23503",
+            constraint: Some(constraint.to_string()),
+            message: format!("insert or update on table \"{}\" violates foreign key constraint \"{}\"", table, constraint),
+        }))
+    }
+
+    pub fn value_too_long(max_len: usize) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(MemoryDatabaseError {
+            code: "22001",
+            constraint: None,
+            message: format!("value too long for type character varying({})", max_len),
+        }))
+    }
+}
+
+impl fmt::Display for MemoryDatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for MemoryDatabaseError {}
+
+impl DatabaseError for MemoryDatabaseError {
+    fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn code(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.code))
+    }
+
+    fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn constraint(&self) -> Option<&str> {
+        self.constraint.as_deref()
+    }
+}
@@ -0,0 +1,217 @@
+use crate::config::settings::CONFIG;
+use crate::middlewares::logger::init_logger;
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::{Pool, Postgres};
+use slog::info;
+use std::collections::HashMap;
+
+// The scripts under migrations/, compiled into the binary. Applied versions and their
+// checksums are kept in _sqlx_migrations, and running or undoing them holds a Postgres
+// advisory lock so instances starting together apply each script once.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    // Applied, but the embedded script no longer has the checksum it was applied with.
+    Changed,
+    // Failed halfway and left marked as unsuccessful.
+    Dirty,
+    // Applied, but not embedded in this binary.
+    Missing,
+}
+
+impl MigrationState {
+    pub fn name(self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Changed => "changed",
+            MigrationState::Dirty => "dirty",
+            MigrationState::Missing => "missing",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+// Compares the embedded migrations with the history table, oldest first.
+// Only reads, so the history table is not created when it is missing.
+pub async fn migration_status(pool: &Pool<Postgres>) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(&mut conn)
+        .await?;
+    let (mut applied, dirty) = if exists {
+        let applied: HashMap<_, _> = conn.list_applied_migrations().await?
+            .into_iter()
+            .map(|migration| (migration.version, migration.checksum))
+            .collect();
+        (applied, conn.dirty_version().await?)
+    } else {
+        (HashMap::new(), None)
+    };
+
+    let mut status: Vec<MigrationStatus> = MIGRATOR.iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| {
+            let state = match applied.remove(&migration.version) {
+                _ if dirty == Some(migration.version) => MigrationState::Dirty,
+                Some(checksum) if checksum != migration.checksum => MigrationState::Changed,
+                Some(_) => MigrationState::Applied,
+                None => MigrationState::Pending,
+            };
+            MigrationStatus { version: migration.version, description: migration.description.to_string(), state }
+        })
+        .collect();
+    status.extend(applied.into_keys().map(|version| MigrationStatus {
+        version,
+        description: String::new(),
+        state: if dirty == Some(version) { MigrationState::Dirty } else { MigrationState::Missing },
+    }));
+    status.sort_by_key(|migration| migration.version);
+    Ok(status)
+}
+
+pub async fn migrate_up(pool: &Pool<Postgres>) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+// Reverts the latest `steps` applied migrations, newest first.
+pub async fn migrate_down(pool: &Pool<Postgres>, steps: usize) -> Result<(), MigrateError> {
+    let mut applied: Vec<i64> = migration_status(pool).await?
+        .into_iter()
+        .filter(|migration| migration.state != MigrationState::Pending)
+        .map(|migration| migration.version)
+        .collect();
+    applied.reverse();
+    let target = applied.get(steps).copied().unwrap_or(0);
+    MIGRATOR.undo(pool, target).await
+}
+
+// MIGRATION_MODE=run applies whatever is pending before the server starts,
+// MIGRATION_MODE=verify only refuses to start on a database that is not up to date.
+pub async fn run_startup_migrations(pool: &Pool<Postgres>) {
+    let (_, logger_terminal) = init_logger();
+    match CONFIG.migration_mode.as_str() {
+        "run" => {
+            migrate_up(pool).await.expect("Unable to run database migrations");
+            info!(logger_terminal, "Database migrations applied successfully");
+        }
+        "verify" => {
+            let status = migration_status(pool).await.expect("Unable to read database migrations");
+            let outdated: Vec<String> = status.iter()
+                .filter(|migration| migration.state != MigrationState::Applied)
+                .map(|migration| format!("{} ({})", migration.version, migration.state.name()))
+                .collect();
+            if !outdated.is_empty() {
+                panic!("Database migrations are not up to date: {}", outdated.join(", "));
+            }
+            info!(logger_terminal, "Database migrations verified successfully");
+        }
+        other => panic!("Invalid MIGRATION_MODE: {}, expected run or verify", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::pkg::database::sql::scratch::ScratchDatabase;
+
+    // The SHA-384 checksums _sqlx_migrations recorded for every script shipped before
+    // migrations became reversible pairs. An edited script shows up as `changed` on
+    // every database that ran it.
+    const SHIPPED_CHECKSUMS: [(i64, &str); 17] = [
+        (20250326071808, "01aa04aa6b3e5de0a1732988ec28d355321c07eda56ce2fd87060d9c84a88b2b532919e033e17d18218e7ccb06d55195"),
+        (20250326085525, "89b95d3bd1a65f7c15427772a28b50d1c8858b281dae9cca7ff3eec00f7150a4fe87f4392b5f36014388c29a04026279"),
+        (20261018080000, "c5722bc73e8243cee295a22cc1d283bc64a7b5207a4ea6513bf33d7c9ea16f2bfa34afa6f758eb479fb35ae43d0a9b9b"),
+        (20261018081000, "676019a32bae81bc7dc7883502ec131a70e958e350bd34aca42cb5e7299637457518bc2e40cfb656c3add2bbebbe780a"),
+        (20261018082000, "531502ffb42d3c25f1f10d0dc6b74a9243a9581fd8f38524e9f4381b95eba80fb8bb8d0c44aed3a858454d3836e0e63c"),
+        (20261018083000, "00a618f01fc471021545db2c31e8570b1e32f81d5da23a24919886b6e6813e2d12e2d5cf118f6c6bc6ba526053842ed4"),
+        (20261018084000, "1ae8e65058cd8e15d780532cfa7726c1fe1a635b3002ed48c76bd0ea63b9e102e9867ff96be15b07b1c2a0c94f1c0880"),
+        (20261018085000, "f7f9f3bdcd30fe47f30bf6f18c874be4942bbb649c9ced9057533580f4ab4f0ccbfebcbfe667da252bb2578fc861e574"),
+        (20261018086000, "949e4e5a6d2986930b5726e1939d22a70c886ff2b639795862f530e388ffe3585fb87a45f6d3450fee1c060997b62f80"),
+        (20261018087000, "e7b49bbed3f34664a073513fa42ab5a426ed2475699bd830569bf3671c016e5dad1e392c6ed7d890d224bab1e37b0be9"),
+        (20261018088000, "b300f7f4805eb6601142a2124e953bfcfcf1031168ad0fe6e6099869782a2790ff7c07b48bffbd97d2003a0859491bfc"),
+        (20261018089000, "1ceeab9ed6d039072b15ed94dfeda878e6a788159d4361bdabc31447547a0dab4f94b8743adc3866277be0903b8a2b23"),
+        (20261018090000, "e98c8d9a1ff59e74e9332a801b16ce8f83e182139119ca1abb1664f8e0338ed60caf417417738a41e728196ab2ab1d98"),
+        (20261018091000, "53e8d4b9dc9b615c6147d891a3652a46b9a9a7688abc74ad7a7334cab97c8a03e44cb6af4b74b44f58e35d6cb3018837"),
+        (20261018092000, "f9cbf1a6c7df83a2bbda32df761dba971f7bc4a9951767407f04b4fe2835ecdde66567a8160acef61a0fea340df86840"),
+        (20261018093000, "50dc2893b1e0e00c9f3802e258e86f85a9e6c9cec4f1554f28e8fc3c7a41f569dccf93b51ef34c1c3309514eab391cc5"),
+        (20261018094000, "e0ef9d4755d19835fe012888063fd03ecf322431977ad8d139739055d140032978de91c1a1dcb9f97bffacc0c168ee58"),
+    ];
+
+    // The migrations of the baseline release.
+    const BASELINE_VERSIONS: [i64; 2] = [20250326071808, 20250326085525];
+
+    #[test]
+    fn shipped_scripts_keep_their_checksums() {
+        for (version, checksum) in SHIPPED_CHECKSUMS {
+            let migration = MIGRATOR.iter()
+                .find(|migration| migration.version == version && !migration.migration_type.is_down_migration())
+                .unwrap_or_else(|| panic!("Migration {} is no longer embedded", version));
+            assert_eq!(hex::encode(&*migration.checksum), checksum, "Migration {} was edited", version);
+        }
+    }
+
+    #[test]
+    fn every_migration_can_be_reverted() {
+        for migration in MIGRATOR.iter().filter(|migration| !migration.migration_type.is_down_migration()) {
+            assert!(
+                MIGRATOR.iter().any(|down| down.version == migration.version && down.migration_type.is_down_migration()),
+                "Migration {} has no down script", migration.version,
+            );
+        }
+    }
+
+    // A database set up with the baseline's scripts, as `sqlx migrate run` left it.
+    async fn baseline_database() -> ScratchDatabase {
+        let database = ScratchDatabase::create("migrate").await;
+        let mut conn = database.pool.acquire().await.unwrap();
+        conn.ensure_migrations_table().await.unwrap();
+        for version in BASELINE_VERSIONS {
+            let migration = MIGRATOR.iter()
+                .find(|migration| migration.version == version && !migration.migration_type.is_down_migration())
+                .unwrap();
+            let (_, checksum) = SHIPPED_CHECKSUMS.iter().find(|(shipped, _)| *shipped == version).unwrap();
+            sqlx::query(&migration.sql).execute(&mut conn).await.unwrap();
+            sqlx::query("INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) VALUES ($1, $2, TRUE, $3, 0)")
+                .bind(version)
+                .bind(&*migration.description)
+                .bind(hex::decode(checksum).unwrap())
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+        drop(conn);
+        database
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn status_of_a_baseline_database() {
+        let database = baseline_database().await;
+
+        let status = migration_status(&database.pool).await.unwrap();
+        for migration in &status {
+            let expected = if BASELINE_VERSIONS.contains(&migration.version) { MigrationState::Applied } else { MigrationState::Pending };
+            assert_eq!(migration.state, expected, "Migration {}", migration.version);
+        }
+
+        migrate_up(&database.pool).await.unwrap();
+        let status = migration_status(&database.pool).await.unwrap();
+        assert!(status.iter().all(|migration| migration.state == MigrationState::Applied), "{:?}", status);
+
+        migrate_down(&database.pool, status.len()).await.unwrap();
+        let status = migration_status(&database.pool).await.unwrap();
+        assert!(status.iter().all(|migration| migration.state == MigrationState::Pending), "{:?}", status);
+
+        database.drop().await;
+    }
+}
@@ -1 +1,6 @@
+#[cfg(test)]
+pub mod memory;
+pub mod migrate;
 pub mod postgres;
+#[cfg(test)]
+pub mod scratch;
\ No newline at end of file
@@ -0,0 +1,35 @@
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::sync::atomic::{AtomicU32, Ordering};
+use url::Url;
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+// A throwaway database on the server of TEST_DATABASE_URL, for tests that need
+// Postgres itself. Those tests are #[ignore]d, run them with
+// `TEST_DATABASE_URL=postgres://... cargo test -- --ignored`.
+pub struct ScratchDatabase {
+    pub pool: PgPool,
+    admin: PgPool,
+    name: String,
+}
+
+impl ScratchDatabase {
+    pub async fn create(label: &str) -> ScratchDatabase {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set for database tests");
+        let admin = PgPoolOptions::new().max_connections(1).connect(&url).await.expect("Unable to connect to TEST_DATABASE_URL");
+        let name = format!("scratch_{}_{}_{}", label, std::process::id(), NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        sqlx::query(&format!("DROP DATABASE IF EXISTS {}", name)).execute(&admin).await.expect("Unable to drop the scratch database");
+        sqlx::query(&format!("CREATE DATABASE {}", name)).execute(&admin).await.expect("Unable to create the scratch database");
+
+        let mut url = Url::parse(&url).expect("Invalid TEST_DATABASE_URL");
+        url.set_path(&name);
+        let pool = PgPoolOptions::new().max_connections(5).connect(url.as_str()).await.expect("Unable to connect to the scratch database");
+        ScratchDatabase { pool, admin, name }
+    }
+
+    pub async fn drop(self) {
+        self.pool.close().await;
+        // Closed connections may not have ended on the server yet.
+        sqlx::query(&format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", self.name)).execute(&self.admin).await.expect("Unable to drop the scratch database");
+    }
+}
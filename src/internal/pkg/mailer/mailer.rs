@@ -0,0 +1,28 @@
+use crate::config::settings::CONFIG;
+use crate::internal::pkg::mailer::outbox::OutboxMailer;
+use crate::internal::pkg::mailer::smtp::SmtpMailer;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+// Outgoing mail delivery, shared with handlers as `web::Data<dyn Mailer>`.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), String>;
+}
+
+// Build the mailer selected by MAILER ("smtp" or "outbox").
+pub fn create_mailer() -> Arc<dyn Mailer> {
+    match CONFIG.mailer.as_str() {
+        "smtp" => Arc::new(SmtpMailer::from_config().expect("Invalid SMTP configuration")),
+        "outbox" => Arc::new(OutboxMailer::new(&CONFIG.mail_outbox_dir)),
+        other => panic!("Unsupported MAILER: {}", other),
+    }
+}
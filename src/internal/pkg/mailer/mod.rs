@@ -0,0 +1,3 @@
+pub mod mailer;
+pub mod outbox;
+pub mod smtp;
\ No newline at end of file
@@ -0,0 +1,33 @@
+use crate::internal::pkg::mailer::mailer::{Email, Mailer};
+use crate::internal::pkg::utils::crypto::generate_token;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::json;
+use std::path::PathBuf;
+
+// Writes every email as a JSON file instead of delivering it, for local development and tests.
+pub struct OutboxMailer {
+    dir: PathBuf,
+}
+
+impl OutboxMailer {
+    pub fn new(dir: &str) -> Self {
+        OutboxMailer { dir: PathBuf::from(dir) }
+    }
+}
+
+#[async_trait]
+impl Mailer for OutboxMailer {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        tokio::fs::create_dir_all(&self.dir).await.map_err(|err| err.to_string())?;
+        let now = Utc::now();
+        let path = self.dir.join(format!("{}-{}.json", now.format("%Y%m%d%H%M%S%3f"), generate_token(6)));
+        let content = json!({
+            "to": email.to,
+            "subject": email.subject,
+            "body": email.body,
+            "sentAt": now.to_rfc3339(),
+        });
+        tokio::fs::write(&path, content.to_string()).await.map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,47 @@
+use crate::config::settings::CONFIG;
+use crate::internal::pkg::mailer::mailer::{Email, Mailer};
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn from_config() -> Result<Self, String> {
+        let host = CONFIG.smtp_host.as_str();
+        let mut builder = match CONFIG.smtp_tls.as_str() {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host).map_err(|err| err.to_string())?,
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(|err| err.to_string())?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            other => return Err(format!("Unsupported SMTP_TLS: {}", other)),
+        };
+        if let Some(port) = CONFIG.smtp_port.as_deref() {
+            builder = builder.port(port.parse().map_err(|_| format!("Invalid SMTP_PORT: {}", port))?);
+        }
+        if let (Some(username), Some(password)) = (CONFIG.smtp_username.clone(), CONFIG.smtp_password.clone()) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        let from = CONFIG.mail_from.parse().map_err(|_| format!("Invalid MAIL_FROM: {}", CONFIG.mail_from))?;
+        Ok(SmtpMailer { transport: builder.build(), from })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        let to: Mailbox = email.to.parse().map_err(|_| format!("Invalid recipient: {}", email.to))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body)
+            .map_err(|err| err.to_string())?;
+        self.transport.send(message).await.map_err(|err| err.to_string())?;
+        Ok(())
+    }
+}
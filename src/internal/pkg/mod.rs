@@ -1,2 +1,6 @@
 pub mod database;
-pub mod utils;
+pub mod mailer;
+pub mod oidc;
+pub mod password;
+pub mod query;
+pub mod utils;
\ No newline at end of file
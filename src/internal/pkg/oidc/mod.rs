@@ -0,0 +1,3 @@
+#[cfg(test)]
+pub mod mock;
+pub mod oidc;
\ No newline at end of file
@@ -0,0 +1,358 @@
+use crate::config::settings::CONFIG;
+use crate::internal::pkg::utils::cache::TtlCache;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use jsonwebtoken::jwk::JwkSet;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use url::Url;
+
+// Configured from the OIDC_* settings, None when OIDC login is disabled.
+pub static OIDC_CLIENT: Lazy<Option<OidcClient>> = Lazy::new(|| OidcClient::from_config().expect("Invalid OIDC configuration"));
+
+// Discovery documents and signing keys are refetched after this long, or sooner
+// when an ID token names a key we have not seen.
+const METADATA_TTL: Duration = Duration::from_secs(600);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+const ALLOWED_ALGORITHMS: [Algorithm; 7] = [
+    Algorithm::RS256, Algorithm::RS384, Algorithm::RS512,
+    Algorithm::PS256, Algorithm::ES256, Algorithm::ES384, Algorithm::EdDSA,
+];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    // Some providers send "true"/"false" strings, anything but a true bool counts as unverified.
+    #[serde(default)]
+    pub email_verified: serde_json::Value,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+}
+
+impl IdTokenClaims {
+    pub fn email_verified(&self) -> bool {
+        self.email_verified == serde_json::Value::Bool(true)
+    }
+}
+
+pub struct OidcClient {
+    http: reqwest::Client,
+    pub issuer: String,
+    client_id: String,
+    client_secret: Option<String>,
+    redirect_uri: String,
+    scopes: String,
+    allow_insecure_http: bool,
+    metadata: TtlCache<String, ProviderMetadata>,
+    jwks: TtlCache<String, JwkSet>,
+}
+
+// PKCE S256 challenge for a code verifier (RFC 7636).
+pub fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+impl OidcClient {
+    fn from_config() -> Result<Option<Self>, String> {
+        let issuer = match CONFIG.oidc_issuer.as_deref() {
+            Some(issuer) if !issuer.is_empty() => issuer.to_string(),
+            _ => return Ok(None),
+        };
+        if CONFIG.oidc_client_id.is_empty() {
+            return Err("OIDC_CLIENT_ID must be set when OIDC_ISSUER is".to_string());
+        }
+        OidcClient::new(
+            issuer,
+            CONFIG.oidc_client_id.clone(),
+            CONFIG.oidc_client_secret.clone(),
+            CONFIG.oidc_redirect_uri.clone(),
+            CONFIG.oidc_scopes.clone(),
+            CONFIG.oidc_allow_insecure_http,
+        )
+        .map(Some)
+    }
+
+    // `allow_insecure_http` accepts plain http URLs, for a provider running locally.
+    pub fn new(
+        issuer: String,
+        client_id: String,
+        client_secret: Option<String>,
+        redirect_uri: String,
+        scopes: String,
+        allow_insecure_http: bool,
+    ) -> Result<Self, String> {
+        check_url(&issuer, allow_insecure_http)?;
+        Url::parse(&redirect_uri).map_err(|err| format!("Invalid OIDC_REDIRECT_URI: {}", err))?;
+
+        let http = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|err| err.to_string())?;
+
+        Ok(OidcClient {
+            http,
+            issuer,
+            client_id,
+            client_secret,
+            redirect_uri,
+            scopes,
+            allow_insecure_http,
+            metadata: TtlCache::new(METADATA_TTL, 1),
+            jwks: TtlCache::new(METADATA_TTL, 4),
+        })
+    }
+
+    async fn metadata(&self) -> Result<ProviderMetadata, String> {
+        if let Some(metadata) = self.metadata.get(&self.issuer) {
+            return Ok(metadata);
+        }
+        let url = format!("{}/.well-known/openid-configuration", self.issuer);
+        let metadata: ProviderMetadata = self.get_json(&url).await?;
+        // The discovery document must be about the issuer we asked for (OIDC Discovery 4.3).
+        if metadata.issuer.trim_end_matches('/') != self.issuer {
+            return Err(format!("Discovery document issuer mismatch: {}", metadata.issuer));
+        }
+        for endpoint in [&metadata.authorization_endpoint, &metadata.token_endpoint, &metadata.jwks_uri] {
+            check_url(endpoint, self.allow_insecure_http)?;
+        }
+        self.metadata.insert(self.issuer.clone(), metadata.clone());
+        Ok(metadata)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, String> {
+        let response = self.http.get(url).send().await.map_err(|err| format!("{}: {}", url, err))?;
+        if !response.status().is_success() {
+            return Err(format!("{}: HTTP {}", url, response.status()));
+        }
+        response.json::<T>().await.map_err(|err| format!("{}: {}", url, err))
+    }
+
+    pub async fn authorization_url(&self, state: &str, nonce: &str, code_verifier: &str) -> Result<String, String> {
+        let metadata = self.metadata().await?;
+        let mut url = Url::parse(&metadata.authorization_endpoint).map_err(|err| err.to_string())?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &self.redirect_uri)
+            .append_pair("scope", &self.scopes)
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", &code_challenge(code_verifier))
+            .append_pair("code_challenge_method", "S256");
+        Ok(url.into())
+    }
+
+    // Redeem the authorization code and return the raw ID token.
+    pub async fn exchange_code(&self, code: &str, code_verifier: &str) -> Result<String, String> {
+        let metadata = self.metadata().await?;
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("client_id", self.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        let mut request = self.http.post(&metadata.token_endpoint).form(&form);
+        if let Some(client_secret) = &self.client_secret {
+            request = request.basic_auth(&self.client_id, Some(client_secret));
+        }
+
+        let response = request.send().await.map_err(|err| format!("Token request failed: {}", err))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Token request failed: HTTP {} {}", status, body));
+        }
+        let token: TokenResponse = response.json().await.map_err(|err| format!("Invalid token response: {}", err))?;
+        token.id_token.ok_or_else(|| "Token response has no id_token".to_string())
+    }
+
+    async fn signing_keys(&self, refresh: bool) -> Result<JwkSet, String> {
+        let metadata = self.metadata().await?;
+        if !refresh && let Some(jwks) = self.jwks.get(&metadata.jwks_uri) {
+            return Ok(jwks);
+        }
+        let jwks: JwkSet = self.get_json(&metadata.jwks_uri).await?;
+        self.jwks.insert(metadata.jwks_uri.clone(), jwks.clone());
+        Ok(jwks)
+    }
+
+    // Check signature, issuer, audience, expiry and nonce of an ID token.
+    pub async fn verify_id_token(&self, id_token: &str, nonce: &str) -> Result<IdTokenClaims, String> {
+        let header = decode_header(id_token).map_err(|err| format!("Invalid ID token: {}", err))?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(format!("Unsupported ID token algorithm: {:?}", header.alg));
+        }
+
+        let kid = header.kid.as_deref();
+        let find_key = |jwks: JwkSet| match kid {
+            Some(kid) => jwks.find(kid).cloned(),
+            None if jwks.keys.len() == 1 => jwks.keys.into_iter().next(),
+            None => None,
+        };
+        let jwk = match find_key(self.signing_keys(false).await?) {
+            Some(jwk) => jwk,
+            // The provider may have rotated its keys since we cached them.
+            None => find_key(self.signing_keys(true).await?)
+                .ok_or_else(|| "No matching signing key for the ID token".to_string())?,
+        };
+        let decoding_key = DecodingKey::from_jwk(&jwk).map_err(|err| format!("Invalid signing key: {}", err))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[self.issuer.as_str()]);
+        validation.set_audience(&[self.client_id.as_str()]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = decode::<IdTokenClaims>(id_token, &decoding_key, &validation)
+            .map_err(|err| format!("Invalid ID token: {}", err))?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err("ID token nonce mismatch".to_string());
+        }
+        Ok(claims)
+    }
+}
+
+fn check_url(value: &str, allow_insecure_http: bool) -> Result<(), String> {
+    let url = Url::parse(value).map_err(|err| format!("Invalid OIDC URL {}: {}", value, err))?;
+    match url.scheme() {
+        "https" => Ok(()),
+        "http" if allow_insecure_http => Ok(()),
+        _ => Err(format!("OIDC URL must use https (set OIDC_ALLOW_INSECURE_HTTP for local testing): {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::pkg::oidc::mock::MockIssuer;
+    use serde_json::json;
+
+    const NONCE: &str = "nonce-1";
+    const CODE_VERIFIER: &str = "verifier-0123456789-0123456789-0123456789-0123456789";
+
+    // Runs the browser part of the flow and returns the authorization code.
+    async fn authorization_code(mock: &MockIssuer, client: &OidcClient) -> String {
+        let url = client.authorization_url("state-1", NONCE, CODE_VERIFIER).await.unwrap();
+        let (code, state) = mock.authorize(&url).await.unwrap();
+        assert_eq!(state, "state-1");
+        code
+    }
+
+    async fn login(mock: &MockIssuer, client: &OidcClient) -> Result<IdTokenClaims, String> {
+        let code = authorization_code(mock, client).await;
+        let id_token = client.exchange_code(&code, CODE_VERIFIER).await?;
+        client.verify_id_token(&id_token, NONCE).await
+    }
+
+    #[actix_web::test]
+    async fn logs_in_with_authorization_code_and_pkce() {
+        let mock = MockIssuer::start().await;
+        let client = mock.client();
+
+        let url = Url::parse(&client.authorization_url("state-1", NONCE, CODE_VERIFIER).await.unwrap()).unwrap();
+        let params: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(url.path(), "/authorize");
+        assert_eq!(params["code_challenge"], code_challenge(CODE_VERIFIER));
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert_eq!(params["nonce"], NONCE);
+
+        let claims = login(&mock, &client).await.unwrap();
+        assert_eq!(claims.iss, mock.issuer);
+        assert_eq!(claims.sub, "mock-user-1");
+        assert_eq!(claims.email.as_deref(), Some("mock.user@example.com"));
+        assert!(claims.email_verified());
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn rejects_a_wrong_code_verifier_and_reused_codes() {
+        let mock = MockIssuer::start().await;
+        let client = mock.client();
+
+        let code = authorization_code(&mock, &client).await;
+        assert!(client.exchange_code(&code, "another-verifier").await.is_err());
+        assert!(client.exchange_code(&code, CODE_VERIFIER).await.is_err());
+
+        let code = authorization_code(&mock, &client).await;
+        assert!(client.exchange_code(&code, CODE_VERIFIER).await.is_ok());
+        assert!(client.exchange_code(&code, CODE_VERIFIER).await.is_err());
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn rejects_id_tokens_with_wrong_nonce_audience_issuer_or_expiry() {
+        let mock = MockIssuer::start().await;
+        let client = mock.client();
+
+        let code = authorization_code(&mock, &client).await;
+        let id_token = client.exchange_code(&code, CODE_VERIFIER).await.unwrap();
+        assert!(client.verify_id_token(&id_token, "another-nonce").await.is_err());
+
+        for claims in [
+            json!({ "aud": "another-client" }),
+            json!({ "aud": crate::internal::pkg::oidc::mock::CLIENT_ID, "iss": "https://issuer.example.com" }),
+            json!({ "iss": mock.issuer, "exp": chrono::Utc::now().timestamp() - 3600 }),
+        ] {
+            mock.set_claims(claims);
+            assert!(login(&mock, &client).await.is_err());
+        }
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn only_a_boolean_email_verified_counts() {
+        let mock = MockIssuer::start().await;
+        let client = mock.client();
+
+        mock.set_claims(json!({ "email_verified": "true" }));
+        assert!(!login(&mock, &client).await.unwrap().email_verified());
+        mock.set_claims(json!({ "email_verified": null }));
+        assert!(!login(&mock, &client).await.unwrap().email_verified());
+        mock.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn refetches_signing_keys_after_a_rotation() {
+        let mock = MockIssuer::start().await;
+        let client = mock.client();
+
+        assert!(login(&mock, &client).await.is_ok());
+        mock.rotate_keys();
+        assert!(login(&mock, &client).await.is_ok());
+        mock.stop().await;
+    }
+
+    #[test]
+    fn plain_http_issuers_need_opting_in() {
+        let client = |allow_insecure_http| OidcClient::new(
+            "http://127.0.0.1:1".to_string(),
+            "client".to_string(),
+            None,
+            "http://localhost/callback".to_string(),
+            "openid".to_string(),
+            allow_insecure_http,
+        );
+        assert!(client(false).is_err());
+        assert!(client(true).is_ok());
+    }
+}
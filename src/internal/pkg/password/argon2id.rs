@@ -0,0 +1,67 @@
+use crate::config::settings::CONFIG;
+use crate::internal::pkg::password::password::PasswordHasher;
+use argon2::{Algorithm, Argon2, Params, Version};
+use argon2::password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier, SaltString, rand_core::OsRng};
+
+pub struct Argon2idHasher {
+    params: Params,
+}
+
+impl Argon2idHasher {
+    pub fn from_config() -> Result<Self, String> {
+        Self::new(CONFIG.argon2_memory_kib, CONFIG.argon2_iterations, CONFIG.argon2_parallelism)
+    }
+
+    pub fn new(memory_kib: u32, iterations: u32, parallelism: u32) -> Result<Self, String> {
+        let params = Params::new(memory_kib, iterations, parallelism, None)
+            .map_err(|err| format!("Invalid ARGON2_* settings: {}", err))?;
+        Ok(Argon2idHasher { params })
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+}
+
+impl PasswordHasher for Argon2idHasher {
+    fn name(&self) -> &'static str {
+        "argon2id"
+    }
+
+    // argon2i and argon2d hashes are accepted too and upgraded like any other legacy hash.
+    fn recognizes(&self, hash: &str) -> bool {
+        hash.starts_with("$argon2")
+    }
+
+    fn hash(&self, password: &str) -> Result<String, String> {
+        let salt = SaltString::generate(&mut OsRng);
+        self.argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|err| err.to_string())
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> bool {
+        // The parameters are taken from the stored hash, not from the configuration.
+        match PasswordHash::new(hash) {
+            Ok(parsed) => Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    fn is_outdated(&self, hash: &str) -> bool {
+        let parsed = match PasswordHash::new(hash) {
+            Ok(parsed) => parsed,
+            Err(_) => return true,
+        };
+        if parsed.algorithm != Algorithm::Argon2id.ident() || parsed.version != Some(Version::V0x13.into()) {
+            return true;
+        }
+        match Params::try_from(&parsed) {
+            Ok(params) => params.m_cost() != self.params.m_cost()
+                || params.t_cost() != self.params.t_cost()
+                || params.p_cost() != self.params.p_cost(),
+            Err(_) => true,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::config::settings::CONFIG;
+use crate::internal::pkg::password::password::PasswordHasher;
+
+// Hashes stored before argon2id became the default are bcrypt.
+pub struct BcryptHasher {
+    cost: u32,
+}
+
+impl BcryptHasher {
+    pub fn from_config() -> Result<Self, String> {
+        Self::new(CONFIG.bcrypt_cost)
+    }
+
+    pub fn new(cost: u32) -> Result<Self, String> {
+        if !(4..=31).contains(&cost) {
+            return Err(format!("BCRYPT_COST must be between 4 and 31, got {}", cost));
+        }
+        Ok(BcryptHasher { cost })
+    }
+}
+
+impl PasswordHasher for BcryptHasher {
+    fn name(&self) -> &'static str {
+        "bcrypt"
+    }
+
+    fn recognizes(&self, hash: &str) -> bool {
+        ["$2a$", "$2b$", "$2x$", "$2y$"].iter().any(|prefix| hash.starts_with(prefix))
+    }
+
+    fn hash(&self, password: &str) -> Result<String, String> {
+        ::bcrypt::hash(password, self.cost).map_err(|err| err.to_string())
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> bool {
+        ::bcrypt::verify(password, hash).unwrap_or(false)
+    }
+
+    // "$2b$12$..." carries the cost in the second field.
+    fn is_outdated(&self, hash: &str) -> bool {
+        hash.split('$').nth(2).and_then(|cost| cost.parse::<u32>().ok()) != Some(self.cost)
+    }
+}
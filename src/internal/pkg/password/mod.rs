@@ -0,0 +1,4 @@
+pub mod password;
+pub mod argon2id;
+pub mod bcrypt;
+pub mod policy;
\ No newline at end of file
@@ -0,0 +1,140 @@
+use crate::config::settings::CONFIG;
+use crate::internal::pkg::password::argon2id::Argon2idHasher;
+use crate::internal::pkg::password::bcrypt::BcryptHasher;
+use once_cell::sync::Lazy;
+use tokio::task;
+
+pub static PASSWORDS: Lazy<Passwords> = Lazy::new(|| Passwords::from_config().expect("Invalid password hashing configuration"));
+
+// Verified against when there is no stored hash, so unknown accounts take as long
+// to reject as a wrong password.
+static DUMMY_HASH: Lazy<String> = Lazy::new(|| PASSWORDS.current.hash("dummy-password").expect("dummy password hash"));
+
+// One password hashing algorithm. Hashes are self describing (PHC or modular crypt
+// strings), so the algorithm and its parameters can be read back from a stored hash.
+pub trait PasswordHasher: Send + Sync {
+    fn name(&self) -> &'static str;
+    // Whether the stored hash was produced by this algorithm.
+    fn recognizes(&self, hash: &str) -> bool;
+    fn hash(&self, password: &str) -> Result<String, String>;
+    fn verify(&self, password: &str, hash: &str) -> bool;
+    // Whether the hash was made with other parameters than the configured ones.
+    fn is_outdated(&self, hash: &str) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    Failed,
+    Verified,
+    // Correct password, but the hash should be replaced with one from the current hasher.
+    NeedsRehash,
+}
+
+pub struct Passwords {
+    current: Box<dyn PasswordHasher>,
+    hashers: Vec<Box<dyn PasswordHasher>>,
+}
+
+impl Passwords {
+    fn from_config() -> Result<Self, String> {
+        let argon2id = Argon2idHasher::from_config()?;
+        let bcrypt = BcryptHasher::from_config()?;
+        let (current, legacy): (Box<dyn PasswordHasher>, Box<dyn PasswordHasher>) = match CONFIG.password_hasher.trim().to_lowercase().as_str() {
+            "argon2id" => (Box::new(argon2id), Box::new(bcrypt)),
+            "bcrypt" => (Box::new(bcrypt), Box::new(argon2id)),
+            other => return Err(format!("Unsupported PASSWORD_HASHER: {}", other)),
+        };
+        Ok(Passwords { current, hashers: vec![legacy] })
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String, String> {
+        self.current.hash(password)
+    }
+
+    // Hashes no hasher recognizes (like the placeholder of accounts without a
+    // password) never verify.
+    pub fn verify(&self, password: &str, hash: &str) -> Verification {
+        if self.current.recognizes(hash) {
+            return match self.current.verify(password, hash) {
+                false => Verification::Failed,
+                true if self.current.is_outdated(hash) => Verification::NeedsRehash,
+                true => Verification::Verified,
+            };
+        }
+        match self.hashers.iter().find(|hasher| hasher.recognizes(hash)) {
+            Some(hasher) if hasher.verify(password, hash) => Verification::NeedsRehash,
+            _ => Verification::Failed,
+        }
+    }
+}
+
+// Hash with the configured hasher off the async runtime.
+pub async fn hash_password(password: String) -> Result<String, String> {
+    task::spawn_blocking(move || PASSWORDS.hash(&password))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+// Verify off the async runtime. Without a stored hash the dummy hash is checked
+// so the response time does not tell whether the account exists.
+pub async fn verify_password(password: String, hash: Option<String>) -> Verification {
+    let verification = task::spawn_blocking(move || match hash {
+        Some(hash) => PASSWORDS.verify(&password, &hash),
+        None => {
+            PASSWORDS.verify(&password, &DUMMY_HASH);
+            Verification::Failed
+        }
+    }).await;
+    verification.unwrap_or(Verification::Failed)
+}
+
+pub fn hasher_name() -> &'static str {
+    PASSWORDS.current.name()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cheap parameters, the checks only look at which hasher made a hash and with what.
+    fn argon2id(memory_kib: u32) -> Box<dyn PasswordHasher> {
+        Box::new(Argon2idHasher::new(memory_kib, 1, 1).unwrap())
+    }
+
+    fn bcrypt(cost: u32) -> Box<dyn PasswordHasher> {
+        Box::new(BcryptHasher::new(cost).unwrap())
+    }
+
+    #[test]
+    fn bcrypt_hashes_are_upgraded_to_argon2id() {
+        let legacy = bcrypt(4).hash("secret123").unwrap();
+        let passwords = Passwords { current: argon2id(64), hashers: vec![bcrypt(4)] };
+
+        assert_eq!(passwords.verify("secret123", &legacy), Verification::NeedsRehash);
+        assert_eq!(passwords.verify("wrong", &legacy), Verification::Failed);
+
+        let upgraded = passwords.hash("secret123").unwrap();
+        assert!(upgraded.starts_with("$argon2id$"));
+        assert_eq!(passwords.verify("secret123", &upgraded), Verification::Verified);
+    }
+
+    #[test]
+    fn hashes_with_old_parameters_are_upgraded() {
+        let passwords = Passwords { current: argon2id(128), hashers: vec![bcrypt(5)] };
+        let argon2id_hash = argon2id(64).hash("secret123").unwrap();
+        assert_eq!(passwords.verify("secret123", &argon2id_hash), Verification::NeedsRehash);
+        assert_eq!(passwords.verify("wrong", &argon2id_hash), Verification::Failed);
+
+        let passwords = Passwords { current: bcrypt(5), hashers: vec![argon2id(64)] };
+        assert_eq!(passwords.verify("secret123", &bcrypt(4).hash("secret123").unwrap()), Verification::NeedsRehash);
+        assert_eq!(passwords.verify("secret123", &bcrypt(5).hash("secret123").unwrap()), Verification::Verified);
+        assert_eq!(passwords.verify("secret123", &argon2id_hash), Verification::NeedsRehash);
+    }
+
+    #[test]
+    fn unrecognized_hashes_never_verify() {
+        let passwords = Passwords { current: argon2id(64), hashers: vec![bcrypt(4)] };
+        assert_eq!(passwords.verify("!oidc:abc", "!oidc:abc"), Verification::Failed);
+        assert_eq!(passwords.verify("secret123", ""), Verification::Failed);
+    }
+}
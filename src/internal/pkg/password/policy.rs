@@ -0,0 +1,118 @@
+use crate::config::settings::CONFIG;
+use once_cell::sync::Lazy;
+use sha1::{Digest, Sha1};
+use std::collections::HashSet;
+use std::fs;
+
+pub static PASSWORD_POLICY: Lazy<PasswordPolicy> = Lazy::new(|| PasswordPolicy::from_config().expect("Invalid password policy configuration"));
+
+pub struct PasswordPolicy {
+    min_length: usize,
+    max_length: usize,
+    // Upper case SHA-1 hex of every breached password.
+    breached: HashSet<String>,
+}
+
+fn sha1_hex(password: &str) -> String {
+    hex::encode_upper(Sha1::digest(password.as_bytes()))
+}
+
+// A line is either a plain password or a SHA-1 hash, optionally followed by
+// ":count" as in the Have I Been Pwned downloads.
+fn parse_breached_line(line: &str) -> Option<String> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.trim().is_empty() {
+        return None;
+    }
+    let candidate = line.split_once(':').map_or(line, |(hash, _)| hash).trim();
+    if candidate.len() == 40 && candidate.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(candidate.to_uppercase());
+    }
+    Some(sha1_hex(line))
+}
+
+impl PasswordPolicy {
+    fn from_config() -> Result<Self, String> {
+        if CONFIG.password_min_length == 0 || CONFIG.password_min_length > CONFIG.password_max_length {
+            return Err("PASSWORD_MIN_LENGTH must be between 1 and PASSWORD_MAX_LENGTH".to_string());
+        }
+        let breached = match CONFIG.password_breached_list.as_deref() {
+            Some(path) => fs::read_to_string(path)
+                .map_err(|err| format!("Cannot read PASSWORD_BREACHED_LIST {}: {}", path, err))?
+                .lines()
+                .filter_map(parse_breached_line)
+                .collect(),
+            None => HashSet::new(),
+        };
+        Ok(PasswordPolicy {
+            min_length: CONFIG.password_min_length,
+            max_length: CONFIG.password_max_length,
+            breached,
+        })
+    }
+
+    pub fn breached_count(&self) -> usize {
+        self.breached.len()
+    }
+
+    // The reason the password is rejected, None when it is acceptable.
+    pub fn check(&self, password: &str, username: &str) -> Option<String> {
+        let length = password.chars().count();
+        if length < self.min_length {
+            return Some(format!("Password must be at least {} characters long", self.min_length));
+        }
+        if length > self.max_length {
+            return Some(format!("Password must be at most {} characters long", self.max_length));
+        }
+        if !username.trim().is_empty() && password.trim().to_lowercase() == username.trim().to_lowercase() {
+            return Some("Password must not be the same as the username".to_string());
+        }
+        if self.breached.contains(&sha1_hex(password)) {
+            return Some("Password appears in a list of breached passwords, choose another one".to_string());
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(breached: &str) -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 8,
+            max_length: 16,
+            breached: breached.lines().filter_map(parse_breached_line).collect(),
+        }
+    }
+
+    #[test]
+    fn the_length_is_counted_in_characters() {
+        let policy = policy("");
+        assert_eq!(policy.check("short", "alice").unwrap(), "Password must be at least 8 characters long");
+        assert_eq!(policy.check("a-much-too-long-password", "alice").unwrap(), "Password must be at most 16 characters long");
+        assert!(policy.check("eightchr", "alice").is_none());
+        assert!(policy.check("sixteen-chars-ok", "alice").is_none());
+        // Eight characters, but more than sixteen bytes.
+        assert!(policy.check("éééééééé", "alice").is_none());
+    }
+
+    #[test]
+    fn breached_passwords_are_rejected() {
+        // A plain password, a SHA-1 of "password123" as in the HIBP downloads and a blank line.
+        let policy = policy("letmein1\r\ncbfdac6008f9cab4083784cbd1874f76618d2a97:42\n\n");
+        assert_eq!(policy.breached_count(), 2);
+        assert!(policy.check("letmein1", "alice").unwrap().contains("breached"));
+        assert!(policy.check("password123", "alice").unwrap().contains("breached"));
+        assert!(policy.check("Password123", "alice").is_none());
+    }
+
+    #[test]
+    fn the_password_must_differ_from_the_username() {
+        let policy = policy("");
+        assert_eq!(policy.check("Alice.Smith", "alice.smith").unwrap(), "Password must not be the same as the username");
+        assert_eq!(policy.check(" alice.smith ", "alice.smith").unwrap(), "Password must not be the same as the username");
+        assert!(policy.check("alice.smith1", "alice.smith").is_none());
+        assert!(policy.check("password-ok", "").is_none());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::internal::pkg::query::filter::FilterValue;
+use crate::internal::pkg::query::query::{ColumnKind, Schema, SortDirection, SortKey};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+// How a cursor page reports the size of the whole result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountMode {
+    None,
+    Exact,
+    Estimate,
+}
+
+impl CountMode {
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(str::trim) {
+            None | Some("") | Some("none") => Ok(CountMode::None),
+            Some("exact") => Ok(CountMode::Exact),
+            Some("estimate") => Ok(CountMode::Estimate),
+            Some(other) => Err(format!("Invalid count: {}, expected none, exact or estimate", other)),
+        }
+    }
+}
+
+// Where a keyset page starts: the sort key values of the row next to it and
+// whether the page lies before (backward) or after that row.
+#[derive(Debug)]
+pub struct Cursor {
+    pub backward: bool,
+    pub values: Vec<Option<FilterValue>>,
+}
+
+// One keyset page and the cursors of the pages around it, None at either end.
+#[derive(Debug)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+// What a cursor carries on the wire. The order is part of it so a cursor
+// cannot be replayed against a different sort.
+#[derive(Serialize, Deserialize)]
+struct Token {
+    #[serde(rename = "o")]
+    order: String,
+    #[serde(rename = "b")]
+    backward: bool,
+    #[serde(rename = "v")]
+    values: Vec<Value>,
+}
+
+fn order_signature(order: &[SortKey]) -> String {
+    order.iter()
+        .map(|sort| match sort.direction {
+            SortDirection::Asc => sort.column.name.to_string(),
+            SortDirection::Desc => format!("-{}", sort.column.name),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Cursor {
+    pub fn encode(order: &[SortKey], backward: bool, values: &[Option<FilterValue>]) -> String {
+        let values = values.iter()
+            .map(|value| match value {
+                Some(FilterValue::Integer(value)) => Value::from(*value),
+                Some(FilterValue::Float(value)) => Value::from(*value),
+                Some(FilterValue::Text(value)) => Value::from(value.as_str()),
+                Some(FilterValue::Timestamp(value)) => Value::from(value.format(TIMESTAMP_FORMAT).to_string()),
+                _ => Value::Null,
+            })
+            .collect();
+        let token = Token { order: order_signature(order), backward, values };
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(&token).unwrap_or_default())
+    }
+}
+
+impl Schema {
+    // Decodes a cursor handed out for the same order, typing its values per column.
+    pub fn decode_cursor(&'static self, cursor: &str, order: &[SortKey]) -> Result<Cursor, String> {
+        let invalid = || "Invalid cursor".to_string();
+        let token: Token = URL_SAFE_NO_PAD.decode(cursor.trim()).ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(invalid)?;
+        if token.order != order_signature(order) {
+            return Err("Cursor does not match the requested sort".to_string());
+        }
+        if token.values.len() != order.len() {
+            return Err(invalid());
+        }
+
+        let values = order.iter().zip(token.values)
+            .map(|(sort, value)| match (sort.column.kind, value) {
+                (_, Value::Null) => Some(None),
+                (ColumnKind::Integer, Value::Number(value)) => value.as_i64().map(|value| Some(FilterValue::Integer(value))),
+                (ColumnKind::Float, Value::Number(value)) => value.as_f64().map(|value| Some(FilterValue::Float(value))),
+                (ColumnKind::Text, Value::String(value)) => Some(Some(FilterValue::Text(value))),
+                (ColumnKind::Timestamp, Value::String(value)) => NaiveDateTime::parse_from_str(&value, TIMESTAMP_FORMAT).ok()
+                    .map(|value| Some(FilterValue::Timestamp(value))),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+        Ok(Cursor { backward: token.backward, values })
+    }
+}
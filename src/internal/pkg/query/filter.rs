@@ -0,0 +1,146 @@
+use crate::internal::pkg::query::query::{Column, ColumnKind, Schema};
+use crate::internal::pkg::utils::time::parse_time;
+use chrono::NaiveDateTime;
+use url::form_urlencoded;
+
+// Most values one `in` filter may list.
+const MAX_IN_VALUES: usize = 100;
+
+// The operators that compare a column with one bound value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl Comparison {
+    // The comparison written between the column and the bound value.
+    pub fn sql(self) -> &'static str {
+        match self {
+            Comparison::Eq => " = ",
+            Comparison::Ne => " <> ",
+            Comparison::Gt => " > ",
+            Comparison::Gte => " >= ",
+            Comparison::Lt => " < ",
+            Comparison::Lte => " <= ",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Compare(Comparison),
+    In,
+    Null,
+    Like,
+}
+
+const OPERATORS: [(&str, Operator); 9] = [
+    ("eq", Operator::Compare(Comparison::Eq)),
+    ("ne", Operator::Compare(Comparison::Ne)),
+    ("in", Operator::In),
+    ("gt", Operator::Compare(Comparison::Gt)),
+    ("gte", Operator::Compare(Comparison::Gte)),
+    ("lt", Operator::Compare(Comparison::Lt)),
+    ("lte", Operator::Compare(Comparison::Lte)),
+    ("null", Operator::Null),
+    ("like", Operator::Like),
+];
+
+impl Operator {
+    fn parse(value: &str) -> Option<Self> {
+        OPERATORS.iter().find(|(name, _)| *name == value).map(|(_, operator)| *operator)
+    }
+
+    fn name(self) -> &'static str {
+        OPERATORS.iter().find(|(_, operator)| *operator == self).map(|(name, _)| *name).unwrap_or_default()
+    }
+
+    fn supports(self, kind: ColumnKind) -> bool {
+        match self {
+            Operator::Compare(Comparison::Eq | Comparison::Ne) | Operator::In | Operator::Null => true,
+            Operator::Compare(_) => kind != ColumnKind::Text,
+            Operator::Like => kind == ColumnKind::Text,
+        }
+    }
+}
+
+// A filter value converted to the type of its column.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    Integer(i64),
+    Float(f64),
+    Text(String),
+    Timestamp(NaiveDateTime),
+    Bool(bool),
+    List(Vec<FilterValue>),
+}
+
+// One validated `filter[field][op]=value`.
+#[derive(Debug)]
+pub struct Filter {
+    pub column: &'static Column,
+    pub operator: Operator,
+    pub value: FilterValue,
+}
+
+fn typed_value(column: &Column, value: &str) -> Option<FilterValue> {
+    match column.kind {
+        ColumnKind::Integer => value.trim().parse().ok().map(FilterValue::Integer),
+        ColumnKind::Float => value.trim().parse().ok().filter(|value: &f64| value.is_finite()).map(FilterValue::Float),
+        ColumnKind::Text => Some(FilterValue::Text(value.to_string())),
+        ColumnKind::Timestamp => parse_time(value).map(FilterValue::Timestamp),
+    }
+}
+
+impl Schema {
+    // Reads every `filter[field]` and `filter[field][op]` parameter of a query string.
+    // Without an operator text columns match a substring and the others match exactly.
+    pub fn parse_filters(&'static self, query_string: &str) -> Result<Vec<Filter>, String> {
+        let mut filters = Vec::new();
+        for (key, value) in form_urlencoded::parse(query_string.as_bytes()) {
+            let Some(inner) = key.strip_prefix("filter[").and_then(|key| key.strip_suffix(']')) else {
+                continue;
+            };
+            let (field, operator) = match inner.split_once("][") {
+                Some((field, operator)) => (field, Some(operator)),
+                None => (inner, None),
+            };
+            let column = self.column(field).ok_or_else(|| format!("Unknown filter field: {}", field))?;
+            let operator = match operator {
+                Some(operator) => Operator::parse(operator).ok_or_else(|| format!("Unknown filter operator: {}", operator))?,
+                None if column.kind == ColumnKind::Text => Operator::Like,
+                None => Operator::Compare(Comparison::Eq),
+            };
+            if !operator.supports(column.kind) {
+                return Err(format!("Filter operator {} is not supported on {}", operator.name(), field));
+            }
+
+            let invalid = || format!("Invalid value for filter on {}: {}", field, value);
+            let value = match operator {
+                Operator::Null => match value.trim() {
+                    "true" => FilterValue::Bool(true),
+                    "false" => FilterValue::Bool(false),
+                    _ => return Err(invalid()),
+                },
+                Operator::In => {
+                    let values = value.split(',')
+                        .map(|value| typed_value(column, value))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(invalid)?;
+                    if values.len() > MAX_IN_VALUES {
+                        return Err(format!("Filter on {} lists more than {} values", field, MAX_IN_VALUES));
+                    }
+                    FilterValue::List(values)
+                }
+                _ => typed_value(column, &value).ok_or_else(invalid)?,
+            };
+            filters.push(Filter { column, operator, value });
+        }
+        Ok(filters)
+    }
+}
@@ -0,0 +1,231 @@
+use crate::internal::pkg::query::cursor::{CountMode, Cursor, CursorPage};
+use crate::internal::pkg::query::filter::{Comparison, FilterValue, Operator};
+use crate::internal::pkg::query::query::{ListQuery, SortDirection};
+use crate::internal::pkg::query::search::{Search, SearchTerm};
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+// One row of an in-memory table: the value of every schema column by field name,
+// a missing column is NULL. Under the schema's search vector it holds the text a
+// search matches against.
+pub type MemoryRow = HashMap<&'static str, FilterValue>;
+
+// Compares two values of the same column. Text compares by code point, as the C collation does.
+fn compare(left: &FilterValue, right: &FilterValue) -> Ordering {
+    match (left, right) {
+        (FilterValue::Integer(left), FilterValue::Integer(right)) => left.cmp(right),
+        (FilterValue::Float(left), FilterValue::Float(right)) => left.total_cmp(right),
+        (FilterValue::Text(left), FilterValue::Text(right)) => left.cmp(right),
+        (FilterValue::Timestamp(left), FilterValue::Timestamp(right)) => left.cmp(right),
+        (FilterValue::Bool(left), FilterValue::Bool(right)) => left.cmp(right),
+        _ => Ordering::Equal,
+    }
+}
+
+// ILIKE with a `like` filter value: `*` matches any run of characters and a value
+// without one matches anywhere in the text.
+fn like(text: &str, pattern: &str) -> bool {
+    let text = text.to_lowercase();
+    let pattern = pattern.to_lowercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let [first, middle @ .., last] = parts.as_slice() else {
+        return text.contains(&pattern);
+    };
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+// A filter on one value, NULL matching nothing but `null`, as in SQL.
+fn matches_filter(value: Option<&FilterValue>, operator: Operator, filter: &FilterValue) -> bool {
+    if operator == Operator::Null {
+        return value.is_none() == (*filter == FilterValue::Bool(true));
+    }
+    let Some(value) = value else {
+        return false;
+    };
+    match (operator, filter) {
+        (Operator::Like, FilterValue::Text(pattern)) => matches!(value, FilterValue::Text(text) if like(text, pattern)),
+        (Operator::In, FilterValue::List(values)) => values.iter().any(|filter| compare(value, filter) == Ordering::Equal),
+        (Operator::Compare(Comparison::Eq), filter) => compare(value, filter) == Ordering::Equal,
+        (Operator::Compare(Comparison::Ne), filter) => compare(value, filter) != Ordering::Equal,
+        (Operator::Compare(Comparison::Gt), filter) => compare(value, filter) == Ordering::Greater,
+        (Operator::Compare(Comparison::Gte), filter) => compare(value, filter) != Ordering::Less,
+        (Operator::Compare(Comparison::Lt), filter) => compare(value, filter) == Ordering::Less,
+        (Operator::Compare(Comparison::Lte), filter) => compare(value, filter) != Ordering::Greater,
+        _ => false,
+    }
+}
+
+fn json_value(value: &FilterValue) -> Value {
+    match value {
+        FilterValue::Integer(value) => json!(value),
+        FilterValue::Float(value) => json!(value),
+        FilterValue::Text(value) => json!(value),
+        FilterValue::Timestamp(value) => json!(value),
+        FilterValue::Bool(value) => json!(value),
+        FilterValue::List(values) => Value::Array(values.iter().map(json_value).collect()),
+    }
+}
+
+// The same list semantics as the SQL of ListQuery, evaluated over rows held in memory.
+impl ListQuery {
+    // The search of this query, for computing search dependent columns such as a rank.
+    pub fn searching(&self) -> Option<&Search> {
+        self.search.as_ref()
+    }
+
+    // The sort key values of a row, in order.
+    fn keys<'a>(&self, row: &'a MemoryRow) -> Vec<Option<&'a FilterValue>> {
+        self.order.iter().map(|sort| row.get(sort.column.name)).collect()
+    }
+
+    // Orders sort keys the way the SQL does: NULLs last ascending and first descending,
+    // and reversed while paging backwards.
+    fn compare_keys(&self, left: &[Option<&FilterValue>], right: &[Option<&FilterValue>]) -> Ordering {
+        for ((sort, left), right) in self.order.iter().zip(left).zip(right) {
+            let ordering = match (left, right) {
+                (Some(left), Some(right)) => compare(left, right),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            };
+            let ordering = if self.direction(sort) == SortDirection::Desc { ordering.reverse() } else { ordering };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+
+    // Live or deleted rows, the search and filters, and with `keyset` the rows past the cursor.
+    fn matches(&self, row: &MemoryRow, keyset: bool) -> bool {
+        // Rows are keyed by field name, `deleted` is the column's SQL.
+        if let Some(deleted) = self.schema.deleted
+            && let Some(deleted) = self.schema.columns.iter().find(|column| column.sql == deleted)
+            && row.contains_key(deleted.name) != self.trashed
+        {
+            return false;
+        }
+        if let (Some(vector), Some(search)) = (self.schema.search, &self.search)
+            && !matches!(row.get(vector), Some(FilterValue::Text(text)) if search.matches(text))
+        {
+            return false;
+        }
+        if keyset && let Some(cursor) = &self.cursor {
+            let values: Vec<Option<&FilterValue>> = cursor.values.iter().map(Option::as_ref).collect();
+            if self.compare_keys(&self.keys(row), &values) != Ordering::Greater {
+                return false;
+            }
+        }
+        self.filters.iter().all(|filter| matches_filter(row.get(filter.column.name), filter.operator, &filter.value))
+    }
+
+    fn sorted<'a>(&self, rows: &'a [MemoryRow], keyset: bool) -> Vec<&'a MemoryRow> {
+        let mut rows: Vec<&MemoryRow> = rows.iter().filter(|row| self.matches(row, keyset)).collect();
+        rows.sort_by(|left, right| self.compare_keys(&self.keys(left), &self.keys(right)));
+        rows
+    }
+
+    // The selected fields of a row, each under its field name.
+    fn project(&self, row: &MemoryRow) -> Value {
+        let mut object = Map::new();
+        for field in &self.fields {
+            object.insert(field.name.to_string(), row.get(field.name).map_or(Value::Null, json_value));
+        }
+        Value::Object(object)
+    }
+
+    // fetch_all over in-memory rows.
+    pub fn fetch_all_rows(&self, rows: &[MemoryRow]) -> Vec<Value> {
+        self.sorted(rows, false).into_iter()
+            .skip(self.offset as usize)
+            .take(self.limit as usize)
+            .map(|row| self.project(row))
+            .collect()
+    }
+
+    // fetch_page over in-memory rows.
+    pub fn fetch_page_rows(&self, rows: &[MemoryRow]) -> CursorPage<Value> {
+        let mut rows = self.sorted(rows, true);
+        let more = rows.len() as i64 > self.limit;
+        rows.truncate(self.limit as usize);
+        let backward = self.cursor.as_ref().is_some_and(|cursor| cursor.backward);
+        if backward {
+            rows.reverse();
+        }
+
+        let cursor_keys = |row: &MemoryRow| self.keys(row).into_iter().map(Option::<&FilterValue>::cloned).collect::<Vec<_>>();
+        let (first, last) = match (rows.first(), rows.last()) {
+            (Some(first), Some(last)) => (cursor_keys(first), cursor_keys(last)),
+            _ => return CursorPage { items: Vec::new(), next_cursor: None, prev_cursor: None },
+        };
+        let (has_prev, has_next) = if backward { (more, true) } else { (self.cursor.is_some(), more) };
+        CursorPage {
+            items: rows.iter().map(|row| self.project(row)).collect(),
+            next_cursor: has_next.then(|| Cursor::encode(&self.order, false, &last)),
+            prev_cursor: has_prev.then(|| Cursor::encode(&self.order, true, &first)),
+        }
+    }
+
+    // total over in-memory rows, where an estimate is the exact count.
+    pub fn total_rows(&self, rows: &[MemoryRow], mode: CountMode) -> Option<i64> {
+        match mode {
+            CountMode::None => None,
+            CountMode::Exact | CountMode::Estimate => Some(rows.iter().filter(|row| self.matches(row, false)).count() as i64),
+        }
+    }
+}
+
+impl SearchTerm {
+    fn matches(&self, word: &str) -> bool {
+        if self.prefix { word.starts_with(&self.word) } else { word == self.word }
+    }
+}
+
+// Full-text search over plain text for in-memory rows. Words are lowercased runs of
+// letters and digits compared as they are, there is no stemming or stop word list.
+impl Search {
+    fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+        text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).map(str::to_lowercase)
+    }
+
+    // Every term occurs in the text.
+    pub fn matches(&self, text: &str) -> bool {
+        let words: Vec<String> = Search::words(text).collect();
+        self.terms.iter().all(|term| words.iter().any(|word| term.matches(word)))
+    }
+
+    // How many words of the text match a term.
+    pub fn occurrences(&self, text: &str) -> usize {
+        Search::words(text).filter(|word| self.terms.iter().any(|term| term.matches(word))).count()
+    }
+
+    // The text with every matching word marked as a headline marks it.
+    pub fn highlight(&self, text: &str) -> String {
+        let mut highlighted = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find(char::is_alphanumeric) {
+            highlighted.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest.find(|c: char| !c.is_alphanumeric()).unwrap_or(rest.len());
+            let word = &rest[..end];
+            if self.terms.iter().any(|term| term.matches(&word.to_lowercase())) {
+                highlighted.push_str(&format!("<mark>{}</mark>", word));
+            } else {
+                highlighted.push_str(word);
+            }
+            rest = &rest[end..];
+        }
+        highlighted.push_str(rest);
+        highlighted
+    }
+}
@@ -0,0 +1,6 @@
+pub mod cursor;
+pub mod filter;
+#[cfg(test)]
+pub mod memory;
+pub mod query;
+pub mod search;
\ No newline at end of file
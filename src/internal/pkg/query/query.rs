@@ -0,0 +1,457 @@
+use crate::internal::pkg::query::cursor::{CountMode, Cursor, CursorPage};
+use crate::internal::pkg::query::filter::{Filter, FilterValue, Operator};
+use crate::internal::pkg::query::search::Search;
+use chrono::NaiveDateTime;
+use serde_json::{json, Map, Value};
+use sqlx::{postgres::{PgPool, PgRow}, Column as _, Postgres, QueryBuilder, Row};
+
+// How a column is compared when a list endpoint filters on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Integer,
+    Float,
+    Text,
+    Timestamp,
+}
+
+// A column a list endpoint may filter and sort on. `name` is what clients send and
+// the key rows come back under, camelCase like every other field of the API. `sql`
+// is written into the query as is and must never come from input. It may be an
+// expression over the row for computed fields.
+#[derive(Debug)]
+pub struct Column {
+    pub name: &'static str,
+    pub sql: &'static str,
+    pub kind: ColumnKind,
+}
+
+// The whitelist of one entity: its table, the columns clients may use, the
+// fields returned when none are asked for, the sort applied when none is asked
+// for, the unique, non-null column that breaks ties, the relations that can
+// be embedded with `include`, the tsvector column full-text search matches and
+// the timestamp column that marks a row as deleted.
+//
+// With a search vector the table is joined with `search`, whose search_config
+// and search_query columns hold the current search (NULL when there is none),
+// so computed columns such as a rank can refer to them.
+#[derive(Debug)]
+pub struct Schema {
+    pub table: &'static str,
+    pub columns: &'static [Column],
+    pub default_fields: &'static [&'static str],
+    pub default_sort: &'static str,
+    pub key: &'static str,
+    pub includes: &'static [&'static str],
+    pub search: Option<&'static str>,
+    pub deleted: Option<&'static str>,
+}
+
+impl Schema {
+    pub fn column(&self, name: &str) -> Option<&'static Column> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    // Parses a sparse fieldset such as "id,name". Nothing asked for means the default
+    // fields, the key column is always returned.
+    pub fn parse_fields(&'static self, spec: Option<&str>) -> Result<Vec<&'static Column>, String> {
+        let mut fields: Vec<&'static Column> = self.column(self.key).into_iter().collect();
+        let names: Vec<&str> = match spec.map(str::trim).filter(|spec| !spec.is_empty()) {
+            Some(spec) => spec.split(',').map(str::trim).collect(),
+            None => self.default_fields.to_vec(),
+        };
+        for name in names {
+            let column = self.column(name).ok_or_else(|| format!("Unknown field: {}", name))?;
+            if !fields.iter().any(|field| field.name == column.name) {
+                fields.push(column);
+            }
+        }
+        Ok(fields)
+    }
+
+    // Parses a comma separated list of relations to embed.
+    pub fn parse_includes(&'static self, spec: Option<&str>) -> Result<Vec<&'static str>, String> {
+        let mut includes = Vec::new();
+        for name in spec.unwrap_or_default().trim().split_terminator(',').map(str::trim) {
+            let include = self.includes.iter().find(|include| **include == name)
+                .ok_or_else(|| format!("Unknown include: {}", name))?;
+            if !includes.contains(include) {
+                includes.push(*include);
+            }
+        }
+        Ok(includes)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    fn sql(self) -> &'static str {
+        match self {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        }
+    }
+}
+
+// Turns a `like` filter into an ILIKE pattern. `*` is the only wildcard, LIKE's own
+// are escaped, and a value without one matches anywhere in the column.
+fn like_pattern(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    if escaped.contains('*') { escaped.replace('*', "%") } else { format!("%{}%", escaped) }
+}
+
+// A selected column, cast so its value decodes the same whatever the column type.
+fn select_sql(column: &Column) -> String {
+    match column.kind {
+        ColumnKind::Integer => format!("CAST({} AS BIGINT)", column.sql),
+        ColumnKind::Float => format!("CAST({} AS DOUBLE PRECISION)", column.sql),
+        ColumnKind::Text => format!("CAST({} AS TEXT)", column.sql),
+        ColumnKind::Timestamp => column.sql.to_string(),
+    }
+}
+
+fn push_value(query: &mut QueryBuilder<'static, Postgres>, value: &FilterValue) {
+    match value {
+        FilterValue::Integer(value) => { query.push_bind(*value); }
+        FilterValue::Float(value) => { query.push_bind(*value); }
+        FilterValue::Text(value) => { query.push_bind(value.clone()); }
+        FilterValue::Timestamp(value) => { query.push_bind(*value); }
+        FilterValue::Bool(value) => { query.push_bind(*value); }
+        FilterValue::List(values) => {
+            query.push("(");
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    query.push(", ");
+                }
+                push_value(query, value);
+            }
+            query.push(")");
+        }
+    }
+}
+
+// One ORDER BY key.
+#[derive(Debug, Clone, Copy)]
+pub struct SortKey {
+    pub column: &'static Column,
+    pub direction: SortDirection,
+}
+
+impl Schema {
+    // Parses a sort spec such as "-created_at,name": fields of the schema, each at most
+    // once, "-" in front for descending. An empty spec is the schema default. The key
+    // column is appended when missing so ties keep a stable order.
+    pub fn parse_sort(&'static self, spec: &str) -> Result<Vec<SortKey>, String> {
+        let mut order: Vec<SortKey> = Vec::new();
+        for field in spec.trim().split_terminator(',').map(str::trim) {
+            let (name, direction) = match field.strip_prefix('-') {
+                Some(name) => (name, SortDirection::Desc),
+                None => (field.strip_prefix('+').unwrap_or(field), SortDirection::Asc),
+            };
+            if name.is_empty() {
+                return Err(format!("Invalid sort: {}", spec));
+            }
+            let column = self.column(name).ok_or_else(|| format!("Unknown sort field: {}", name))?;
+            if order.iter().any(|sort| sort.column.name == column.name) {
+                return Err(format!("Sort field listed twice: {}", name));
+            }
+            order.push(SortKey { column, direction });
+        }
+        if order.is_empty()
+            && let Some(column) = self.column(self.default_sort)
+        {
+            order.push(SortKey { column, direction: SortDirection::Asc });
+        }
+        if let Some(key) = self.column(self.key)
+            && !order.iter().any(|sort| sort.column.name == key.name)
+        {
+            let direction = order.last().map_or(SortDirection::Asc, |sort| sort.direction);
+            order.push(SortKey { column: key, direction });
+        }
+        Ok(order)
+    }
+}
+
+// A list query over one schema. Every value is sent as a bound parameter, only
+// whitelisted column SQL and fixed keywords are written into the statement.
+#[derive(Debug)]
+pub struct ListQuery {
+    pub(super) schema: &'static Schema,
+    pub(super) fields: Vec<&'static Column>,
+    pub(super) filters: Vec<Filter>,
+    pub(super) order: Vec<SortKey>,
+    pub(super) limit: i64,
+    pub(super) offset: i64,
+    pub(super) cursor: Option<Cursor>,
+    pub(super) search: Option<Search>,
+    pub(super) trashed: bool,
+}
+
+impl ListQuery {
+    pub fn new(schema: &'static Schema) -> Self {
+        let fields = schema.parse_fields(None).unwrap_or_default();
+        ListQuery { schema, fields, filters: Vec::new(), order: Vec::new(), limit: 10, offset: 0, cursor: None, search: None, trashed: false }
+    }
+
+    // Fields come from Schema::parse_fields, the schema defaults are selected otherwise.
+    pub fn fields(mut self, fields: Vec<&'static Column>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    // Filters come from Schema::parse_filters, already checked against the schema.
+    pub fn filters(mut self, filters: Vec<Filter>) -> Self {
+        self.filters.extend(filters);
+        self
+    }
+
+    pub fn order_by(mut self, order: Vec<SortKey>) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn paginate(mut self, limit: i64, offset: i64) -> Self {
+        self.limit = limit.max(1);
+        self.offset = offset.max(0);
+        self
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.limit
+    }
+
+    // Keyset paging: rows after (or before) the cursor instead of an offset.
+    // The cursor comes from Schema::decode_cursor for this query's order.
+    pub fn after(mut self, cursor: Option<Cursor>) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    // Only rows matching the search, for schemas with a search vector.
+    pub fn search(mut self, search: Option<Search>) -> Self {
+        self.search = search;
+        self
+    }
+
+    // Deleted rows instead of the live ones, for schemas with soft delete.
+    pub fn trashed(mut self, trashed: bool) -> Self {
+        self.trashed = trashed;
+        self
+    }
+
+    fn push_from(&self, query: &mut QueryBuilder<'static, Postgres>) {
+        query.push(" FROM ").push(self.schema.table);
+        if self.schema.search.is_none() {
+            return;
+        }
+        match &self.search {
+            Some(search) => {
+                query.push(" CROSS JOIN (SELECT CAST(").push_bind(search.language.clone())
+                    .push(" AS regconfig) AS search_config, to_tsquery(CAST(").push_bind(search.language.clone())
+                    .push(" AS regconfig), ").push_bind(search.query())
+                    .push(") AS search_query) AS search");
+            }
+            None => {
+                query.push(" CROSS JOIN (SELECT CAST(NULL AS regconfig) AS search_config, CAST(NULL AS tsquery) AS search_query) AS search");
+            }
+        }
+    }
+
+    // Live or deleted rows, the search and filters, and with `keyset` the rows past the cursor.
+    fn push_where(&self, query: &mut QueryBuilder<'static, Postgres>, keyset: bool) {
+        let mut keyword = " WHERE ";
+        if let Some(deleted) = self.schema.deleted {
+            query.push(keyword).push(deleted).push(if self.trashed { " IS NOT NULL" } else { " IS NULL" });
+            keyword = " AND ";
+        }
+        if let (Some(vector), Some(_)) = (self.schema.search, &self.search) {
+            query.push(keyword).push(vector).push(" @@ search.search_query");
+            keyword = " AND ";
+        }
+        if keyset && let Some(cursor) = &self.cursor {
+            query.push(keyword);
+            self.push_keyset(query, cursor);
+            keyword = " AND ";
+        }
+        for filter in &self.filters {
+            query.push(keyword).push(filter.column.sql);
+            keyword = " AND ";
+            match (filter.operator, &filter.value) {
+                (Operator::Null, FilterValue::Bool(true)) => { query.push(" IS NULL"); }
+                (Operator::Null, _) => { query.push(" IS NOT NULL"); }
+                (Operator::Like, FilterValue::Text(value)) => { query.push(" ILIKE ").push_bind(like_pattern(value)); }
+                // Only text is matched against a pattern, anything else matches no row.
+                (Operator::Like, _) => { query.push(" IS NULL AND FALSE"); }
+                (Operator::In, value) => {
+                    query.push(" IN ");
+                    push_value(query, value);
+                }
+                (Operator::Compare(comparison), value) => {
+                    query.push(comparison.sql());
+                    push_value(query, value);
+                }
+            }
+        }
+    }
+
+    // Paging backwards walks the order reversed and flips the page afterwards.
+    pub(super) fn direction(&self, sort: &SortKey) -> SortDirection {
+        match self.cursor.as_ref().is_some_and(|cursor| cursor.backward) {
+            false => sort.direction,
+            true if sort.direction == SortDirection::Asc => SortDirection::Desc,
+            true => SortDirection::Asc,
+        }
+    }
+
+    fn push_order(&self, query: &mut QueryBuilder<'static, Postgres>) {
+        for (index, sort) in self.order.iter().enumerate() {
+            query.push(if index == 0 { " ORDER BY " } else { ", " }).push(sort.column.sql).push(" ").push(self.direction(sort).sql());
+        }
+    }
+
+    // Rows strictly past the cursor in the order, written out key by key:
+    // (k1 past v1) OR (k1 = v1 AND k2 past v2) OR ... NULLs sort last ascending
+    // and first descending, as Postgres does by default.
+    fn push_keyset(&self, query: &mut QueryBuilder<'static, Postgres>, cursor: &Cursor) {
+        query.push("(");
+        for (index, sort) in self.order.iter().enumerate() {
+            query.push(if index == 0 { "(" } else { " OR (" });
+            for (sort, value) in self.order[..index].iter().zip(&cursor.values) {
+                query.push(sort.column.sql);
+                match value {
+                    Some(value) => { query.push(" = "); push_value(query, value); }
+                    None => { query.push(" IS NULL"); }
+                }
+                query.push(" AND ");
+            }
+            match (self.direction(sort), &cursor.values[index]) {
+                (SortDirection::Asc, Some(value)) => {
+                    query.push("(").push(sort.column.sql).push(" > ");
+                    push_value(query, value);
+                    query.push(" OR ").push(sort.column.sql).push(" IS NULL)");
+                }
+                (SortDirection::Asc, None) => { query.push("FALSE"); }
+                (SortDirection::Desc, Some(value)) => {
+                    query.push(sort.column.sql).push(" < ");
+                    push_value(query, value);
+                }
+                (SortDirection::Desc, None) => { query.push(sort.column.sql).push(" IS NOT NULL"); }
+            }
+            query.push(")");
+        }
+        query.push(")");
+    }
+
+    // The selected fields, each under its field name.
+    fn push_fields(&self, query: &mut QueryBuilder<'static, Postgres>) {
+        for (index, column) in self.fields.iter().enumerate() {
+            query.push(if index == 0 { "SELECT " } else { ", " }).push(select_sql(column)).push(format!(" AS \"{}\"", column.name));
+        }
+    }
+
+    // The filtered, ordered page of the selected fields, one JSON object per row.
+    pub async fn fetch_all(&self, pool: &PgPool) -> Result<Vec<Value>, sqlx::Error> {
+        let mut query = QueryBuilder::new("");
+        self.push_fields(&mut query);
+        self.push_from(&mut query);
+        self.push_where(&mut query, false);
+        self.push_order(&mut query);
+        query.push(" LIMIT ").push_bind(self.limit).push(" OFFSET ").push_bind(self.offset);
+
+        let rows = query.build().fetch_all(pool).await?;
+        rows.iter().map(|row| self.row_json(row)).collect()
+    }
+
+    fn row_json(&self, row: &PgRow) -> Result<Value, sqlx::Error> {
+        let mut object = Map::new();
+        for column in row.columns() {
+            let Some(field) = self.schema.column(column.name()) else {
+                continue;
+            };
+            let value = match field.kind {
+                ColumnKind::Integer => json!(row.try_get::<Option<i64>, _>(column.ordinal())?),
+                ColumnKind::Float => json!(row.try_get::<Option<f64>, _>(column.ordinal())?),
+                ColumnKind::Text => json!(row.try_get::<Option<String>, _>(column.ordinal())?),
+                ColumnKind::Timestamp => json!(row.try_get::<Option<NaiveDateTime>, _>(column.ordinal())?),
+            };
+            object.insert(field.name.to_string(), value);
+        }
+        Ok(Value::Object(object))
+    }
+
+    // COUNT(*) of the rows matching the filters, ignoring order and page.
+    fn count(&self) -> QueryBuilder<'static, Postgres> {
+        let mut query = QueryBuilder::new("SELECT COUNT(*)");
+        self.push_from(&mut query);
+        self.push_where(&mut query, false);
+        query
+    }
+
+    // One keyset page of the selected fields with the cursors leading to its neighbours.
+    // The sort keys are selected alongside as cursor_N, one row past the page tells
+    // whether there is more in the direction being walked.
+    pub async fn fetch_page(&self, pool: &PgPool) -> Result<CursorPage<Value>, sqlx::Error> {
+        let mut query = QueryBuilder::new("");
+        self.push_fields(&mut query);
+        for (index, sort) in self.order.iter().enumerate() {
+            query.push(format!(", {} AS cursor_{}", select_sql(sort.column), index));
+        }
+        self.push_from(&mut query);
+        self.push_where(&mut query, true);
+        self.push_order(&mut query);
+        query.push(" LIMIT ").push_bind(self.limit + 1);
+
+        let mut rows = query.build().fetch_all(pool).await?;
+        let more = rows.len() as i64 > self.limit;
+        rows.truncate(self.limit as usize);
+        let backward = self.cursor.as_ref().is_some_and(|cursor| cursor.backward);
+        if backward {
+            rows.reverse();
+        }
+
+        let (first, last) = match (rows.first(), rows.last()) {
+            (Some(first), Some(last)) => (self.cursor_keys(first)?, self.cursor_keys(last)?),
+            _ => return Ok(CursorPage { items: Vec::new(), next_cursor: None, prev_cursor: None }),
+        };
+        let (has_prev, has_next) = if backward { (more, true) } else { (self.cursor.is_some(), more) };
+        Ok(CursorPage {
+            items: rows.iter().map(|row| self.row_json(row)).collect::<Result<Vec<_>, _>>()?,
+            next_cursor: has_next.then(|| Cursor::encode(&self.order, false, &last)),
+            prev_cursor: has_prev.then(|| Cursor::encode(&self.order, true, &first)),
+        })
+    }
+
+    fn cursor_keys(&self, row: &PgRow) -> Result<Vec<Option<FilterValue>>, sqlx::Error> {
+        self.order.iter().enumerate().map(|(index, sort)| {
+            let name = format!("cursor_{}", index);
+            Ok(match sort.column.kind {
+                ColumnKind::Integer => row.try_get::<Option<i64>, _>(name.as_str())?.map(FilterValue::Integer),
+                ColumnKind::Float => row.try_get::<Option<f64>, _>(name.as_str())?.map(FilterValue::Float),
+                ColumnKind::Text => row.try_get::<Option<String>, _>(name.as_str())?.map(FilterValue::Text),
+                ColumnKind::Timestamp => row.try_get::<Option<NaiveDateTime>, _>(name.as_str())?.map(FilterValue::Timestamp),
+            })
+        }).collect()
+    }
+
+    // The number of matching rows, counted or taken from the planner's estimate.
+    pub async fn total(&self, pool: &PgPool, mode: CountMode) -> Result<Option<i64>, sqlx::Error> {
+        match mode {
+            CountMode::None => Ok(None),
+            CountMode::Exact => {
+                let (count,): (i64,) = self.count().build_query_as().fetch_one(pool).await?;
+                Ok(Some(count))
+            }
+            CountMode::Estimate => {
+                let mut query = QueryBuilder::new("EXPLAIN (FORMAT JSON) SELECT 1");
+                self.push_from(&mut query);
+                self.push_where(&mut query, false);
+                let (plan,): (Value,) = query.build_query_as().fetch_one(pool).await?;
+                Ok(Some(plan[0]["Plan"]["Plan Rows"].as_f64().unwrap_or_default() as i64))
+            }
+        }
+    }
+}
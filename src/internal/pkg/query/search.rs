@@ -0,0 +1,82 @@
+use crate::internal::pkg::query::query::Schema;
+use sqlx::postgres::PgPool;
+
+// Most words one search may use.
+const MAX_SEARCH_TERMS: usize = 16;
+
+// One word of a search, lowercased, and whether it matches as a prefix.
+#[derive(Debug)]
+pub struct SearchTerm {
+    pub word: String,
+    pub prefix: bool,
+}
+
+// A full-text search, matched against the search vector of a schema.
+#[derive(Debug)]
+pub struct Search {
+    pub language: String,
+    pub terms: Vec<SearchTerm>,
+}
+
+impl Search {
+    // Splits user input into the words that all have to match, a word ending in * matching
+    // as a prefix. Only letters and digits are kept, so the input is never read as tsquery syntax.
+    pub fn parse(input: &str, language: &str) -> Result<Self, String> {
+        let mut terms = Vec::new();
+        for word in input.split_whitespace() {
+            let prefix = word.ends_with('*');
+            let pieces: Vec<&str> = word.split(|c: char| !c.is_alphanumeric()).filter(|piece| !piece.is_empty()).collect();
+            for (index, piece) in pieces.iter().enumerate() {
+                terms.push(SearchTerm { word: piece.to_lowercase(), prefix: prefix && index + 1 == pieces.len() });
+            }
+        }
+        if terms.is_empty() {
+            return Err("Search query has no words".to_string());
+        }
+        if terms.len() > MAX_SEARCH_TERMS {
+            return Err(format!("Search query has more than {} words", MAX_SEARCH_TERMS));
+        }
+        Ok(Search { language: language.to_string(), terms })
+    }
+
+    // The to_tsquery expression of the search.
+    pub fn query(&self) -> String {
+        self.terms.iter()
+            .map(|term| if term.prefix { format!("'{}':*", term.word) } else { format!("'{}'", term.word) })
+            .collect::<Vec<_>>()
+            .join(" & ")
+    }
+}
+
+// Fails when Postgres has no text search configuration of that name, or when the
+// schema's search vector is generated with another one. Queries and the stored
+// vectors have to be stemmed alike, so a new language needs a migration rebuilding
+// the column.
+pub async fn check_language(pool: &PgPool, schema: &Schema, language: &str) -> Result<(), String> {
+    sqlx::query("SELECT CAST($1 AS regconfig)")
+        .bind(language)
+        .execute(pool)
+        .await
+        .map_err(|err| err.to_string())?;
+    let Some(vector) = schema.search else {
+        return Ok(());
+    };
+
+    let configs: Vec<String> = sqlx::query_scalar(
+        "SELECT DISTINCT config FROM ( \
+            SELECT (regexp_matches(pg_get_expr(d.adbin, d.adrelid), 'to_tsvector\\(''([^'']+)''::regconfig', 'g'))[1] AS config \
+            FROM pg_attrdef d JOIN pg_attribute a ON a.attrelid = d.adrelid AND a.attnum = d.adnum \
+            WHERE d.adrelid = CAST($1 AS regclass) AND a.attname = $2 \
+        ) generated WHERE CAST(config AS regconfig) <> CAST($3 AS regconfig)"
+    )
+    .bind(schema.table)
+    .bind(vector)
+    .bind(language)
+    .fetch_all(pool)
+    .await
+    .map_err(|err| err.to_string())?;
+    if !configs.is_empty() {
+        return Err(format!("{}.{} is generated with {}, not {}", schema.table, vector, configs.join(", "), language));
+    }
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use actix_web::HttpRequest;
+use actix_web::http::header::{self, EntityTag, Header, IfMatch, IfNoneMatch};
+
+// The entity tag of a resource at `version`.
+pub fn entity_tag(version: i32) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+// The versions If-Match allows a write on, None without the header or with `*`.
+// Weak and malformed tags match nothing, so the write fails its precondition.
+pub fn if_match(http_req: &HttpRequest) -> Option<Vec<i32>> {
+    if !http_req.headers().contains_key(header::IF_MATCH) {
+        return None;
+    }
+    match IfMatch::parse(http_req) {
+        Ok(IfMatch::Any) => None,
+        Ok(IfMatch::Items(tags)) => Some(tags.iter().filter(|tag| !tag.weak).filter_map(|tag| tag.tag().parse().ok()).collect()),
+        Err(_) => Some(Vec::new()),
+    }
+}
+
+// Whether If-None-Match names `version`, so the client's copy is still current.
+pub fn not_modified(http_req: &HttpRequest, version: i32) -> bool {
+    if !http_req.headers().contains_key(header::IF_NONE_MATCH) {
+        return false;
+    }
+    match IfNoneMatch::parse(http_req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&entity_tag(version))),
+        Err(_) => false,
+    }
+}
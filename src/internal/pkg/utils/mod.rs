@@ -1 +1,8 @@
-pub mod pagination;
\ No newline at end of file
+pub mod pagination;
+pub mod crypto;
+pub mod etag;
+pub mod request;
+pub mod cache;
+pub mod jwt_keys;
+pub mod time;
+pub mod totp;
\ No newline at end of file
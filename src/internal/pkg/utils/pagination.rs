@@ -1,31 +1,46 @@
-use regex::Regex;
-
 #[derive(Debug)]
 pub struct PaginationRequest {
     pub limit: i64,
     pub page: i64,
-    pub field: String,
+    // Comma separated fields, "-" in front of one sorts it descending.
     pub sort: String,
+    // A bare `sort=asc` or `sort=desc`, the direction of the default sort.
+    pub descending: Option<bool>,
 }
 
 impl PaginationRequest {
-    pub fn new(limit: i64, page: i64, field: &str, sort: &str) -> Self {
-        let re = Regex::new(r"^[a-zA-Z]+$").unwrap();
-        let mut field = field.to_string();
-        
-        if !re.is_match(&field) {
-            field = "created_at".to_string();
-        }
-        
-        let sort = match sort.to_lowercase().as_str() {
-            "asc" => "ASC".to_string(),
-            "desc" => "DESC".to_string(),
-            _ => "ASC".to_string(),
+    // The older `field=name&sort=desc` form is turned into the same sort spec.
+    pub fn new(limit: i64, page: i64, field: Option<&str>, sort: Option<&str>) -> Self {
+        let descending = sort.map(str::trim).and_then(|direction| match direction {
+            _ if direction.eq_ignore_ascii_case("desc") => Some(true),
+            _ if direction.eq_ignore_ascii_case("asc") => Some(false),
+            _ => None,
+        });
+        let (sort, descending) = match (field.map(str::trim).filter(|field| !field.is_empty()), descending) {
+            (Some(field), Some(true)) => (format!("-{}", field), None),
+            (Some(field), _) => (field.to_string(), None),
+            (None, Some(descending)) => (String::new(), Some(descending)),
+            (None, None) => (sort.unwrap_or_default().to_string(), None),
         };
-        
+
         let limit = if limit == 0 { 10 } else { limit };
         let page = if page == 0 { 1 } else { page };
         
-        PaginationRequest { limit, page, field, sort }
+        PaginationRequest { limit, page, sort, descending }
+    }
+
+    // The requested sort, or `default` turned to a bare direction when there is one.
+    pub fn sort_or(&self, default: &str) -> String {
+        if !self.sort.trim().is_empty() {
+            return self.sort.clone();
+        }
+        match self.descending {
+            None => default.to_string(),
+            Some(descending) => default.split(',')
+                .map(|field| field.trim().trim_start_matches(['-', '+']))
+                .map(|name| if descending { format!("-{}", name) } else { name.to_string() })
+                .collect::<Vec<_>>()
+                .join(","),
+        }
     }
 }
@@ -0,0 +1,19 @@
+use crate::config::settings::CONFIG;
+use actix_web::{HttpRequest, http::header};
+
+// Resolve the caller IP. Forwarded / X-Forwarded-For can be set by any client, so
+// they are only honoured when TRUST_PROXY_HEADERS is enabled.
+pub fn client_ip(http_req: &HttpRequest) -> Option<String> {
+    if CONFIG.trust_proxy_headers {
+        return http_req.connection_info().realip_remote_addr().map(|ip| ip.to_string());
+    }
+    http_req.peer_addr().map(|addr| addr.ip().to_string())
+}
+
+pub fn user_agent(http_req: &HttpRequest) -> Option<String> {
+    http_req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
@@ -0,0 +1,9 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+// Accepts RFC 3339, a naive "YYYY-MM-DDTHH:MM:SS" (taken as UTC) or a plain date (midnight UTC).
+pub fn parse_time(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value).map(|time| time.naive_utc()).ok()
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)))
+}
@@ -0,0 +1,121 @@
+use base32::Alphabet;
+use hmac::{Hmac, Mac};
+use rand::{RngCore, rngs::OsRng};
+use sha1::Sha1;
+use url::form_urlencoded;
+
+// RFC 6238 defaults understood by every authenticator app.
+const DIGITS: u32 = 6;
+const PERIOD: u64 = 30;
+// Accept one step of clock drift in each direction.
+const SKEW: i64 = 1;
+const ALPHABET: Alphabet = Alphabet::Rfc4648 { padding: false };
+
+// Generate a 160 bit secret, base32 encoded as authenticator apps expect.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    OsRng.fill_bytes(&mut bytes);
+    base32::encode(ALPHABET, &bytes)
+}
+
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+    let label: String = form_urlencoded::byte_serialize(format!("{}:{}", issuer, account).as_bytes()).collect();
+    let query = form_urlencoded::Serializer::new(String::new())
+        .append_pair("secret", secret)
+        .append_pair("issuer", issuer)
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", &DIGITS.to_string())
+        .append_pair("period", &PERIOD.to_string())
+        .finish();
+    format!("otpauth://totp/{}?{}", label, query)
+}
+
+// HOTP value (RFC 4226) for a single counter.
+fn hotp(key: &[u8], counter: u64) -> Option<u32> {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).ok()?;
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]]) & 0x7fff_ffff;
+    Some(binary % 10u32.pow(DIGITS))
+}
+
+// Check a code against the secret at `unix_time`, returning the matching time step.
+// Callers store the step to refuse replaying the same code.
+pub fn verify(secret: &str, code: &str, unix_time: u64) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let expected: u32 = code.parse().ok()?;
+    let key = base32::decode(ALPHABET, secret)?;
+    let current = (unix_time / PERIOD) as i64;
+    (-SKEW..=SKEW)
+        .map(|offset| current + offset)
+        .filter(|step| *step >= 0)
+        .find(|step| hotp(&key, *step as u64) == Some(expected))
+        .map(|step| step as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The SHA1 key of RFC 6238 appendix B, "12345678901234567890".
+    const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    // The code of the step `offset` steps away from the one of `unix_time`.
+    fn code_at(unix_time: u64, offset: i64) -> String {
+        let key = base32::decode(ALPHABET, SECRET).unwrap();
+        let step = (unix_time / PERIOD) as i64 + offset;
+        format!("{:06}", hotp(&key, step as u64).unwrap())
+    }
+
+    #[test]
+    fn matches_the_rfc_6238_sha1_vectors() {
+        // The last six digits of the eight digit values in appendix B.
+        for (unix_time, code) in [
+            (59, "287082"),
+            (1_111_111_109, "081804"),
+            (1_111_111_111, "050471"),
+            (1_234_567_890, "005924"),
+            (2_000_000_000, "279037"),
+            (20_000_000_000, "353130"),
+        ] {
+            assert_eq!(code_at(unix_time, 0), code, "at {}", unix_time);
+            assert_eq!(verify(SECRET, code, unix_time), Some(unix_time / PERIOD), "at {}", unix_time);
+        }
+    }
+
+    #[test]
+    fn accepts_one_step_of_drift_each_way() {
+        let now = 1_234_567_890;
+        let step = now / PERIOD;
+        assert_eq!(verify(SECRET, &code_at(now, -1), now), Some(step - 1));
+        assert_eq!(verify(SECRET, &code_at(now, 1), now), Some(step + 1));
+        assert_eq!(verify(SECRET, &code_at(now, -2), now), None);
+        assert_eq!(verify(SECRET, &code_at(now, 2), now), None);
+    }
+
+    #[test]
+    fn returns_the_step_of_the_code_so_it_can_be_refused_once_used() {
+        // The same code verifies during the whole window it is valid in, always as
+        // the same step, which the caller records as used.
+        let issued = 1_234_567_890 - 1_234_567_890 % PERIOD;
+        let code = code_at(issued, 0);
+        for unix_time in [issued, issued + PERIOD - 1, issued + PERIOD, issued + 2 * PERIOD - 1] {
+            assert_eq!(verify(SECRET, &code, unix_time), Some(issued / PERIOD));
+        }
+        assert_eq!(verify(SECRET, &code, issued + 2 * PERIOD), None);
+    }
+
+    #[test]
+    fn rejects_malformed_codes() {
+        let now = 1_234_567_890;
+        assert_eq!(verify(SECRET, " 005924 ", now), Some(now / PERIOD));
+        for code in ["", "05924", "0005924", "00592a", "00 924", "-05924"] {
+            assert_eq!(verify(SECRET, code, now), None, "{:?}", code);
+        }
+        assert_eq!(verify("not base32!", "005924", now), None);
+    }
+}
@@ -1,13 +1,51 @@
+#![allow(clippy::module_inception)]
+
 mod config;
 mod internal;
 mod api;
 mod middlewares;
 
+use crate::internal::application::repositories::audit::postgres::PgAuditEventRepository;
+use crate::internal::application::repositories::items::items::ItemRepository;
+use crate::internal::application::repositories::items::postgres::PgItemRepository;
+use crate::internal::application::repositories::users::postgres::PgUserRepository;
+use crate::internal::application::repositories::users::users::UserRepository;
+use crate::internal::application::usecases::audit::audit::Auditor;
+use crate::internal::pkg::database::sql::migrate::{migrate_down, migrate_up, migration_status, run_startup_migrations};
 use crate::internal::pkg::database::sql::postgres::create_pool;
+use crate::internal::pkg::mailer::mailer::create_mailer;
 use crate::api::rest::api::server::start_server;
 use actix_web::web::Data;
 use dotenv::dotenv;
 use env_logger::init;
+use sqlx::{Pool, Postgres};
+use std::io::Error;
+use std::sync::Arc;
+
+const MIGRATE_USAGE: &str = "Usage: migrate <up|down [steps]|status>";
+
+// `migrate up`, `migrate down [steps]` and `migrate status`, run instead of the server.
+async fn migrate_command(pool: &Pool<Postgres>, args: &[String]) -> std::io::Result<()> {
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["up"] => migrate_up(pool).await.map_err(Error::other)?,
+        ["down", steps @ ..] => {
+            let steps = match steps {
+                [] => 1,
+                [steps] => steps.parse().ok().filter(|steps| *steps > 0)
+                    .ok_or_else(|| Error::other(format!("Invalid steps: {}", steps)))?,
+                _ => return Err(Error::other(MIGRATE_USAGE)),
+            };
+            migrate_down(pool, steps).await.map_err(Error::other)?;
+        }
+        ["status"] => {
+            for migration in migration_status(pool).await.map_err(Error::other)? {
+                println!("{:<16}{:<10}{}", migration.version, migration.state.name(), migration.description);
+            }
+        }
+        _ => return Err(Error::other(MIGRATE_USAGE)),
+    }
+    Ok(())
+}
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -18,9 +56,29 @@ async fn main() -> std::io::Result<()> {
     // Initialize Postgres connection pool
     let pool = create_pool().await;
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        return migrate_command(&pool, &args[1..]).await;
+    }
+
+    // Bring the schema up to date, or check it is, before serving requests.
+    run_startup_migrations(&pool).await;
+
+    // Items and users are stored through their repository traits, backed by Postgres.
+    let item_repository: Arc<dyn ItemRepository> = Arc::new(PgItemRepository::new(pool.clone()));
+    let user_repository: Arc<dyn UserRepository> = Arc::new(PgUserRepository::new(pool.clone()));
+    let items_data = Data::from(item_repository);
+    let users_data = Data::from(user_repository.clone());
+
+    // Item and user writes record their audit trail through the Auditor.
+    let auditor_data = Data::new(Auditor::new(Arc::new(PgAuditEventRepository::new(pool.clone())), user_repository));
+
     // Wrap pool in actix_web::Data so it can be shared among handlers.
     let pool_data = Data::new(pool);
 
+    // Mail delivery used by the account recovery flows.
+    let mailer_data = Data::from(create_mailer());
+
     // Start server
-    start_server(pool_data).await
+    start_server(pool_data, mailer_data, items_data, users_data, auditor_data).await
 }
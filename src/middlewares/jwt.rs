@@ -1,6 +1,6 @@
 // use crate::internal::domain::entities::auth::login::Claims;
-// use crate::config::settings::CONFIG;
-// use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm, errors::ErrorKind};
+// use crate::internal::pkg::utils::jwt_keys::JWT_KEYS;
+// use jsonwebtoken::errors::ErrorKind;
 // use std::time::Duration;
 
 // pub async fn jwt_decode(token: &str) -> Result<Claims, String> {
@@ -35,159 +35,111 @@
 //     }
 // }
 
-use crate::internal::constant::status::FAILED_AUTHORIZED;
+use crate::internal::constant::status::{FAILED_AUTHORIZED, FAILED_INTERNAL};
 use crate::internal::domain::entities::response::Response;
+use crate::internal::domain::entities::auth::identity::Identity;
 use crate::internal::domain::entities::auth::login::Claims;
-use crate::config::settings::CONFIG;
-use actix_web::{dev::{Service, ServiceRequest, ServiceResponse, Transform}, Error, HttpMessage, HttpResponse};
-use futures_util::future::{ok, LocalBoxFuture, Ready};
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm, errors::ErrorKind};
-use std::rc::Rc;
+use crate::internal::application::usecases::auth::api_keys::{authenticate_api_key, ApiKeyError};
+use crate::internal::application::usecases::auth::revocation::{ensure_not_revoked, RevocationError};
+use crate::internal::pkg::utils::jwt_keys::JWT_KEYS;
+use actix_web::{dev::ServiceRequest, web, HttpResponse};
+use sqlx::postgres::PgPool;
+use jsonwebtoken::errors::ErrorKind;
 use std::time::Duration;
-use std::task::{Context, Poll};
-
-pub struct JwtMiddleware;
-
-impl<S, B> Transform<S, ServiceRequest> for JwtMiddleware
-where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
-    B: actix_web::body::MessageBody + 'static,
-{
-    type Response = ServiceResponse<actix_web::body::BoxBody>;
-    type Error = Error;
-    type Transform = JwtMiddlewareMiddleware<S>;
-    type InitError = ();
-    type Future = Ready<Result<Self::Transform, Self::InitError>>;
-
-    fn new_transform(&self, service: S) -> Self::Future {
-        ok(JwtMiddlewareMiddleware {
-            service: Rc::new(service),
-        })
-    }
+
+fn unauthorized(desc: String) -> HttpResponse {
+    HttpResponse::Unauthorized().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_AUTHORIZED.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
 }
 
-pub struct JwtMiddlewareMiddleware<S> {
-    service: Rc<S>,
+fn internal_error(desc: String) -> HttpResponse {
+    HttpResponse::InternalServerError().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_INTERNAL.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
 }
 
-impl<S, B> Service<ServiceRequest> for JwtMiddlewareMiddleware<S>
-where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
-    B: actix_web::body::MessageBody + 'static,
-{
-    type Response = ServiceResponse<actix_web::body::BoxBody>;
-    type Error = Error;
-    type Future = LocalBoxFuture<'static, Result<Self::Response, Error>>;
-
-    fn poll_ready(&self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(()))
+// Resolve the caller from `Authorization: Bearer <jwt>`, `Authorization: ApiKey <key>` or `X-API-Key`.
+// Ok(None) means no credentials were sent, Err carries the response to reject the request with.
+pub async fn authenticate(req: &ServiceRequest) -> Result<Option<Identity>, HttpResponse> {
+    let pool = match req.app_data::<web::Data<PgPool>>() {
+        Some(pool) => pool.clone(),
+        None => return Err(internal_error("Database pool is not configured.".to_string())),
+    };
+
+    if let Some(api_key) = req.headers().get("X-API-Key") {
+        return match api_key.to_str() {
+            Ok(api_key) => authenticate_with_api_key(pool.get_ref(), api_key).await,
+            Err(_) => Err(unauthorized("Invalid X-API-Key header.".to_string())),
+        };
+    }
+
+    let auth_header = match req.headers().get("Authorization") {
+        Some(auth_header) => auth_header,
+        None => return Ok(None),
+    };
+
+    let header_value = match auth_header.to_str() {
+        Ok(header_value) => header_value,
+        Err(_) => return Err(unauthorized("Invalid Authorization header.".to_string())),
+    };
+
+    if let Some(api_key) = header_value.strip_prefix("ApiKey ") {
+        return authenticate_with_api_key(pool.get_ref(), api_key).await;
     }
 
-    fn call(&self, req: ServiceRequest) -> Self::Future {
-        let service = Rc::clone(&self.service);
-        Box::pin(async move {
-            // Check for a required header
-            if !req.headers().contains_key("Authorization") {
-                return Ok(req.into_response(
-                    HttpResponse::Unauthorized().json(
-                        Response::<serde_json::Value> {
-                            response_code: FAILED_AUTHORIZED.to_string(),
-                            response_desc: "Authorization is missing.".to_string(),
-                            response_data: None,
-                        }
-                    )
-                ).map_into_boxed_body());
-            }
-
-            if let Some(auth_header) = req.headers().get("Authorization") {
-                match auth_header.to_str() {
-                    Ok(header_value) => {
-                        let token = header_value.strip_prefix("Bearer ").unwrap_or("").to_string();
-                        if token.is_empty() {
-                            return Ok(req.into_response(
-                                HttpResponse::Unauthorized().json(
-                                    Response::<serde_json::Value> {
-                                        response_code: FAILED_AUTHORIZED.to_string(),
-                                        response_desc: "Invalid Bearer token.".to_string(),
-                                        response_data: None,
-                                    }
-                                )
-                            ).map_into_boxed_body());
-                        }
-
-                        let decoding_key = DecodingKey::from_secret(CONFIG.secret_key_jwt.clone().as_ref());
-                        let validation = Validation::new(Algorithm::HS256);
-
-                        let token_data = match decode::<Claims>(&token, &decoding_key, &validation) {
-                            Ok(data) => data,
-                            Err(err) => {
-                                if let ErrorKind::ExpiredSignature = err.kind() {
-                                    return Ok(req.into_response(
-                                        HttpResponse::Unauthorized().json(
-                                            Response::<serde_json::Value> {
-                                                response_code: FAILED_AUTHORIZED.to_string(),
-                                                response_desc: "Token has expired.".to_string(),
-                                                response_data: None,
-                                            }
-                                        )
-                                    ).map_into_boxed_body());
-                                } else if let ErrorKind::InvalidToken = err.kind() {
-                                    return Ok(req.into_response(
-                                        HttpResponse::Unauthorized().json(
-                                            Response::<serde_json::Value> {
-                                                response_code: FAILED_AUTHORIZED.to_string(),
-                                                response_desc: "Token is invalid.".to_string(),
-                                                response_data: None,
-                                            }
-                                        )
-                                    ).map_into_boxed_body());
-                                } else {
-                                    return Ok(req.into_response(
-                                        HttpResponse::Unauthorized().json(
-                                            Response::<serde_json::Value> {
-                                                response_code: FAILED_AUTHORIZED.to_string(),
-                                                response_desc: format!("Token verification failed: {}.", err),
-                                                response_data: None,
-                                            }
-                                        )
-                                    ).map_into_boxed_body());
-                                }
-                            }
-                        };
-
-                        let ctx = Claims {
-                            sub: token_data.claims.sub,
-                            name: token_data.claims.name,
-                            exp: token_data.claims.exp,
-                        };
-                        req.extensions_mut().insert(ctx);
-                    }
-                    Err(_) => {
-                        return Ok(req.into_response(
-                            HttpResponse::Unauthorized().json(
-                                Response::<serde_json::Value> {
-                                    response_code: FAILED_AUTHORIZED.to_string(),
-                                    response_desc: "Invalid Authorization header.".to_string(),
-                                    response_data: None,
-                                }
-                            )
-                        ).map_into_boxed_body());
-                    }
-                }
-            }
-            service.call(req).await.map(ServiceResponse::map_into_boxed_body)
-        })
+    let token = header_value.strip_prefix("Bearer ").unwrap_or("").to_string();
+    if token.is_empty() {
+        return Err(unauthorized("Invalid Bearer token.".to_string()));
+    }
+
+    let token_data = match JWT_KEYS.decode::<Claims>(&token) {
+        Ok(data) => data,
+        Err(err) => {
+            return Err(match err.kind() {
+                ErrorKind::ExpiredSignature => unauthorized("Token has expired.".to_string()),
+                ErrorKind::InvalidToken => unauthorized("Token is invalid.".to_string()),
+                _ => unauthorized(format!("Token verification failed: {}.", err)),
+            });
+        }
+    };
+
+    match ensure_not_revoked(pool.get_ref(), &token_data.claims).await {
+        Ok(()) => Ok(Some(Identity::from_claims(token_data.claims))),
+        Err(RevocationError::Revoked(desc)) => Err(unauthorized(desc.to_string())),
+        Err(RevocationError::DatabaseError(err)) => Err(internal_error(err.to_string())),
+    }
+}
+
+async fn authenticate_with_api_key(pool: &PgPool, api_key: &str) -> Result<Option<Identity>, HttpResponse> {
+    match authenticate_api_key(pool, api_key).await {
+        Ok(identity) => Ok(Some(identity)),
+        Err(ApiKeyError::Invalid(desc)) => Err(unauthorized(desc.to_string())),
+        Err(ApiKeyError::DatabaseError(err)) => Err(internal_error(err.to_string())),
     }
 }
 
 pub fn parse_jwt_exp(exp: &str) -> Option<Duration> {
-    let (num_part, unit_part) = exp.trim().split_at(exp.len() - 1);
+    // Also parses user supplied lifetimes, so guard against empty or non-ASCII input.
+    let exp = exp.trim();
+    if exp.is_empty() || !exp.is_ascii() {
+        return None;
+    }
+    let (num_part, unit_part) = exp.split_at(exp.len() - 1);
     let number: u64 = num_part.parse().ok()?;
     match unit_part {
         "s" => Some(Duration::from_secs(number)),          // seconds
-        "m" => Some(Duration::from_secs(number * 60)),     // minutes
-        "h" => Some(Duration::from_secs(number * 3600)),   // hours
-        "d" => Some(Duration::from_secs(number * 86400)),  // days
+        "m" => number.checked_mul(60).map(Duration::from_secs),     // minutes
+        "h" => number.checked_mul(3600).map(Duration::from_secs),   // hours
+        "d" => number.checked_mul(86400).map(Duration::from_secs),  // days
         _ => None, // invalid format
     }
 }
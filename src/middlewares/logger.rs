@@ -89,12 +89,13 @@ where
             );
 
             // Re-insert body so the handler can use it
-            req.set_payload(actix_web::web::Bytes::from(body_bytes).into());
+            req.set_payload(body_bytes.into());
 
             // Call inner service
-            let res = srv.borrow_mut().call(req).await?;
+            let fut = srv.borrow_mut().call(req);
+            let res = fut.await?;
 
-            let status = res.status().clone();
+            let status = res.status();
             let res_headers = res.headers().clone();
 
             // Buffer the response body to log it
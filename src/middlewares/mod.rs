@@ -1,2 +1,4 @@
 pub mod jwt;
-pub mod logger;
\ No newline at end of file
+pub mod logger;
+pub mod policy;
+pub mod request_id;
\ No newline at end of file
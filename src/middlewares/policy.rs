@@ -0,0 +1,158 @@
+use crate::internal::constant::status::FAILED_AUTHORIZED;
+use crate::internal::domain::entities::response::Response;
+use crate::internal::domain::entities::auth::identity::Identity;
+use crate::middlewares::jwt::authenticate;
+use actix_web::{dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform}, error::InternalError, Error, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use futures_util::future::{ok, ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+
+// Access rule attached to a route, scope or resource.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthPolicy {
+    // Anyone may call, the identity is still resolved when valid credentials are sent.
+    Public,
+    Authenticated,
+    Permission(String),
+}
+
+// Parses "public", "authenticated" or "permission:<name>", e.g. "permission:items:read".
+impl FromStr for AuthPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "public" => Ok(AuthPolicy::Public),
+            "authenticated" => Ok(AuthPolicy::Authenticated),
+            other => match other.strip_prefix("permission:") {
+                Some(permission) if !permission.is_empty() => Ok(AuthPolicy::Permission(permission.to_string())),
+                _ => Err(format!("Invalid auth policy: {}", value)),
+            },
+        }
+    }
+}
+
+fn unauthorized(desc: String) -> HttpResponse {
+    HttpResponse::Unauthorized().json(
+        Response::<serde_json::Value> {
+            response_code: FAILED_AUTHORIZED.to_string(),
+            response_desc: desc,
+            response_data: None,
+        }
+    )
+}
+
+pub struct Policy {
+    policy: Rc<AuthPolicy>,
+}
+
+impl Policy {
+    pub fn new(policy: AuthPolicy) -> Self {
+        Policy { policy: Rc::new(policy) }
+    }
+
+    pub fn authenticated() -> Self {
+        Policy::new(AuthPolicy::Authenticated)
+    }
+
+    pub fn permission(permission: &str) -> Self {
+        Policy::new(AuthPolicy::Permission(permission.to_string()))
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Policy
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: actix_web::body::MessageBody + 'static,
+{
+    type Response = ServiceResponse<actix_web::body::BoxBody>;
+    type Error = Error;
+    type Transform = PolicyMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(PolicyMiddleware {
+            service: Rc::new(service),
+            policy: Rc::clone(&self.policy),
+        })
+    }
+}
+
+pub struct PolicyMiddleware<S> {
+    service: Rc<S>,
+    policy: Rc<AuthPolicy>,
+}
+
+impl<S, B> Service<ServiceRequest> for PolicyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: actix_web::body::MessageBody + 'static,
+{
+    type Response = ServiceResponse<actix_web::body::BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Error>>;
+
+    fn poll_ready(&self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let policy = Rc::clone(&self.policy);
+        Box::pin(async move {
+            // An outer policy may already have resolved the caller.
+            let resolved = req.extensions().get::<Identity>().cloned();
+            let identity = match resolved {
+                Some(identity) => Some(identity),
+                None => match authenticate(&req).await {
+                    Ok(identity) => identity,
+                    Err(_) if *policy == AuthPolicy::Public => None,
+                    Err(resp) => return Ok(req.into_response(resp).map_into_boxed_body()),
+                },
+            };
+
+            match (policy.as_ref(), identity) {
+                (AuthPolicy::Public, None) => {}
+                (_, None) => {
+                    return Ok(req.into_response(
+                        unauthorized("Authorization is missing.".to_string())
+                    ).map_into_boxed_body());
+                }
+                (AuthPolicy::Permission(permission), Some(identity)) if !identity.has_permission(permission) => {
+                    return Ok(req.into_response(
+                        HttpResponse::Forbidden().json(
+                            Response::<serde_json::Value> {
+                                response_code: FAILED_AUTHORIZED.to_string(),
+                                response_desc: format!("Missing permission: {}.", permission),
+                                response_data: None,
+                            }
+                        )
+                    ).map_into_boxed_body());
+                }
+                (_, Some(identity)) => {
+                    req.extensions_mut().insert(identity);
+                }
+            }
+
+            service.call(req).await.map(ServiceResponse::map_into_boxed_body)
+        })
+    }
+}
+
+// Handlers take `Identity` to require a caller, or `Option<Identity>` on public routes.
+impl FromRequest for Identity {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        match req.extensions().get::<Identity>() {
+            Some(identity) => ready(Ok(identity.clone())),
+            None => ready(Err(InternalError::from_response(
+                "Unauthorized",
+                unauthorized("Unauthorized".to_string()),
+            ).into())),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::internal::pkg::utils::crypto::generate_token;
+use actix_web::{dev::{Service, ServiceRequest, ServiceResponse, Transform}, http::header::{HeaderName, HeaderValue}, Error, HttpMessage, HttpRequest};
+use futures_util::future::{ok, LocalBoxFuture, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+#[derive(Debug, Clone)]
+struct RequestIdValue(String);
+
+// Tags every request with an id, taken from X-Request-Id when the caller (or a
+// proxy) sent a sane one, and echoes it back on the response.
+pub struct RequestId;
+
+// The id of the request being handled, for logs and audit events.
+pub fn request_id(http_req: &HttpRequest) -> Option<String> {
+    http_req.extensions().get::<RequestIdValue>().map(|value| value.0.clone())
+}
+
+fn incoming_request_id(req: &ServiceRequest) -> Option<String> {
+    let value = req.headers().get(&REQUEST_ID_HEADER)?.to_str().ok()?.trim();
+    let valid = !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    valid.then(|| value.to_string())
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestIdMiddleware { service: Rc::new(service) })
+    }
+}
+
+pub struct RequestIdMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let id = incoming_request_id(&req).unwrap_or_else(|| generate_token(12));
+        req.extensions_mut().insert(RequestIdValue(id.clone()));
+        Box::pin(async move {
+            let mut res = service.call(req).await?;
+            if let Ok(value) = HeaderValue::from_str(&id) {
+                res.headers_mut().insert(REQUEST_ID_HEADER, value);
+            }
+            Ok(res)
+        })
+    }
+}